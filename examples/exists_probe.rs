@@ -0,0 +1,55 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use nodejs_resolver::{FileSystem, OsFileSystem};
+
+// Compares `FileSystem::exists` with `FileSystem::metadata` on a generated tree.
+// cargo run --release --example exists_probe -- [packages] [rounds]
+
+fn generate(root: &Path, packages: usize) -> Vec<PathBuf> {
+    let mut probes = Vec::with_capacity(packages * 2);
+    for index in 0..packages {
+        let dir = root
+            .join("node_modules")
+            .join(format!("@scope{}", index % 64))
+            .join(format!("pkg{index}"));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("lib").join("index.js"), "").unwrap();
+        probes.push(dir.join("lib").join("index.js"));
+        probes.push(dir.join("lib").join("missing.js"));
+    }
+    probes
+}
+
+fn measure(name: &str, rounds: usize, probes: &[PathBuf], probe: impl Fn(&Path) -> bool) {
+    let start = Instant::now();
+    let mut hits = 0;
+    for _ in 0..rounds {
+        hits += probes.iter().filter(|path| probe(path)).count();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:>8}: {elapsed:?} for {} probes, {hits} hits, {:?} per probe",
+        probes.len() * rounds,
+        elapsed / (probes.len() * rounds) as u32
+    );
+}
+
+fn main() {
+    let packages = std::env::args()
+        .nth(1)
+        .map_or(20_000, |n| n.parse().expect("packages"));
+    let rounds = std::env::args()
+        .nth(2)
+        .map_or(5, |n| n.parse().expect("rounds"));
+    let root = std::env::temp_dir().join(format!("exists-probe-{}", std::process::id()));
+    let probes = generate(&root, packages);
+    let fs = OsFileSystem;
+    measure("metadata", rounds, &probes, |path| {
+        fs.metadata(path).is_ok()
+    });
+    measure("exists", rounds, &probes, |path| fs.exists(path));
+    std::fs::remove_dir_all(root).unwrap();
+}
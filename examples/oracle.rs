@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use nodejs_resolver::oracle;
+
+// Compares the results with `require.resolve` of Node.js on the generated trees.
+// cargo run --example oracle --features oracle -- [first seed] [seeds]
+// The `NODE` environment variable overrides the `node` executable.
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let first: u64 = args.next().map_or(0, |arg| arg.parse().unwrap());
+    let seeds: u64 = args.next().map_or(1, |arg| arg.parse().unwrap());
+    let node = PathBuf::from(std::env::var("NODE").unwrap_or_else(|_| "node".to_string()));
+    let workspace = std::env::temp_dir().join("nodejs_resolver_oracle");
+    std::fs::create_dir_all(&workspace).unwrap();
+
+    let mut failed = 0;
+    for seed in first..first + seeds {
+        for mismatch in oracle::check(seed, &workspace, &node).unwrap() {
+            failed += 1;
+            println!(
+                "seed {}: require.resolve({:?}) from {}\n  node:     {:?}\n  resolver: {:?}",
+                mismatch.seed,
+                mismatch.request,
+                mismatch.dir.display(),
+                mismatch.expected,
+                mismatch.actual
+            );
+        }
+    }
+    println!("{failed} mismatches in {seeds} seeds");
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
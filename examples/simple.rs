@@ -13,6 +13,7 @@ fn main() {
     match resolver.resolve(&path_to_resolve, &request) {
         Ok(ResolveResult::Resource(resource)) => println!("{:?}", resource.join()),
         Ok(ResolveResult::Ignored) => println!("Ignored"),
+        Ok(ResolveResult::OptionalMissing) => println!("Optional peer dependency is missing"),
         Err(err) => println!("{err:?}"),
     }
 }
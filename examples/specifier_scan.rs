@@ -0,0 +1,55 @@
+use std::time::Instant;
+
+use nodejs_resolver::{core, split_protocol};
+
+// Measures the scans of the specifiers, which run for every import of a build.
+// cargo run --release --example specifier_scan -- [specifiers] [rounds]
+
+fn generate(count: usize) -> Vec<String> {
+    (0..count)
+        .map(|index| match index % 6 {
+            0 => format!("./components/button/index{index}.js"),
+            1 => format!("@scope{}/pkg{index}/lib/deep/nested/module.js", index % 64),
+            2 => format!("pkg{index}/dist/esm/index.mjs?raw#section"),
+            3 => format!("node:module{index}"),
+            4 => format!("#internal/utils/helper{index}"),
+            _ => format!("../../very/long/relative/path/to/some/file{index}.ts?query=1"),
+        })
+        .collect()
+}
+
+fn measure(name: &str, rounds: usize, specifiers: &[String], scan: impl Fn(&str) -> usize) {
+    let start = Instant::now();
+    let mut total = 0;
+    for _ in 0..rounds {
+        total += specifiers
+            .iter()
+            .map(|specifier| scan(specifier))
+            .sum::<usize>();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name:>16}: {elapsed:?} for {} specifiers, {total} bytes, {:?} per specifier",
+        specifiers.len() * rounds,
+        elapsed / (specifiers.len() * rounds) as u32
+    );
+}
+
+fn main() {
+    let count = std::env::args()
+        .nth(1)
+        .map_or(100_000, |n| n.parse().expect("specifiers"));
+    let rounds = std::env::args()
+        .nth(2)
+        .map_or(10, |n| n.parse().expect("rounds"));
+    let specifiers = generate(count);
+    measure("parse_request", rounds, &specifiers, |specifier| {
+        core::parse_request(specifier).target().len()
+    });
+    measure("split_module", rounds, &specifiers, |specifier| {
+        core::split_module_request(specifier).0.len()
+    });
+    measure("split_protocol", rounds, &specifiers, |specifier| {
+        split_protocol(specifier).map_or(0, |(protocol, _)| protocol.len())
+    });
+}
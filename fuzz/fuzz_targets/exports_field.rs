@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nodejs_resolver::fuzzing;
+use std::collections::HashSet;
+
+// The first line is the request and the rest is the `exports` field.
+// cargo fuzz run exports_field
+fuzz_target!(|data: &str| {
+    let Some((request, json)) = data.split_once('\n') else {
+        return;
+    };
+    let Ok(root) = serde_json::from_str::<serde_json::Value>(json) else {
+        return;
+    };
+    let condition_names: HashSet<String> = ["import", "require", "node", "browser"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if let Ok(list) = fuzzing::exports_field(&root, request, &condition_names) {
+        for item in list {
+            let _ = fuzzing::check_target(&item);
+        }
+    }
+});
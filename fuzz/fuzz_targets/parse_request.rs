@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nodejs_resolver::fuzzing;
+
+// cargo fuzz run parse_request
+fuzz_target!(|request: &str| {
+    let (target, query, fragment) = fuzzing::parse_request(request);
+    assert!(target.len() + query.len() + fragment.len() <= request.len());
+    if let Some((module_name, path)) = fuzzing::split_request(&target) {
+        assert_eq!(format!("{module_name}{path}"), target);
+        assert!(path.starts_with('/'));
+    }
+    let _ = fuzzing::check_target(request);
+});
@@ -0,0 +1,33 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nodejs_resolver::{Options, Resolver};
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+
+static FIXTURES: Lazy<PathBuf> =
+    Lazy::new(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../tests/fixtures"));
+
+static RESOLVER: Lazy<Resolver> = Lazy::new(|| {
+    Resolver::new(Options {
+        browser_field: true,
+        condition_names: ["import", "require", "node", "browser"]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        ..Default::default()
+    })
+});
+
+// cargo fuzz run resolve_request
+fuzz_target!(|request: &str| {
+    for dir in [
+        "",
+        "exports-field",
+        "exports-field5",
+        "imports-field",
+        "scoped",
+    ] {
+        let _ = RESOLVER.resolve(&FIXTURES.join(dir), request);
+    }
+});
@@ -0,0 +1,279 @@
+use crate::file_system::{FileKind, FileMetadata, FileSystem};
+use dashmap::DashMap;
+use rustc_hash::{FxHashMap, FxHashSet, FxHasher};
+use std::{
+    hash::BuildHasherDefault,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+/// Reads the files inside zip archives, such as the packages in the cache
+/// of Yarn PnP, a path like `cache/pkg.zip/node_modules/pkg/index.js` is
+/// treated as the file `node_modules/pkg/index.js` in `cache/pkg.zip`.
+///
+/// Only the stored (uncompressed) entries can be read, which is how Yarn
+/// writes its cache by default. Zip64 archives are not supported.
+#[derive(Debug)]
+pub struct ZipFileSystem {
+    inner: Arc<dyn FileSystem>,
+    archives: DashMap<PathBuf, CachedArchive, BuildHasherDefault<FxHasher>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedArchive {
+    archive: Arc<Archive>,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug)]
+struct Archive {
+    bytes: Vec<u8>,
+    files: FxHashMap<String, ArchiveFile>,
+    dirs: FxHashSet<String>,
+}
+
+#[derive(Debug)]
+struct ArchiveFile {
+    method: u16,
+    size: usize,
+    local_header_offset: usize,
+}
+
+const STORED: u16 = 0;
+
+impl ZipFileSystem {
+    pub fn new(inner: Arc<dyn FileSystem>) -> Self {
+        Self {
+            inner,
+            archives: Default::default(),
+        }
+    }
+
+    /// Splits `path` into the archive and the path inside it.
+    fn split(&self, path: &Path) -> Option<(PathBuf, String)> {
+        let mut archive = PathBuf::new();
+        let mut components = path.components();
+        while let Some(component) = components.next() {
+            archive.push(component);
+            let Component::Normal(name) = component else {
+                continue;
+            };
+            let is_zip = name
+                .to_str()
+                .map_or(false, |name| name.to_ascii_lowercase().ends_with(".zip"));
+            if is_zip
+                && self
+                    .inner
+                    .metadata(&archive)
+                    .map_or(false, |meta| meta.kind == FileKind::File)
+            {
+                let inside: Vec<&str> = components
+                    .map(|component| component.as_os_str().to_str())
+                    .collect::<Option<_>>()?;
+                return Some((archive, inside.join("/")));
+            }
+        }
+        None
+    }
+
+    fn archive(&self, path: &Path) -> io::Result<CachedArchive> {
+        let modified = self.inner.metadata(path)?.modified;
+        if let Some(cached) = self.archives.get(path) {
+            if cached.modified == modified {
+                return Ok(cached.clone());
+            }
+        }
+        let archive = Archive::parse(self.inner.read(path)?)
+            .map_err(|msg| invalid_data(format!("{msg} in {}", path.display())))?;
+        let cached = CachedArchive {
+            archive: Arc::new(archive),
+            modified,
+        };
+        self.archives.insert(path.to_path_buf(), cached.clone());
+        Ok(cached)
+    }
+}
+
+impl FileSystem for ZipFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let Some((archive_path, inside)) = self.split(path) else {
+            return self.inner.read(path);
+        };
+        let archive = self.archive(&archive_path)?.archive;
+        let file = archive.files.get(&inside).ok_or_else(not_found)?;
+        if file.method != STORED {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "{} is compressed by method {}, only stored entries are supported",
+                    path.display(),
+                    file.method
+                ),
+            ));
+        }
+        archive.data(file).map(<[u8]>::to_vec)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?).map_err(invalid_data)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let Some((archive_path, inside)) = self.split(path) else {
+            return self.inner.metadata(path);
+        };
+        let CachedArchive { archive, modified } = self.archive(&archive_path)?;
+        let kind = if archive.files.contains_key(&inside) {
+            FileKind::File
+        } else if inside.is_empty() || archive.dirs.contains(&inside) {
+            FileKind::Dir
+        } else {
+            return Err(not_found());
+        };
+        Ok(FileMetadata { kind, modified })
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.split(path) {
+            // the symlinks in archives are not supported.
+            Some(_) => Err(io::Error::from(io::ErrorKind::InvalidInput)),
+            None => self.inner.read_link(path),
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        match self.split(path) {
+            Some((archive_path, inside)) => {
+                self.metadata(path)?;
+                let mut real = self.inner.canonicalize(&archive_path)?;
+                real.extend(inside.split('/').filter(|name| !name.is_empty()));
+                Ok(real)
+            }
+            None => self.inner.canonicalize(path),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let Some((archive_path, inside)) = self.split(path) else {
+            return self.inner.read_dir(path);
+        };
+        let archive = self.archive(&archive_path)?.archive;
+        if !inside.is_empty() && !archive.dirs.contains(&inside) {
+            return Err(not_found());
+        }
+        let prefix = if inside.is_empty() {
+            inside
+        } else {
+            format!("{inside}/")
+        };
+        let mut children: Vec<PathBuf> = archive
+            .files
+            .keys()
+            .chain(archive.dirs.iter())
+            .filter_map(|name| name.strip_prefix(&prefix))
+            .filter(|name| !name.is_empty() && !name.contains('/'))
+            .map(|name| path.join(name))
+            .collect();
+        children.sort();
+        children.dedup();
+        Ok(children)
+    }
+}
+
+impl Archive {
+    fn parse(bytes: Vec<u8>) -> Result<Self, &'static str> {
+        const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+        const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+
+        // the comment at the end of archive is at most `u16::MAX` bytes.
+        let min_start = bytes.len().saturating_sub(22 + u16::MAX as usize);
+        let end = (min_start..=bytes.len().saturating_sub(22))
+            .rev()
+            .find(|&offset| read_u32(&bytes, offset) == Some(END_OF_CENTRAL_DIRECTORY))
+            .ok_or("Can't find the end of central directory")?;
+        let count = read_u16(&bytes, end + 10).ok_or("Truncated archive")?;
+        let mut offset = read_u32(&bytes, end + 16).ok_or("Truncated archive")? as usize;
+        if count == u16::MAX || offset == u32::MAX as usize {
+            return Err("Zip64 is not supported");
+        }
+
+        let mut files = FxHashMap::default();
+        let mut dirs = FxHashSet::default();
+        for _ in 0..count {
+            if read_u32(&bytes, offset) != Some(CENTRAL_DIRECTORY_HEADER) {
+                return Err("Invalid central directory");
+            }
+            let field = |at: usize| read_u16(&bytes, offset + at).ok_or("Truncated archive");
+            let method = field(10)?;
+            let name_len = field(28)? as usize;
+            let extra_len = field(30)? as usize;
+            let comment_len = field(32)? as usize;
+            let size = read_u32(&bytes, offset + 20).ok_or("Truncated archive")? as usize;
+            let local_header_offset =
+                read_u32(&bytes, offset + 42).ok_or("Truncated archive")? as usize;
+            let name = bytes
+                .get(offset + 46..offset + 46 + name_len)
+                .ok_or("Truncated archive")?;
+            let name = std::str::from_utf8(name).map_err(|_| "Invalid file name")?;
+            offset += 46 + name_len + extra_len + comment_len;
+
+            // the parent directories might not have their own entries.
+            let mut parent = name.trim_end_matches('/');
+            while let Some(index) = parent.rfind('/') {
+                parent = &parent[..index];
+                dirs.insert(parent.to_string());
+            }
+            if let Some(dir) = name.strip_suffix('/') {
+                dirs.insert(dir.to_string());
+            } else {
+                files.insert(
+                    name.to_string(),
+                    ArchiveFile {
+                        method,
+                        size,
+                        local_header_offset,
+                    },
+                );
+            }
+        }
+
+        Ok(Self { bytes, files, dirs })
+    }
+
+    fn data(&self, file: &ArchiveFile) -> io::Result<&[u8]> {
+        const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+        let offset = file.local_header_offset;
+        if read_u32(&self.bytes, offset) != Some(LOCAL_FILE_HEADER) {
+            return Err(invalid_data("Invalid local file header"));
+        }
+        let name_len = read_u16(&self.bytes, offset + 26).unwrap_or_default() as usize;
+        let extra_len = read_u16(&self.bytes, offset + 28).unwrap_or_default() as usize;
+        let start = offset + 30 + name_len + extra_len;
+        self.bytes
+            .get(start..start + file.size)
+            .ok_or_else(|| invalid_data("Truncated archive"))
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn not_found() -> io::Error {
+    io::Error::from(io::ErrorKind::NotFound)
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
@@ -0,0 +1,54 @@
+use std::path::{Path, PathBuf};
+
+/// The buffers which are kept for the next resolution, the nested probes
+/// rarely hold more at the same time.
+const KEPT: usize = 8;
+
+/// The buffers of the candidate paths, such as `./a` with the extensions
+/// appended, which are taken by the probes and given back once they are
+/// done, so probing allocates nothing once the buffers are warm. It lives
+/// in `Context`, so it is reused by the next resolutions of the context,
+/// see `ResolverPool`.
+#[derive(Debug, Default)]
+pub struct PathArena {
+    free: Vec<PathBuf>,
+}
+
+impl PathArena {
+    /// A buffer holding `path` with the room for `extra` bytes appended,
+    /// and whether it was allocated rather than reused.
+    pub fn alloc(&mut self, path: &Path, extra: usize) -> (PathBuf, bool) {
+        let len = path.as_os_str().len() + extra;
+        let mut buf = self.free.pop().unwrap_or_default().into_os_string();
+        buf.clear();
+        let allocated = buf.capacity() < len;
+        buf.reserve(len);
+        buf.push(path.as_os_str());
+        (PathBuf::from(buf), allocated)
+    }
+
+    /// Gives `buf` back for the next probes.
+    pub fn free(&mut self, buf: PathBuf) {
+        self.free.push(buf);
+    }
+
+    /// Drops the buffers which are more than the next resolution needs.
+    pub fn reset(&mut self) {
+        self.free.truncate(KEPT);
+    }
+}
+
+#[test]
+fn path_arena_test() {
+    let mut arena = PathArena::default();
+    let (buf, allocated) = arena.alloc(Path::new("/a/b"), 4);
+    assert_eq!(buf, Path::new("/a/b"));
+    assert!(allocated);
+    arena.free(buf);
+    let (buf, allocated) = arena.alloc(Path::new("/c"), 4);
+    assert_eq!(buf, Path::new("/c"));
+    assert!(!allocated);
+    arena.free(buf);
+    let (_, allocated) = arena.alloc(Path::new("/a/much/longer/path"), 4);
+    assert!(allocated);
+}
@@ -0,0 +1,70 @@
+use crate::{
+    description::DescriptionData, info::NormalizedPath, kind::PathKind, Error, RResult, Resolver,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+impl Resolver {
+    /// Returns the script of the executable `bin_name` declared by the `bin`
+    /// field of `package`, which is a package name looked up from `path`,
+    /// or a directory relative to `path`.
+    ///
+    /// The string form of `bin` declares the executable named after the
+    /// package, without its scope.
+    pub fn resolve_bin(&self, path: &Path, package: &str, bin_name: &str) -> RResult<PathBuf> {
+        let pkg_info = match Self::parse(package).kind() {
+            PathKind::Normal => self.find_package(path, package),
+            _ => {
+                let dir = NormalizedPath::new(path.join(package));
+                self.issuer_pkg_info(dir.as_ref())
+                    .filter(|pkg_info| pkg_info.dir().as_ref() == dir.as_ref())
+            }
+        }
+        .ok_or_else(|| {
+            Error::UnexpectedValue(format!(
+                "Can't find package {package} in {}",
+                path.display()
+            ))
+        })?;
+        let description_path = pkg_info.dir().as_ref().join(&self.options.description_file);
+        let script = match pkg_info.data().raw().get("bin") {
+            Some(serde_json::Value::String(script)) => pkg_info
+                .data()
+                .name()
+                .map(|name| name.rsplit('/').next().unwrap_or(name))
+                .filter(|name| *name == bin_name)
+                .map(|_| script.as_str()),
+            Some(serde_json::Value::Object(map)) => map.get(bin_name).and_then(|v| v.as_str()),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            Error::UnexpectedValue(format!(
+                "Can't find bin {bin_name} in {}",
+                description_path.display()
+            ))
+        })?;
+        let script = NormalizedPath::new(pkg_info.dir().as_ref().join(script));
+        if !self.load_entry(script.as_ref()).is_file() {
+            return Err(Error::UnexpectedValue(format!(
+                "Bin {bin_name} is mapped to the missing file {} in {}",
+                script.as_ref().display(),
+                description_path.display()
+            )));
+        }
+        Ok(self.output_path(script.as_ref().to_path_buf()))
+    }
+
+    /// The package named `name` in the `modules` directories of `path`.
+    pub(crate) fn find_package(&self, path: &Path, name: &str) -> Option<Arc<DescriptionData>> {
+        self.modules_paths(path, name)?
+            .into_iter()
+            .filter(|modules| modules.exists)
+            .find_map(|modules| {
+                let dir = modules.path.join(name);
+                let pkg_info = self.issuer_pkg_info(&dir)?;
+                (pkg_info.dir().as_ref() == dir).then_some(pkg_info)
+            })
+    }
+}
@@ -1,11 +1,75 @@
 use crate::entry::Entry;
+use crate::file_system::FileSystem;
 use crate::fs::CachedFS;
+use crate::Generation;
 use rustc_hash::FxHasher;
-use std::{hash::BuildHasherDefault, path::Path, sync::Arc};
+use std::{
+    hash::BuildHasherDefault,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+/// The entries keyed by the normalized paths.
+pub type Entries = dashmap::DashMap<Box<Path>, Arc<Entry>, BuildHasherDefault<FxHasher>>;
 
 #[derive(Debug, Default)]
 pub struct Cache {
     pub fs: CachedFS,
     /// File entries keyed by normalized paths
-    pub entries: dashmap::DashMap<Box<Path>, Arc<Entry>, BuildHasherDefault<FxHasher>>,
+    pub entries: Entries,
+    /// The current generation, see `Resolver::gc`.
+    generation: AtomicU64,
+    /// The fields of the description files kept for all the resolvers
+    /// sharing this cache, see `DescriptionRetention::ResolutionFields`.
+    retained_fields: Mutex<Vec<String>>,
+}
+
+impl Cache {
+    /// Create a cache which reads files through `file_system`.
+    pub fn with_file_system(file_system: Arc<dyn FileSystem>) -> Self {
+        Self {
+            fs: CachedFS::new(file_system),
+            entries: Default::default(),
+            generation: Default::default(),
+            retained_fields: Default::default(),
+        }
+    }
+
+    pub fn generation(&self) -> Generation {
+        Generation(self.generation.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn next_generation(&self) -> Generation {
+        Generation(self.generation.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    /// Adds `fields` to the retained fields of the description files. The
+    /// cached descriptions were trimmed without the new ones, such as the
+    /// `main_fields` of another resolver, so they are read again.
+    pub(crate) fn retain_fields(&self, fields: &[&str]) {
+        let mut retained = self
+            .retained_fields
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let len = retained.len();
+        for field in fields {
+            if !retained.iter().any(|retained| retained == field) {
+                retained.push(field.to_string());
+            }
+        }
+        if retained.len() != len {
+            self.fs.clear_descriptions();
+            self.entries.clear();
+        }
+    }
+
+    pub(crate) fn retained_fields(&self) -> Vec<String> {
+        self.retained_fields
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
 }
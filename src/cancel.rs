@@ -0,0 +1,61 @@
+use crate::{dir_resolver::DirResolver, RResult, ResolveResult, Resolver, Resource};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Aborts the resolutions which hold it, such as the resolutions of a build
+/// superseded by a rebuild. It is cheap to clone, and the clones cancel
+/// each other.
+///
+/// The cancelled resolutions return `Error::Cancelled` at the next probe.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Resolver {
+    /// The same as `Resolver::resolve`, but it is aborted once `token` is cancelled.
+    pub fn resolve_cancellable(
+        &self,
+        path: &Path,
+        request: &str,
+        token: &CancelToken,
+    ) -> RResult<ResolveResult<Resource>> {
+        self.resolve_in(path, request, None, None, Some(token))
+    }
+}
+
+impl<'a> DirResolver<'a> {
+    /// The same as `DirResolver::resolve`, but it is aborted once `token` is cancelled.
+    pub fn resolve_cancellable(
+        &self,
+        request: &str,
+        token: &CancelToken,
+    ) -> RResult<ResolveResult<Resource>> {
+        self.resolver.resolve_in(
+            &self.context.path,
+            request,
+            Some(&self.context),
+            None,
+            Some(token),
+        )
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{Error, RResult, ResolveResult, Resource};
+use dashmap::{mapref::entry::Entry, DashMap};
+use rustc_hash::FxHasher;
+use std::{
+    hash::BuildHasherDefault,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex},
+};
+
+/// The result of a resolution shared with the waiters, `None` if it can't
+/// be shared, such as a panic or an error of parsing JSON, then the waiters
+/// resolve by themselves.
+type Shared = Option<RResult<ResolveResult<Resource>>>;
+
+#[derive(Debug, Default)]
+struct Flight {
+    /// `None` until the resolution is finished.
+    result: Mutex<Option<Shared>>,
+    finished: Condvar,
+}
+
+/// The resolutions in progress, keyed by the directory and the request
+/// before they are rewritten, see `Options::coalesce_requests`.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    flights: DashMap<(PathBuf, String), Arc<Flight>, BuildHasherDefault<FxHasher>>,
+}
+
+/// Finishes the flight when the resolution returns or panics, so the
+/// waiters never hang.
+struct Landing<'a> {
+    in_flight: &'a InFlight,
+    key: (PathBuf, String),
+    flight: Arc<Flight>,
+    result: Shared,
+}
+
+impl Drop for Landing<'_> {
+    fn drop(&mut self) {
+        self.in_flight.flights.remove(&self.key);
+        if let Ok(mut result) = self.flight.result.lock() {
+            *result = Some(self.result.take());
+        }
+        self.flight.finished.notify_all();
+    }
+}
+
+impl InFlight {
+    /// Calls `resolve` if there is no identical resolution in progress,
+    /// otherwise waits for it and returns its result.
+    ///
+    /// Returns `true` with the result if it was shared.
+    pub(crate) fn coalesce(
+        &self,
+        path: &Path,
+        request: &str,
+        resolve: impl FnOnce() -> RResult<ResolveResult<Resource>>,
+    ) -> (RResult<ResolveResult<Resource>>, bool) {
+        let key = (path.to_path_buf(), request.to_string());
+        let flight = match self.flights.entry(key.clone()) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let flight = Arc::new(Flight::default());
+                entry.insert(flight.clone());
+                let mut landing = Landing {
+                    in_flight: self,
+                    key,
+                    flight,
+                    result: None,
+                };
+                let result = resolve();
+                landing.result = share(&result);
+                return (result, false);
+            }
+        };
+        match wait(&flight) {
+            Some(result) => (result, true),
+            None => (resolve(), false),
+        }
+    }
+}
+
+fn wait(flight: &Flight) -> Shared {
+    let mut result = flight.result.lock().ok()?;
+    while result.is_none() {
+        result = flight.finished.wait(result).ok()?;
+    }
+    result.as_ref()?.as_ref().and_then(share)
+}
+
+fn share(result: &RResult<ResolveResult<Resource>>) -> Shared {
+    match result {
+        Ok(resolved) => Some(Ok(resolved.clone())),
+        // the waiters may not be cancelled.
+        Err(Error::Cancelled) => None,
+        Err(error) => error.try_clone().map(Err),
+    }
+}
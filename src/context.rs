@@ -1,8 +1,65 @@
+use crate::{
+    arena::PathArena, description::DescriptionData, entry::Entry, CancelToken, Error, Info,
+    ModulesPath, ResultKind, Stage, Symlink,
+};
+use rustc_hash::FxHashMap;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 #[derive(Debug)]
 pub struct Context {
     pub depth: Depth,
     pub fully_specified: Bool,
     pub resolve_to_context: Bool,
+    /// The description of the package which issued the request.
+    pub issuer_pkg_info: Option<Arc<DescriptionData>>,
+    /// `(outDir, rootDir)` of the projects referenced by tsconfig.
+    pub source_dirs: Vec<(PathBuf, PathBuf)>,
+    /// The main field which the result was reached through.
+    pub main_field: Option<String>,
+    /// The innermost stage which mapped the request and the matched key,
+    /// see `Provenance`.
+    pub stage: Option<(Stage, String)>,
+    /// The extension which was appended to find the result.
+    pub extension: Option<String>,
+    /// The segments of the result which are symlinks, before the symlinks
+    /// are resolved, see `Resource::symlink_chain`.
+    pub symlink_chain: Vec<Symlink>,
+    /// Whether the request is being looked up in the ancestors
+    /// for the folders in `modules`.
+    pub finding_up_modules: Bool,
+    /// Whether the main fields or main files of a directory are being resolved.
+    pub probing_dir: Bool,
+    /// The modules directories which the module requests were looked up
+    /// in, in order, see `Error::NotFound`.
+    pub modules_dirs: Vec<Arc<Entry>>,
+    /// Whether the lookup of `modules` was stopped by `Options::stop_at`.
+    pub stopped_at_boundary: bool,
+    /// Whether the request was split by the host, the fragment is not tried
+    /// as a part of the path then, see `Resolver::resolve_request`.
+    pub split_by_host: bool,
+    /// What the caller needs from the result, see `Resolver::resolve_with_kind`.
+    pub result_kind: ResultKind,
+    /// Aborts the resolution once it is cancelled.
+    pub cancel: Option<CancelToken>,
+    /// The start of the resolution and `Options::timeout`.
+    pub timeout: Option<(Instant, Duration)>,
+    /// The sub-resolutions which were not resolved, keyed by the request and
+    /// `Context::flags`, so the requests reached again through the fallbacks
+    /// of the aliases and the `exports` are not probed again.
+    pub visited: FxHashMap<(Info, u8), Visited>,
+    /// The buffers of the candidate paths, see `PathArena`.
+    pub paths: PathArena,
+}
+
+/// The outcome of a sub-resolution which was not resolved.
+#[derive(Debug, Clone)]
+pub enum Visited {
+    Failed(Info),
+    Resolving(Info),
 }
 
 impl Context {
@@ -11,7 +68,86 @@ impl Context {
             depth: Depth::new(),
             fully_specified: Bool(fully_specified),
             resolve_to_context: Bool(resolve_to_context),
+            issuer_pkg_info: None,
+            source_dirs: vec![],
+            main_field: None,
+            stage: None,
+            extension: None,
+            symlink_chain: vec![],
+            finding_up_modules: Bool(false),
+            probing_dir: Bool(false),
+            modules_dirs: vec![],
+            stopped_at_boundary: false,
+            split_by_host: false,
+            result_kind: ResultKind::FullMetadata,
+            cancel: None,
+            timeout: None,
+            visited: Default::default(),
+            paths: Default::default(),
+        }
+    }
+
+    /// Same as `Context::new`, but keeps the allocations for the next resolution.
+    pub fn reset(&mut self, fully_specified: bool, resolve_to_context: bool) {
+        self.depth = Depth::new();
+        self.fully_specified.set(fully_specified);
+        self.resolve_to_context.set(resolve_to_context);
+        self.issuer_pkg_info = None;
+        self.source_dirs.clear();
+        self.main_field = None;
+        self.stage = None;
+        self.extension = None;
+        self.symlink_chain.clear();
+        self.finding_up_modules.set(false);
+        self.probing_dir.set(false);
+        self.modules_dirs.clear();
+        self.stopped_at_boundary = false;
+        self.split_by_host = false;
+        self.result_kind = ResultKind::FullMetadata;
+        self.cancel = None;
+        self.timeout = None;
+        self.visited.clear();
+        self.paths.reset();
+    }
+
+    /// The flags which change how a request is resolved.
+    pub fn flags(&self) -> u8 {
+        u8::from(self.fully_specified.get())
+            | u8::from(self.resolve_to_context.get()) << 1
+            | u8::from(self.finding_up_modules.get()) << 2
+            | u8::from(self.probing_dir.get()) << 3
+    }
+
+    /// The directories of `modules_dirs`, without the duplicates.
+    pub fn take_modules_paths(&mut self) -> Vec<ModulesPath> {
+        let mut paths: Vec<ModulesPath> = vec![];
+        for entry in self.modules_dirs.drain(..) {
+            if paths.iter().all(|item| item.path != entry.path()) {
+                paths.push(ModulesPath {
+                    path: entry.path().to_path_buf(),
+                    exists: entry.is_dir(),
+                });
+            }
+        }
+        paths
+    }
+
+    /// Returns the error if the resolution is cancelled or timed out,
+    /// `probe` is the path being resolved.
+    pub fn interrupted(&self, probe: &Path) -> Option<Error> {
+        if self
+            .cancel
+            .as_ref()
+            .map_or(false, CancelToken::is_cancelled)
+        {
+            return Some(Error::Cancelled);
         }
+        let (start, timeout) = self.timeout?;
+        let elapsed = start.elapsed();
+        (elapsed > timeout).then(|| Error::Timeout {
+            elapsed,
+            last_probe: probe.into(),
+        })
     }
 }
 
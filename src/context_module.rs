@@ -0,0 +1,100 @@
+use crate::{walk, Context, Error, Info, RResult, ResolveResult, Resolver, State};
+use std::path::{Path, PathBuf};
+
+/// A predicate on the path relative to the context directory, such as
+/// `./dir/a.js`, it is usually a regular expression of the host.
+pub type ContextFilter<'a> = &'a dyn Fn(&str) -> bool;
+
+/// Describes the files of a context module, such as `require(`./locale/${name}`)`.
+pub struct ContextOptions<'a> {
+    /// Whether to walk the subdirectories.
+    /// Default is `true`.
+    pub recursive: bool,
+    /// The files which are included.
+    pub filter: ContextFilter<'a>,
+    /// The files and directories which are skipped, even if they pass `filter`.
+    /// Default is `None`.
+    pub exclude: Option<ContextFilter<'a>>,
+}
+
+impl<'a> ContextOptions<'a> {
+    pub fn new(filter: ContextFilter<'a>) -> Self {
+        Self {
+            recursive: true,
+            filter,
+            exclude: None,
+        }
+    }
+}
+
+impl Resolver {
+    /// Resolves `request` to a directory as `resolve_to_context` does, and
+    /// returns the sorted files under it which match `options`.
+    ///
+    /// It is empty if the directory is ignored, such as by the `browser` field.
+    pub fn resolve_context(
+        &self,
+        path: &Path,
+        request: &str,
+        options: &ContextOptions,
+    ) -> RResult<Vec<PathBuf>> {
+        let info = Info::new(path, Self::parse(request));
+        let mut context = Context::new(self.options.fully_specified, true);
+        let dir = match self._resolve(info, &mut context) {
+            State::Success(ResolveResult::Resource(info)) => info.to_resolved_path().to_path_buf(),
+            State::Success(ResolveResult::Ignored | ResolveResult::OptionalMissing) => {
+                return Ok(vec![])
+            }
+            State::Error(error) => return Err(error),
+            State::Resolving(_) | State::Failed(_) => return Err(Error::ResolveFailedTag),
+        };
+        let mut files = vec![];
+        self.walk_context(&dir, &dir, options, &mut vec![], &mut files)?;
+        let mut files: Vec<PathBuf> = files
+            .into_iter()
+            .map(|file| self.output_path(file))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// `ancestors` are the real paths of the directories being walked, see
+    /// `walk::enter`.
+    fn walk_context(
+        &self,
+        root: &Path,
+        dir: &Path,
+        options: &ContextOptions,
+        ancestors: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> RResult<()> {
+        let fs = self.cache.fs.file_system();
+        let Some(real) = walk::enter(fs.as_ref(), ancestors, dir) else {
+            return Ok(());
+        };
+        ancestors.push(real);
+        for child in fs.read_dir(dir)? {
+            let Ok(relative) = child.strip_prefix(root) else {
+                continue;
+            };
+            let relative = relative.display().to_string().replace('\\', "/");
+            if self.options.walk_exclude.is_excluded(&relative) {
+                continue;
+            }
+            let relative = format!("./{relative}");
+            if options.exclude.map_or(false, |exclude| exclude(&relative)) {
+                continue;
+            }
+            let entry = self.load_entry(&child);
+            if entry.is_dir() {
+                if options.recursive {
+                    self.walk_context(root, &child, options, ancestors, files)?;
+                }
+            } else if entry.is_file() && (options.filter)(&relative) {
+                files.push(child);
+            }
+        }
+        ancestors.pop();
+        Ok(())
+    }
+}
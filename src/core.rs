@@ -0,0 +1,131 @@
+//! The parts of the resolution which do no IO, such as parsing the
+//! requests, matching the aliases and evaluating `exports` and `imports`.
+//!
+//! They run on the supplied data rather than a file system, so analysis
+//! tools can evaluate the resolution logic without a `Resolver`.
+//!
+//! ```rust
+//! use nodejs_resolver::core::{self, PathKind};
+//! use nodejs_resolver::AliasMap;
+//!
+//! let request = core::parse_request("@scope/pkg/a.js?raw#top");
+//! assert_eq!(request.target(), "@scope/pkg/a.js");
+//! assert_eq!(request.kind(), PathKind::Normal);
+//! assert_eq!(
+//!     core::split_module_request(request.target()),
+//!     ("@scope/pkg", Some(String::from("./a.js")))
+//! );
+//!
+//! let alias = vec![(String::from("@"), vec![AliasMap::Target(String::from("./src"))])];
+//! let matched = core::match_alias(&alias, "@/a").next().unwrap();
+//! assert_eq!(matched.apply("./src"), Some(String::from("./src/a")));
+//! ```
+
+use crate::{resolve::split_slash_from_request, AliasMap, RResult, Resolver};
+use std::collections::HashSet;
+
+pub use crate::kind::PathKind;
+pub use crate::map::{ExportsField, Field, ImportsField};
+pub use crate::parse::Request;
+
+/// Splits `request` into the target, the query and the fragment, such as
+/// `./a.js?raw#top`, and classifies the target.
+#[must_use]
+pub fn parse_request(request: &str) -> Request {
+    Resolver::parse(request)
+}
+
+/// Classifies the target of a request, such as `./a`, `/a`, `C:\a`, `#a`
+/// and `a`.
+#[must_use]
+pub fn path_kind(target: &str) -> PathKind {
+    Resolver::get_target_kind(target)
+}
+
+/// Splits the target of a module request into the name of the package and
+/// the subpath relative to it, such as `@scope/pkg/a` into `@scope/pkg` and
+/// `./a`, the subpath is `None` if it is the package itself.
+#[must_use]
+pub fn split_module_request(target: &str) -> (&str, Option<String>) {
+    match split_slash_from_request(target) {
+        Some(index) => (&target[..index], Some(format!(".{}", &target[index..]))),
+        None => (target, None),
+    }
+}
+
+/// An item of `Options::alias` which matched a target, see `match_alias`.
+#[derive(Debug, Clone, Copy)]
+pub struct AliasMatch<'a> {
+    /// The key in the options, such as `a$`.
+    pub from: &'a str,
+    /// The part of the target which is replaced, `from` without `$`.
+    pub key: &'a str,
+    pub target: &'a str,
+    pub to: &'a [AliasMap],
+}
+
+impl AliasMatch<'_> {
+    /// Replaces the key with `to`, it is `None` if the target already
+    /// starts with `to`, which would alias the target forever.
+    #[must_use]
+    pub fn apply(&self, to: &str) -> Option<String> {
+        (!self.target.starts_with(to)).then(|| self.target.replacen(self.key, to, 1))
+    }
+}
+
+/// The items of `alias` which match `target`, in order. The keys ending
+/// with `$` only match the target exactly, the others match the target and
+/// the paths in it, such as `a` matches `a` and `a/b` but not `ab`.
+pub fn match_alias<'a>(
+    alias: &'a [(String, Vec<AliasMap>)],
+    target: &'a str,
+) -> impl Iterator<Item = AliasMatch<'a>> {
+    alias.iter().filter_map(move |(from, to)| {
+        let key = match from.strip_suffix('$') {
+            Some(key) => (target == key).then_some(key)?,
+            None => target
+                .strip_prefix(from.as_str())
+                .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+                .map(|_| from.as_str())?,
+        };
+        Some(AliasMatch {
+            from,
+            key,
+            target,
+            to,
+        })
+    })
+}
+
+/// Evaluates the `exports` field of a package, which is `tree`, for
+/// `subpath`, such as `.` or `./feature`, under `conditions`, such as
+/// `["node", "require"]`.
+///
+/// Returns the targets in the order of the fallbacks, such as
+/// `["./feature.js"]`, they are empty if `subpath` is not exported. The
+/// targets are not checked against the package directory, see
+/// `Field::check_target`. It fails if `tree` or `subpath` is invalid.
+pub fn evaluate_exports(
+    tree: &serde_json::Value,
+    subpath: &str,
+    conditions: &[&str],
+) -> RResult<Vec<String>> {
+    ExportsField::field_process(tree, subpath, &condition_set(conditions))
+}
+
+/// Same as `evaluate_exports`, but for the `imports` field and a specifier
+/// such as `#internal`, the targets can be the other packages.
+pub fn evaluate_imports(
+    tree: &serde_json::Value,
+    specifier: &str,
+    conditions: &[&str],
+) -> RResult<Vec<String>> {
+    ImportsField::field_process(tree, specifier, &condition_set(conditions))
+}
+
+fn condition_set(conditions: &[&str]) -> HashSet<String> {
+    conditions
+        .iter()
+        .map(|condition| condition.to_string())
+        .collect()
+}
@@ -0,0 +1,353 @@
+use crate::{
+    Algorithm, AliasMap, Cache, EnforceExtension, Error, FileKind, FileMetadata, FileSystem,
+    Options, RResult, ResolveResult, Resolver,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A resolution captured with the files it touched, so it can be attached
+/// to a bug report and replayed without the project, see
+/// `Resolver::dump_debug_bundle`.
+///
+/// Only the data options are kept, see `DebugBundle::options`, and the
+/// symlinks are recorded as the files and the directories they point to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugBundle {
+    /// The directory which the request was issued from.
+    pub path: PathBuf,
+    pub request: String,
+    pub options: Value,
+    /// The files which exist, the contents are only kept for the files
+    /// which were read, such as the description files, otherwise they are
+    /// empty.
+    pub files: BTreeMap<PathBuf, String>,
+    pub dirs: BTreeSet<PathBuf>,
+    /// The trace of the resolution, it is empty without the `trace` feature.
+    pub trace: Vec<String>,
+    /// The resolved path, `None` if it is ignored or an optional peer which
+    /// is missing, or the message of the error.
+    pub result: Result<Option<PathBuf>, String>,
+}
+
+/// Records the files and the directories which the resolution touched.
+#[derive(Debug)]
+struct Recorder {
+    inner: Arc<dyn FileSystem>,
+    files: Mutex<BTreeMap<PathBuf, String>>,
+    dirs: Mutex<BTreeSet<PathBuf>>,
+}
+
+impl Recorder {
+    fn record_file(&self, path: &Path, content: Option<String>) {
+        let mut files = self.files.lock().unwrap_or_else(|error| error.into_inner());
+        let recorded = files.entry(path.to_path_buf()).or_default();
+        if let Some(content) = content {
+            *recorded = content;
+        }
+    }
+
+    fn record_dir(&self, path: &Path) {
+        let mut dirs = self.dirs.lock().unwrap_or_else(|error| error.into_inner());
+        dirs.insert(path.to_path_buf());
+    }
+}
+
+impl FileSystem for Recorder {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let content = self.inner.read(path)?;
+        self.record_file(path, Some(String::from_utf8_lossy(&content).into_owned()));
+        Ok(content)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        let content = self.inner.read_to_string(path)?;
+        self.record_file(path, Some(content.clone()));
+        Ok(content)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let metadata = self.inner.metadata(path)?;
+        match metadata.kind {
+            FileKind::File => self.record_file(path, None),
+            FileKind::Dir => self.record_dir(path),
+            FileKind::Other => {}
+        }
+        Ok(metadata)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.inner.read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.inner.canonicalize(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let children = self.inner.read_dir(path)?;
+        self.record_dir(path);
+        Ok(children)
+    }
+}
+
+impl Resolver {
+    /// Resolves `request` in `path` again with a fresh cache, and writes the
+    /// options, the files it touched, such as the description files, and
+    /// its trace into a JSON file at `out`, which users can attach to the
+    /// bug reports.
+    ///
+    /// The hooks, the observer and the protocol handlers can't be recorded,
+    /// they are not called by the resolution of the bundle.
+    pub fn dump_debug_bundle<P: AsRef<Path>>(
+        &self,
+        path: &Path,
+        request: &str,
+        out: P,
+    ) -> RResult<DebugBundle> {
+        let recorder = Arc::new(Recorder {
+            inner: self.cache.fs.file_system(),
+            files: Default::default(),
+            dirs: Default::default(),
+        });
+        let resolver = Resolver::new(Options {
+            external_cache: Some(Arc::new(Cache::with_file_system(recorder.clone()))),
+            ..self.raw_options.clone()
+        });
+        #[cfg(feature = "trace")]
+        let (result, trace) = {
+            let (result, trace) = resolver.resolve_traced(path, request, Vec::new());
+            let trace = String::from_utf8_lossy(&trace)
+                .lines()
+                .map(String::from)
+                .collect();
+            (result, trace)
+        };
+        #[cfg(not(feature = "trace"))]
+        let (result, trace) = (resolver.resolve(path, request), vec![]);
+        let result = match result {
+            Ok(ResolveResult::Resource(resource)) => Ok(Some(resource.path)),
+            Ok(ResolveResult::Ignored | ResolveResult::OptionalMissing) => Ok(None),
+            Err(error) => Err(error.to_string()),
+        };
+        let bundle = DebugBundle {
+            path: path.to_path_buf(),
+            request: request.to_string(),
+            options: options_to_json(&self.raw_options),
+            files: std::mem::take(&mut recorder.files.lock().unwrap()),
+            dirs: std::mem::take(&mut recorder.dirs.lock().unwrap()),
+            trace,
+            result,
+        };
+        let json = serde_json::to_string_pretty(&bundle.to_json())
+            .map_err(|error| Error::UnexpectedValue(error.to_string()))?;
+        std::fs::write(out, json)?;
+        Ok(bundle)
+    }
+}
+
+impl DebugBundle {
+    /// Reads the bundle written by `Resolver::dump_debug_bundle`.
+    pub fn load<P: AsRef<Path>>(path: P) -> RResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        let json: Value = serde_json::from_str(&content)
+            .map_err(|error| Error::UnexpectedJson((path.into(), error)))?;
+        Self::from_json(&json).ok_or_else(|| {
+            Error::UnexpectedValue(format!("{} is not a debug bundle", path.display()))
+        })
+    }
+
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        let (result, error) = match &self.result {
+            Ok(path) => (json!(path), Value::Null),
+            Err(error) => (Value::Null, json!(error)),
+        };
+        json!({
+            "path": self.path,
+            "request": self.request,
+            "options": self.options,
+            "files": self.files,
+            "dirs": self.dirs,
+            "trace": self.trace,
+            "result": result,
+            "error": error,
+        })
+    }
+
+    #[must_use]
+    pub fn from_json(json: &Value) -> Option<Self> {
+        let path = |value: &Value| value.as_str().map(PathBuf::from);
+        let files = json
+            .get("files")?
+            .as_object()?
+            .iter()
+            .map(|(file, content)| Some((PathBuf::from(file), content.as_str()?.to_string())))
+            .collect::<Option<_>>()?;
+        let result = match json.get("error").and_then(Value::as_str) {
+            Some(error) => Err(error.to_string()),
+            None => Ok(json.get("result").and_then(path)),
+        };
+        Some(Self {
+            path: path(json.get("path")?)?,
+            request: json.get("request")?.as_str()?.to_string(),
+            options: json.get("options")?.clone(),
+            files,
+            dirs: json
+                .get("dirs")?
+                .as_array()?
+                .iter()
+                .map(path)
+                .collect::<Option<_>>()?,
+            trace: strings(json.get("trace")?)?,
+            result,
+        })
+    }
+
+    /// The options of the resolution, the options which are not data, such
+    /// as the hooks, are the defaults.
+    #[must_use]
+    pub fn options(&self) -> Options {
+        options_from_json(&self.options)
+    }
+
+    /// Resolves the request again on the recorded files in memory.
+    #[cfg(feature = "test-util")]
+    pub fn replay(&self) -> RResult<ResolveResult<crate::Resource>> {
+        self.tree()
+            .resolver(self.options())
+            .resolve(&self.path, &self.request)
+    }
+
+    /// The recorded files and directories, see `replay`.
+    #[cfg(feature = "test-util")]
+    #[must_use]
+    pub fn tree(&self) -> crate::test_util::VirtualTree {
+        let root = self
+            .dirs
+            .iter()
+            .next()
+            .and_then(|dir| dir.ancestors().last())
+            .unwrap_or(Path::new("/"));
+        let mut tree = crate::test_util::VirtualTree::new(root);
+        for dir in &self.dirs {
+            tree = tree.dir(dir);
+        }
+        for (file, content) in &self.files {
+            tree = tree.file(file, content);
+        }
+        tree
+    }
+}
+
+fn options_to_json(options: &Options) -> Value {
+    let alias = |alias: &[(String, Vec<AliasMap>)]| {
+        let alias = alias.iter().map(|(from, to)| {
+            let to = to.iter().map(|to| match to {
+                AliasMap::Target(to) => json!(to),
+                AliasMap::Ignored => Value::Null,
+            });
+            json!([from, to.collect::<Vec<_>>()])
+        });
+        Value::Array(alias.collect())
+    };
+    let mut condition_names = Vec::from_iter(&options.condition_names);
+    condition_names.sort();
+    json!({
+        "extensions": options.extensions,
+        "enforceExtension": format!("{:?}", options.enforce_extension),
+        "alias": alias(&options.alias),
+        "fallback": alias(&options.fallback),
+        "preferRelative": options.prefer_relative,
+        "symlinks": options.symlinks,
+        "descriptionFile": options.description_file,
+        "mainFiles": options.main_files,
+        "mainFields": options.main_fields,
+        "browserField": options.browser_field,
+        "conditionNames": condition_names,
+        "modules": options.modules,
+        "fullySpecified": options.fully_specified,
+        "exportsField": options.exports_field,
+        "strictExports": options.strict_exports,
+        "extensionAlias": options.extension_alias,
+        "algorithm": format!("{:?}", options.algorithm),
+    })
+}
+
+fn options_from_json(json: &Value) -> Options {
+    let default = Options::default();
+    let field = |key: &str| json.get(key);
+    let flag = |key: &str, default: bool| field(key).and_then(Value::as_bool).unwrap_or(default);
+    let list = |key: &str, default: Vec<String>| field(key).and_then(strings).unwrap_or(default);
+    let alias = |key: &str| {
+        let items = field(key).and_then(Value::as_array).into_iter().flatten();
+        items
+            .filter_map(|item| {
+                let from = item.get(0)?.as_str()?.to_string();
+                let to = item.get(1)?.as_array()?.iter().map(|to| match to.as_str() {
+                    Some(to) => AliasMap::Target(to.to_string()),
+                    None => AliasMap::Ignored,
+                });
+                Some((from, to.collect()))
+            })
+            .collect()
+    };
+    let enforce_extension = match field("enforceExtension").and_then(Value::as_str) {
+        Some("Enabled") => EnforceExtension::Enabled,
+        Some("Disabled") => EnforceExtension::Disabled,
+        _ => default.enforce_extension,
+    };
+    let algorithm = match field("algorithm").and_then(Value::as_str) {
+        Some("NodeEsm") => Algorithm::NodeEsm,
+        Some("NodeCjs") => Algorithm::NodeCjs,
+        _ => default.algorithm,
+    };
+    let exports_field = field("exportsField")
+        .and_then(Value::as_array)
+        .and_then(|fields| fields.iter().map(strings).collect())
+        .unwrap_or_else(|| default.exports_field.clone());
+    let extension_alias = field("extensionAlias")
+        .and_then(Value::as_array)
+        .and_then(|items| {
+            items
+                .iter()
+                .map(|item| Some((item.get(0)?.as_str()?.to_string(), strings(item.get(1)?)?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    Options {
+        extensions: list("extensions", default.extensions.clone()),
+        enforce_extension,
+        alias: alias("alias"),
+        fallback: alias("fallback"),
+        prefer_relative: flag("preferRelative", default.prefer_relative),
+        symlinks: flag("symlinks", default.symlinks),
+        description_file: field("descriptionFile")
+            .and_then(Value::as_str)
+            .map_or_else(|| default.description_file.clone(), String::from),
+        main_files: list("mainFiles", default.main_files.clone()),
+        main_fields: list("mainFields", default.main_fields.clone()),
+        browser_field: flag("browserField", default.browser_field),
+        condition_names: list("conditionNames", vec![]).into_iter().collect(),
+        modules: list("modules", default.modules.clone()),
+        fully_specified: flag("fullySpecified", default.fully_specified),
+        exports_field,
+        strict_exports: flag("strictExports", default.strict_exports),
+        extension_alias,
+        algorithm,
+        ..default
+    }
+}
+
+fn strings(value: &Value) -> Option<Vec<String>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|item| item.as_str().map(String::from))
+        .collect()
+}
@@ -1,30 +1,72 @@
 use crate::info::NormalizedPath;
+use crate::map::{ExportsField, Field};
 use crate::{AliasMap, Error, RResult};
+use dashmap::DashMap;
 use once_cell::sync::OnceCell;
+use rustc_hash::FxHasher;
+use std::collections::HashSet;
+use std::hash::BuildHasherDefault;
 use std::path::Path;
 use std::sync::Arc;
 
+/// Parse json which may contain comments and trailing commas,
+/// which is allowed by `tsc`.
+#[cfg(any(feature = "tsconfig", feature = "jsonc-description"))]
+pub(crate) fn parse_jsonc(
+    json_str: &str,
+) -> Result<Option<serde_json::Value>, jsonc_parser::errors::ParseError> {
+    let options = jsonc_parser::ParseOptions {
+        allow_comments: true,
+        allow_loose_object_property_names: false,
+        allow_trailing_commas: true,
+    };
+    jsonc_parser::parse_to_serde_value(json_str, &options)
+}
+
 #[derive(Debug)]
 pub struct PkgJSON {
     name: Option<Box<str>>,
     alias_fields: OnceCell<Vec<(String, AliasMap)>>,
     raw: Arc<serde_json::Value>,
+    /// Whether the fields which are not in `retained` were dropped.
+    trimmed: bool,
 }
 
 impl PkgJSON {
-    pub(crate) fn parse(content: &str, file_path: &Path) -> RResult<Self> {
-        let json: serde_json::Value =
+    /// Parses the content of a description file, such as `package.json`,
+    /// `file_path` is only used in the error.
+    /// Only the fields in `retained` are kept if it is `Some`.
+    pub fn parse(content: &str, file_path: &Path, retained: Option<&[&str]>) -> RResult<Self> {
+        let mut json: serde_json::Value =
             tracing::debug_span!("serde_json_from_str").in_scope(|| {
-                serde_json::from_str(content)
-                    .map_err(|error| Error::UnexpectedJson((file_path.into(), error)))
+                match serde_json::from_str(content) {
+                    Ok(json) => Ok(json),
+                    // strict json is the common case, so only fallback to
+                    // the lenient parser when it failed.
+                    #[cfg(feature = "jsonc-description")]
+                    Err(error) => match parse_jsonc(content) {
+                        Ok(Some(json)) => Ok(json),
+                        _ => Err(Error::UnexpectedJson((file_path.into(), error))),
+                    },
+                    #[cfg(not(feature = "jsonc-description"))]
+                    Err(error) => Err(Error::UnexpectedJson((file_path.into(), error))),
+                }
             })?;
 
         let name = json.get("name").and_then(|v| v.as_str()).map(|s| s.into());
+        let trimmed = match (retained, json.as_object_mut()) {
+            (Some(retained), Some(map)) => {
+                map.retain(|key, _| retained.contains(&key.as_str()));
+                true
+            }
+            _ => false,
+        };
 
         Ok(Self {
             name,
             alias_fields: OnceCell::new(),
             raw: Arc::from(json),
+            trimmed,
         })
     }
 
@@ -52,7 +94,8 @@ impl PkgJSON {
         })
     }
 
-    pub(crate) fn get_filed(&self, field: &Vec<String>) -> Option<&serde_json::Value> {
+    /// The value at the nested `field`, such as `["exports"]`.
+    pub fn get_filed(&self, field: &Vec<String>) -> Option<&serde_json::Value> {
         let mut current_value = self.raw().as_ref();
         for current_field in field {
             if !current_value.is_object() {
@@ -73,29 +116,111 @@ impl PkgJSON {
     pub fn raw(&self) -> &Arc<serde_json::Value> {
         &self.raw
     }
+
+    /// Whether `raw` only contains the fields used in resolving,
+    /// see `Options::description_retention`.
+    pub fn is_trimmed(&self) -> bool {
+        self.trimmed
+    }
 }
 
 #[derive(Debug)]
 pub struct DescriptionData {
-    json: PkgJSON,
+    /// Shared by the directories which are the symlinks of the same one,
+    /// see `Options::dedupe_by_realpath`.
+    manifest: Arc<Manifest>,
     /// The path to the directory where the description file located.
     /// It not a property in package.json.
     dir_path: NormalizedPath,
 }
 
+#[derive(Debug)]
+struct Manifest {
+    json: PkgJSON,
+    /// The targets of `exports` keyed by the field, the subpath and the
+    /// sorted condition names, it is dropped together with the description.
+    exports_targets: DashMap<String, Arc<[String]>, BuildHasherDefault<FxHasher>>,
+}
+
 impl DescriptionData {
     pub fn new<P: AsRef<Path>>(json: PkgJSON, dir_path: P) -> Self {
         Self {
-            json,
+            manifest: Arc::new(Manifest {
+                json,
+                exports_targets: Default::default(),
+            }),
             dir_path: NormalizedPath::new(dir_path),
         }
     }
 
+    /// The same description located at `dir_path`, such as a symlink of
+    /// `self.dir()`, the parsed content and the targets of `exports` are
+    /// shared with `self`.
+    pub(crate) fn with_dir<P: AsRef<Path>>(&self, dir_path: P) -> Self {
+        Self {
+            manifest: self.manifest.clone(),
+            dir_path: NormalizedPath::new(dir_path),
+        }
+    }
+
+    /// Whether the description is used by an entry, directly or through
+    /// `DescriptionData::with_dir`.
+    pub(crate) fn is_used(self: &Arc<Self>) -> bool {
+        Arc::strong_count(self) > 1 || Arc::strong_count(&self.manifest) > 1
+    }
+
+    /// The memoized `ExportsField::field_process` of `root`, which is the
+    /// value of `field`. The hot packages are queried with the same
+    /// subpath and conditions again and again.
+    pub(crate) fn exports_targets(
+        &self,
+        field: &[String],
+        root: &serde_json::Value,
+        subpath: &str,
+        condition_names: &HashSet<String>,
+    ) -> RResult<Arc<[String]>> {
+        let mut conditions: Vec<&str> = condition_names.iter().map(String::as_str).collect();
+        conditions.sort_unstable();
+        let key = format!("{}\0{subpath}\0{}", field.join("."), conditions.join(","));
+        let exports_targets = &self.manifest.exports_targets;
+        if let Some(targets) = exports_targets.get(&key) {
+            return Ok(targets.clone());
+        }
+        let targets: Arc<[String]> =
+            ExportsField::field_process(root, subpath, condition_names)?.into();
+        exports_targets.insert(key, targets.clone());
+        Ok(targets)
+    }
+
     pub fn dir(&self) -> &NormalizedPath {
         &self.dir_path
     }
 
     pub fn data(&self) -> &PkgJSON {
-        &self.json
+        &self.manifest.json
     }
 }
+
+#[test]
+fn exports_targets_test() {
+    let json = r#"{ "exports": { ".": { "import": "./a.mjs", "default": "./a.js" } } }"#;
+    let description =
+        DescriptionData::new(PkgJSON::parse(json, Path::new("/p"), None).unwrap(), "/");
+    let field = vec![String::from("exports")];
+    let root = description.data().get_filed(&field).unwrap();
+    let import = crate::test_helper::vec_to_set(vec!["node", "import"]);
+    let targets = description
+        .exports_targets(&field, root, ".", &import)
+        .unwrap();
+    assert_eq!(&*targets, ["./a.mjs"]);
+    // the order of the condition names does not matter.
+    let same = crate::test_helper::vec_to_set(vec!["import", "node"]);
+    let cached = description
+        .exports_targets(&field, root, ".", &same)
+        .unwrap();
+    assert!(Arc::ptr_eq(&targets, &cached));
+    let targets = description
+        .exports_targets(&field, root, ".", &HashSet::new())
+        .unwrap();
+    assert_eq!(&*targets, ["./a.js"]);
+}
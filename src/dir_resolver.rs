@@ -0,0 +1,52 @@
+use crate::{info::NormalizedPath, DescriptionData, RResult, ResolveResult, Resolver, Resource};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The context of the directory which is the same for all requests in it.
+#[derive(Debug)]
+pub(crate) struct DirContext {
+    pub(crate) path: PathBuf,
+    pub(crate) normalized: NormalizedPath,
+    pub(crate) issuer_pkg_info: Option<Arc<DescriptionData>>,
+}
+
+/// Resolves many requests issued from the same directory, such as the
+/// imports of a file, the context of the directory is derived only once.
+///
+/// The handle is expected to be short-lived, the changes of the description
+/// file made after it was created are not observed by it.
+#[derive(Debug)]
+pub struct DirResolver<'a> {
+    pub(crate) resolver: &'a Resolver,
+    pub(crate) context: DirContext,
+}
+
+impl<'a> DirResolver<'a> {
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.context.path
+    }
+
+    /// The same as `Resolver::resolve(self.dir(), request)`.
+    pub fn resolve(&self, request: &str) -> RResult<ResolveResult<Resource>> {
+        self.resolver
+            .resolve_in(&self.context.path, request, Some(&self.context), None, None)
+    }
+}
+
+impl Resolver {
+    #[must_use]
+    pub fn dir_resolver(&self, dir: &Path) -> DirResolver<'_> {
+        let context = DirContext {
+            path: dir.to_path_buf(),
+            normalized: NormalizedPath::new(dir),
+            issuer_pkg_info: self.issuer_pkg_info(dir),
+        };
+        DirResolver {
+            resolver: self,
+            context,
+        }
+    }
+}
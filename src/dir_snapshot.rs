@@ -0,0 +1,163 @@
+use crate::{FileKind, FileMetadata, FileSystem, OsFileSystem};
+use dashmap::DashMap;
+use rustc_hash::{FxHashMap, FxHasher};
+use std::{
+    ffi::OsString,
+    hash::BuildHasherDefault,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[derive(Debug)]
+enum Snapshot {
+    Listed(FxHashMap<OsString, FileMetadata>),
+    /// The directory does not exist, or it is not a directory.
+    Missing,
+    /// The directory can't be listed, such as the permission is denied,
+    /// so its children are probed one by one.
+    Unlistable,
+}
+
+/// A `FileSystem` which lists each directory once, and answers `metadata`
+/// and `exists` of the children from the listing.
+///
+/// It is designed for Windows, where a stat opens the file and is slow,
+/// while the listing returns the kinds and the times of a whole directory
+/// in batches of `NtQueryDirectoryFile`. The snapshots are never refreshed
+/// by themselves, the watchers of the hosts should call `invalidate` with
+/// the changed paths, along with `Resolver::clear_entries`.
+#[derive(Debug, Default)]
+pub struct DirSnapshotFileSystem {
+    snapshots: DashMap<PathBuf, Arc<Snapshot>, BuildHasherDefault<FxHasher>>,
+}
+
+impl DirSnapshotFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops the snapshots of `path` and of its parent, `path` is a file or
+    /// a directory which was created, changed or removed.
+    pub fn invalidate(&self, path: &Path) {
+        self.snapshots.remove(path);
+        if let Some(parent) = path.parent() {
+            self.snapshots.remove(parent);
+        }
+    }
+
+    /// Drops all the snapshots.
+    pub fn clear(&self) {
+        self.snapshots.clear();
+    }
+
+    fn snapshot(&self, dir: &Path) -> Arc<Snapshot> {
+        if let Some(snapshot) = self.snapshots.get(dir) {
+            return snapshot.clone();
+        }
+        let snapshot = Arc::new(list(dir));
+        self.snapshots.insert(dir.to_path_buf(), snapshot.clone());
+        snapshot
+    }
+}
+
+fn list(dir: &Path) -> Snapshot {
+    let read_dir = match std::fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Snapshot::Missing,
+        // `ERROR_DIRECTORY` of Windows and `ENOTDIR` of Unix.
+        Err(error) if error.raw_os_error() == Some(NOT_A_DIRECTORY) => return Snapshot::Missing,
+        Err(_) => return Snapshot::Unlistable,
+    };
+    let mut children = FxHashMap::default();
+    for entry in read_dir {
+        let Ok(entry) = entry else {
+            return Snapshot::Unlistable;
+        };
+        let path = entry.path();
+        // the metadata of the entries is from the listing on Windows, but it
+        // does not follow the symlinks.
+        let meta = match entry.file_type() {
+            Ok(file_type) if file_type.is_symlink() => OsFileSystem.metadata(&path),
+            Ok(_) => entry.metadata().map(|meta| {
+                let kind = if meta.is_file() {
+                    FileKind::File
+                } else if meta.is_dir() {
+                    FileKind::Dir
+                } else {
+                    FileKind::Other
+                };
+                FileMetadata {
+                    kind,
+                    modified: meta.modified().ok(),
+                }
+            }),
+            Err(error) => Err(error),
+        };
+        // the broken symlinks are missing as `metadata` reports.
+        if let Ok(meta) = meta {
+            children.insert(key(&entry.file_name()), meta);
+        }
+    }
+    Snapshot::Listed(children)
+}
+
+#[cfg(windows)]
+const NOT_A_DIRECTORY: i32 = 267;
+#[cfg(not(windows))]
+const NOT_A_DIRECTORY: i32 = 20;
+
+/// The names are case-insensitive on Windows.
+#[cfg(windows)]
+fn key(name: &std::ffi::OsStr) -> OsString {
+    name.to_ascii_lowercase()
+}
+
+#[cfg(not(windows))]
+fn key(name: &std::ffi::OsStr) -> OsString {
+    name.to_os_string()
+}
+
+impl FileSystem for DirSnapshotFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        OsFileSystem.read(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        OsFileSystem.read_to_string(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return OsFileSystem.metadata(path);
+        };
+        match &*self.snapshot(dir) {
+            Snapshot::Listed(children) => children
+                .get(&key(name))
+                .copied()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound)),
+            Snapshot::Missing => Err(io::Error::from(io::ErrorKind::NotFound)),
+            Snapshot::Unlistable => OsFileSystem.metadata(path),
+        }
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        OsFileSystem.read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        OsFileSystem.canonicalize(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        OsFileSystem.read_dir(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    fn read_mapped(&self, path: &Path, parse: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+        OsFileSystem.read_mapped(path, parse)
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{description::DescriptionData, Resolver};
+use dashmap::DashMap;
+use rustc_hash::FxHasher;
+use std::{hash::BuildHasherDefault, path::PathBuf};
+
+/// One physical copy of a package which had been resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PackageInstance {
+    /// `version` in package.json, `None` if it is missing.
+    pub version: Option<String>,
+    /// The directory where the package.json located.
+    pub path: PathBuf,
+}
+
+impl PackageInstance {
+    pub(crate) fn new(description: &DescriptionData) -> Self {
+        let version = description
+            .data()
+            .raw()
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        Self {
+            version,
+            path: description.dir().as_ref().to_path_buf(),
+        }
+    }
+}
+
+/// A package name which had been resolved from more than one directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DuplicatePackage {
+    pub name: String,
+    /// Sorted by `path`.
+    pub instances: Vec<PackageInstance>,
+}
+
+/// Records every named package reached by the resolver in this session.
+#[derive(Debug, Default)]
+pub(crate) struct PackageCollector {
+    packages: DashMap<Box<str>, Vec<PackageInstance>, BuildHasherDefault<FxHasher>>,
+}
+
+impl PackageCollector {
+    pub fn record(&self, description: &DescriptionData) {
+        let Some(name) = description.data().name() else {
+            return;
+        };
+        let path = description.dir().as_ref();
+        if self.contains(name, path) {
+            return;
+        }
+        self.record_instance(name, PackageInstance::new(description));
+    }
+
+    /// Records a package which is known without resolving it, such as the
+    /// packages in lockfiles.
+    pub fn record_instance(&self, name: &str, instance: PackageInstance) {
+        let mut instances = self.packages.entry(name.into()).or_default();
+        if instances.iter().any(|item| item.path == instance.path) {
+            return;
+        }
+        instances.push(instance);
+    }
+
+    fn contains(&self, name: &str, path: &std::path::Path) -> bool {
+        self.packages.get(name).map_or(false, |instances| {
+            instances.iter().any(|item| item.path == path)
+        })
+    }
+
+    pub fn clear(&self) {
+        self.packages.clear();
+    }
+
+    fn report(&self) -> Vec<DuplicatePackage> {
+        let mut report: Vec<DuplicatePackage> = self
+            .packages
+            .iter()
+            .filter(|item| item.value().len() > 1)
+            .map(|item| {
+                let mut instances = item.value().clone();
+                instances.sort_by(|a, b| a.path.cmp(&b.path));
+                DuplicatePackage {
+                    name: item.key().to_string(),
+                    instances,
+                }
+            })
+            .collect();
+        report.sort_by(|a, b| a.name.cmp(&b.name));
+        report
+    }
+}
+
+impl Resolver {
+    /// Returns the packages which had been resolved from multiple
+    /// directories since this resolver was created (or since the last
+    /// `clear_duplicate_report`), sorted by package name.
+    #[must_use]
+    pub fn duplicate_report(&self) -> Vec<DuplicatePackage> {
+        self.packages.report()
+    }
+
+    pub fn clear_duplicate_report(&self) {
+        self.packages.clear();
+    }
+}
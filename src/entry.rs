@@ -1,33 +1,78 @@
 use once_cell::sync::OnceCell;
 use std::{
     borrow::Cow,
-    fs::FileType,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::SystemTime,
 };
 
-use crate::{description::DescriptionData, Error, RResult, Resolver};
+use crate::{
+    cache::Entries,
+    description::DescriptionData,
+    file_system::{FileKind, FileMetadata, FileSystem},
+    info::NormalizedPath,
+    sandbox, DescriptionRetention, Error, Generation, IoErrorClass, MalformedDescription, RResult,
+    Resolver,
+};
+
+/// A segment of a path which is a symlink, see `EntryRef::symlink_chain`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Symlink {
+    /// The path of the symlink, such as `node_modules/foo`.
+    pub path: PathBuf,
+    /// The canonicalized path which `path` points to.
+    pub target: PathBuf,
+}
+
+/// The real path of `path` whose symlinks are `chain`, see
+/// `Entry::symlink_chain`.
+pub(crate) fn real_path(chain: &[Symlink], path: &Path) -> PathBuf {
+    let Some(link) = chain.last() else {
+        return path.to_path_buf();
+    };
+    match path.strip_prefix(&link.path) {
+        Ok(rest) if !rest.as_os_str().is_empty() => link.target.join(rest),
+        _ => link.target.clone(),
+    }
+}
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct EntryStat {
     /// `None` for non-existing file
-    file_type: Option<FileType>,
+    file_type: Option<FileKind>,
 
     /// `None` for existing file but without system time.
     modified: Option<SystemTime>,
+
+    /// The class of the error of the stat, `None` if it succeeded or the
+    /// file does not exist.
+    error: Option<IoErrorClass>,
 }
 
 impl EntryStat {
-    fn new(file_type: Option<FileType>, modified: Option<SystemTime>) -> Self {
+    fn new(file_type: Option<FileKind>, modified: Option<SystemTime>) -> Self {
         Self {
             file_type,
             modified,
+            error: None,
+        }
+    }
+
+    fn failed(error: &std::io::Error) -> Self {
+        let class = IoErrorClass::of(error);
+        Self {
+            file_type: None,
+            modified: None,
+            error: (class != IoErrorClass::NotFound).then_some(class),
         }
     }
 
     /// Returns `None` for non-existing file
-    pub fn file_type(&self) -> Option<FileType> {
+    pub fn file_type(&self) -> Option<FileKind> {
         self.file_type
     }
 
@@ -36,33 +81,52 @@ impl EntryStat {
         self.modified
     }
 
-    fn stat(path: &Path) -> Self {
-        if let Ok(meta) = path.metadata() {
-            // This field might not be available on all platforms,
-            // and will return an Err on platforms where it is not available.
-            let modified = meta.modified().ok();
-            Self::new(Some(meta.file_type()), modified)
-        } else {
-            Self::new(None, None)
+    /// Returns `None` if the stat succeeded or the file does not exist.
+    pub fn error(&self) -> Option<IoErrorClass> {
+        self.error
+    }
+
+    fn stat(fs: &dyn FileSystem, path: &Path) -> Self {
+        match fs.metadata(path) {
+            Ok(meta) => Self::new(Some(meta.kind), meta.modified),
+            Err(error) => Self::failed(&error),
         }
     }
 }
 
 #[derive(Debug)]
 pub struct Entry {
+    fs: Arc<dyn FileSystem>,
     parent: Option<Arc<Entry>>,
     path: Box<Path>,
     // None: package.json does not exist
     pkg_info: OnceCell<Option<Arc<DescriptionData>>>,
+    /// The description file of the package root, see `Entry::package_root_info`.
+    package_root: OnceCell<Option<Arc<DescriptionData>>>,
     stat: OnceCell<EntryStat>,
+    /// The result of `FileSystem::exists`, only used if `stat` is not loaded.
+    exists: OnceCell<bool>,
     /// None represent the `self.path` is not a symlink
     symlink: OnceCell<Option<Box<Path>>>,
     /// If `self.path` is a symlink, then return canonicalized path,
     /// else return `self.path`
     real: OnceCell<Box<Path>>,
+    /// The last generation in which the entry was used, see `Resolver::gc`.
+    touched: AtomicU64,
+    /// Whether the path is outside of the roots of the sandbox, such
+    /// entry is never cached, and treated as missing without any IO.
+    blocked: bool,
 }
 
 impl Entry {
+    fn touch(&self, generation: Generation) {
+        self.touched.fetch_max(generation.0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn touched(&self) -> Generation {
+        Generation(self.touched.load(Ordering::Relaxed))
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
@@ -72,27 +136,48 @@ impl Entry {
     }
 
     pub fn pkg_info(&self, resolver: &Resolver) -> RResult<&Option<Arc<DescriptionData>>> {
+        if self.blocked {
+            sandbox::record(&self.path);
+            return Ok(&None);
+        }
         self.pkg_info.get_or_try_init(|| {
             let pkg_name = &resolver.options.description_file;
             let path = self.path();
             let is_pkg_suffix = path.ends_with(pkg_name);
-            if self.is_dir() || is_pkg_suffix {
+            let real = (resolver.options.dedupe_by_realpath && self.is_dir())
+                .then(|| real_path(&self.symlink_chain(), path))
+                .filter(|real| real != path);
+            if let Some(real) = real {
+                // the symlinks of the same package share the description of the real directory.
+                let shared = resolver.load_entry(&real).pkg_info(resolver)?.clone();
+                if let Some(info) = shared.filter(|info| info.dir().as_ref() == real) {
+                    return Ok(Some(Arc::new(info.with_dir(path))));
+                }
+            } else if self.is_dir() || is_pkg_suffix {
                 let pkg_path = if is_pkg_suffix {
                     Cow::Borrowed(path)
                 } else {
                     Cow::Owned(path.join(pkg_name))
                 };
-                match resolver
-                    .cache
-                    .fs
-                    .read_description_file(&pkg_path, EntryStat::default())
-                {
+                let retained = resolver.retained_description_fields();
+                let retained: Option<Vec<&str>> = retained
+                    .as_ref()
+                    .map(|fields| fields.iter().map(String::as_str).collect());
+                match resolver.cache.fs.read_description_file(
+                    &pkg_path,
+                    EntryStat::default(),
+                    retained.as_deref(),
+                ) {
                     Ok(info) => {
                         return Ok(Some(info));
                     }
                     Err(error @ (Error::UnexpectedJson(_) | Error::UnexpectedValue(_))) => {
-                        // Return bad json
-                        return Err(error);
+                        match &resolver.options.on_malformed_description {
+                            // Return bad json
+                            MalformedDescription::Error => return Err(error),
+                            MalformedDescription::Ignore => {}
+                            MalformedDescription::Warn(callback) => callback(&pkg_path, &error),
+                        }
                     }
                     Err(Error::Io(_)) => {
                         // package.json not found
@@ -100,6 +185,9 @@ impl Entry {
                     _ => unreachable!(),
                 };
             }
+            if self.is_dir() && resolver.is_description_search_root(path) {
+                return Ok(None);
+            }
             if let Some(parent) = &self.parent() {
                 return parent.pkg_info(resolver).cloned();
             }
@@ -107,24 +195,81 @@ impl Entry {
         })
     }
 
+    /// The nearest description file with `name`, which is the root of the
+    /// package, the nested ones without `name`, such as `esm/package.json`
+    /// which only declares `"type"`, are skipped. It stops at `node_modules`,
+    /// and it is the nearest one if no description file has `name`.
+    pub fn package_root_info(&self, resolver: &Resolver) -> RResult<&Option<Arc<DescriptionData>>> {
+        self.package_root.get_or_try_init(|| {
+            let Some(nearest) = self.pkg_info(resolver)? else {
+                return Ok(None);
+            };
+            let dir = nearest.dir().as_ref();
+            let installed = dir
+                .parent()
+                .map_or(false, |parent| parent.ends_with("node_modules"));
+            if nearest.data().name().is_some()
+                || installed
+                || resolver.is_description_search_root(dir)
+            {
+                return Ok(Some(nearest.clone()));
+            }
+            let outer = match dir.parent() {
+                Some(parent) => resolver
+                    .load_entry(parent)
+                    .package_root_info(resolver)?
+                    .clone(),
+                None => None,
+            };
+            Ok(outer.or_else(|| Some(nearest.clone())))
+        })
+    }
+
     pub fn is_file(&self) -> bool {
         self.cached_stat()
             .file_type()
-            .map_or(false, |ft| ft.is_file())
+            .map_or(false, |ft| ft == FileKind::File)
     }
 
     pub fn is_dir(&self) -> bool {
         self.cached_stat()
             .file_type()
-            .map_or(false, |ft| ft.is_dir())
+            .map_or(false, |ft| ft == FileKind::Dir)
     }
 
     pub fn exists(&self) -> bool {
         self.cached_stat().file_type().is_some()
     }
 
+    /// Same as `exists`, but probes by `FileSystem::exists` instead of loading
+    /// the full stat if it is not cached yet.
+    pub fn probe_exists(&self) -> bool {
+        if self.blocked {
+            sandbox::record(&self.path);
+            return false;
+        }
+        if let Some(stat) = self.stat.get() {
+            return stat.file_type().is_some();
+        }
+        *self.exists.get_or_init(|| self.fs.exists(&self.path))
+    }
+
+    /// Caches the stat loaded in a batch, see `FileSystem::metadata_batch`.
+    pub(crate) fn init_stat(&self, meta: &std::io::Result<FileMetadata>) {
+        self.stat.get_or_init(|| match meta {
+            Ok(meta) => EntryStat::new(Some(meta.kind), meta.modified),
+            Err(error) => EntryStat::failed(error),
+        });
+    }
+
     pub fn cached_stat(&self) -> EntryStat {
-        *self.stat.get_or_init(|| EntryStat::stat(&self.path))
+        if self.blocked {
+            sandbox::record(&self.path);
+            return EntryStat::default();
+        }
+        *self
+            .stat
+            .get_or_init(|| EntryStat::stat(self.fs.as_ref(), &self.path))
     }
 
     pub fn real(&self) -> Option<&Path> {
@@ -140,27 +285,198 @@ impl Entry {
     pub fn symlink(&self) -> &Option<Box<Path>> {
         self.symlink.get_or_init(|| {
             debug_assert!(self.path.is_absolute());
-            if self.path.read_link().is_err() {
+            if self.blocked {
                 return None;
             }
-            match dunce::canonicalize(&self.path) {
+            if self.fs.read_link(&self.path).is_err() {
+                return None;
+            }
+            match self.fs.canonicalize(&self.path) {
                 Ok(symlink_path) => Some(Box::from(symlink_path)),
                 Err(_) => None,
             }
         })
     }
+
+    /// The segments of `self.path` which are symlinks, from the outermost.
+    pub fn symlink_chain(&self) -> Vec<Symlink> {
+        let mut chain = vec![];
+        let mut entry = Some(self);
+        while let Some(current) = entry {
+            if let Some(target) = current.symlink() {
+                chain.push(Symlink {
+                    path: current.path().to_path_buf(),
+                    target: target.to_path_buf(),
+                });
+            }
+            entry = current.parent().map(AsRef::as_ref);
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// A view of the cached entry, so that the tools built on the resolver
+/// can share the IO results with it.
+#[derive(Debug, Clone)]
+pub struct EntryRef<'a> {
+    entry: Arc<Entry>,
+    resolver: &'a Resolver,
+}
+
+impl<'a> EntryRef<'a> {
+    pub fn path(&self) -> &Path {
+        self.entry.path()
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.entry.is_file()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.entry.is_dir()
+    }
+
+    pub fn exists(&self) -> bool {
+        self.entry.exists()
+    }
+
+    /// Returns `None` for non-existing file or the platform does not provide it.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.entry.cached_stat().modified()
+    }
+
+    /// The class of the error which failed the stat, such as the permission
+    /// is denied, `None` if the stat succeeded or the file does not exist.
+    pub fn io_error(&self) -> Option<IoErrorClass> {
+        self.entry.cached_stat().error()
+    }
+
+    /// Returns the canonicalized path if `self.path()` is a symlink.
+    pub fn symlink_target(&self) -> Option<&Path> {
+        self.entry.symlink().as_deref()
+    }
+
+    /// The segments of `self.path()` which are symlinks, from the outermost,
+    /// such as `node_modules/foo` of `node_modules/foo/lib/index.js`. The
+    /// real path is the target of the innermost one joined with the rest of
+    /// `self.path()`, it is `self.path()` itself if the chain is empty.
+    pub fn symlink_chain(&self) -> Vec<Symlink> {
+        self.entry.symlink_chain()
+    }
+
+    /// Returns the closest description file of `self.path()`.
+    pub fn pkg_info(&self) -> RResult<Option<Arc<DescriptionData>>> {
+        self.entry.pkg_info(self.resolver).cloned()
+    }
+
+    /// Returns the description file of the package root of `self.path()`,
+    /// which skips the nested ones without `name`.
+    pub fn package_root_info(&self) -> RResult<Option<Arc<DescriptionData>>> {
+        self.entry.package_root_info(self.resolver).cloned()
+    }
+
+    pub fn parent(&self) -> Option<EntryRef<'a>> {
+        self.entry.parent().map(|parent| EntryRef {
+            entry: parent.clone(),
+            resolver: self.resolver,
+        })
+    }
 }
 
 impl Resolver {
+    /// Returns the cached entry of `path`, `path` should be absolute.
+    #[must_use]
+    pub fn entry<P: AsRef<Path>>(&self, path: P) -> EntryRef<'_> {
+        let path = NormalizedPath::new(path);
+        EntryRef {
+            entry: self.load_entry(path.as_ref()),
+            resolver: self,
+        }
+    }
+
+    /// The description file which `field` of `entry` is read from, see
+    /// `Options::package_root_fields`.
+    pub(crate) fn description_for(
+        &self,
+        entry: &Entry,
+        field: &str,
+    ) -> RResult<Option<Arc<DescriptionData>>> {
+        if self.options.package_root_fields.iter().any(|f| f == field) {
+            entry.package_root_info(self).cloned()
+        } else {
+            entry.pkg_info(self).cloned()
+        }
+    }
+
+    /// `None` if the whole description file is retained, otherwise the
+    /// fields kept for all the resolvers sharing the cache.
+    fn retained_description_fields(&self) -> Option<Vec<String>> {
+        (self.options.description_retention != DescriptionRetention::Full)
+            .then(|| self.cache.retained_fields())
+    }
+
+    /// The fields of the description files used by this resolver, they are
+    /// added to the cache by `Resolver::new`, `None` if the whole
+    /// description file is retained.
+    pub(crate) fn resolution_fields(&self) -> Option<Vec<&str>> {
+        if self.options.description_retention == DescriptionRetention::Full {
+            return None;
+        }
+        let mut fields = vec![
+            "name", "version", "type", "main", "browser", "imports", "exports",
+        ];
+        let main_fields = self.options.main_fields.iter().map(String::as_str);
+        let exports_fields = self
+            .options
+            .exports_field
+            .iter()
+            .filter_map(|field| field.first().map(String::as_str));
+        if self.options.package_overrides.is_some() {
+            fields.extend([
+                "overrides",
+                "resolutions",
+                "dependencies",
+                "devDependencies",
+            ]);
+        }
+        if self.options.report_optional_peers {
+            fields.push("peerDependenciesMeta");
+        }
+        if self.options.isolated_mode {
+            for field in ["dependencies", "peerDependencies", "optionalDependencies"] {
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+        }
+        for field in main_fields.chain(exports_fields) {
+            if !fields.contains(&field) {
+                fields.push(field);
+            }
+        }
+        Some(fields)
+    }
+
+    /// The entries of the shared cache, or the own ones of the sandboxed
+    /// resolver which shares the cache, whose entries in the roots have no
+    /// description files above the roots.
+    pub(crate) fn entries(&self) -> &Entries {
+        self.sandbox_entries.as_ref().unwrap_or(&self.cache.entries)
+    }
+
     pub(super) fn load_entry(&self, path: &Path) -> Arc<Entry> {
-        if let Some(cached) = self.cache.entries.get(path) {
+        if self.is_blocked(path) {
+            return Arc::new(self.new_entry(None, path, true));
+        }
+        if let Some(cached) = self.entries().get(path) {
+            self.metrics.record_cache(true);
+            cached.touch(self.cache.generation());
             cached.clone()
         } else {
+            self.metrics.record_cache(false);
             let entry = Arc::new(self.load_entry_uncached(path));
-            self.cache
-                .entries
-                .entry(path.into())
-                .or_insert(entry.clone());
+            self.entries().entry(path.into()).or_insert(entry.clone());
             entry
         }
     }
@@ -172,19 +488,59 @@ impl Resolver {
         } else {
             None
         };
+        self.new_entry(parent, path, false)
+    }
+
+    fn new_entry(&self, parent: Option<Arc<Entry>>, path: &Path, blocked: bool) -> Entry {
         Entry {
+            fs: self.cache.fs.file_system(),
             parent,
             path: path.into(),
             pkg_info: OnceCell::default(),
+            package_root: OnceCell::default(),
             stat: OnceCell::default(),
+            exists: OnceCell::default(),
             symlink: OnceCell::default(),
             real: OnceCell::default(),
+            touched: AtomicU64::new(self.cache.generation().0),
+            blocked,
         }
     }
 
     // TODO: should put entries as a parament.
     pub fn clear_entries(&self) {
-        self.cache.entries.clear();
+        self.entries().clear();
+    }
+
+    /// Resolve with `content` as the content of `path` instead of the one
+    /// on disk, until `close_document` is called. It is used for the
+    /// unsaved files in editors, such as a modified `package.json`.
+    pub fn open_document<P: AsRef<Path>>(&self, path: P, content: &str) {
+        let path = path.as_ref();
+        self.cache
+            .fs
+            .overlay()
+            .open_document(path.to_path_buf(), content.into());
+        self.invalidate_document(path);
+    }
+
+    /// Returns `false` if `path` was not opened.
+    pub fn close_document<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        let closed = self.cache.fs.overlay().close_document(path);
+        if closed {
+            self.invalidate_document(path);
+        }
+        closed
+    }
+
+    fn invalidate_document(&self, path: &Path) {
+        // The description file affects all the entries under its directory.
+        let dir = path.parent().unwrap_or(path);
+        self.cache.entries.retain(|key, _| !key.starts_with(dir));
+        if let Some(entries) = &self.sandbox_entries {
+            entries.retain(|key, _| !key.starts_with(dir));
+        }
     }
 
     #[must_use]
@@ -1,4 +1,5 @@
-use std::{io, path::Path};
+use crate::ModulesPath;
+use std::{fmt, io, path::Path, time::Duration};
 
 #[derive(Debug)]
 pub enum Error {
@@ -8,6 +9,73 @@ pub enum Error {
     ResolveFailedTag,
     Overflow,
     CantFindTsConfig(Box<Path>),
+    /// The resolution was aborted by `CancelToken`.
+    Cancelled,
+    /// The resolution took longer than `Options::timeout`.
+    Timeout {
+        elapsed: Duration,
+        /// The path being resolved when the timeout was noticed.
+        last_probe: Box<Path>,
+    },
+    /// The candidate can't be accessed, see `Options::ignore_permission_denied`.
+    PermissionDenied(Box<Path>),
+    /// The subpath is not exported by the package, including the manifest
+    /// such as `pkg/package.json`, see `Options::allow_manifest_access`.
+    NotExported {
+        /// The request, such as `pkg/package.json`.
+        request: String,
+        /// The directory of the package.
+        package: Box<Path>,
+    },
+    /// The module request was not found before the lookup of the modules
+    /// reached `Options::stop_at`.
+    StoppedAt {
+        request: String,
+        /// The directory which bounds the lookup.
+        boundary: Box<Path>,
+    },
+    /// The module request was not found in any of `paths`, the modules
+    /// directories which were looked up, in order, see
+    /// `Resolver::modules_paths`.
+    NotFound {
+        request: String,
+        paths: Vec<ModulesPath>,
+    },
+}
+
+impl Error {
+    /// Copies the error if it can be, the source of `Io` is not kept.
+    pub(crate) fn try_clone(&self) -> Option<Self> {
+        match self {
+            Self::Io(error) => Some(Self::Io(io::Error::new(error.kind(), error.to_string()))),
+            Self::UnexpectedJson(_) => None,
+            Self::UnexpectedValue(message) => Some(Self::UnexpectedValue(message.clone())),
+            Self::ResolveFailedTag => Some(Self::ResolveFailedTag),
+            Self::Overflow => Some(Self::Overflow),
+            Self::CantFindTsConfig(path) => Some(Self::CantFindTsConfig(path.clone())),
+            Self::Cancelled => Some(Self::Cancelled),
+            Self::Timeout {
+                elapsed,
+                last_probe,
+            } => Some(Self::Timeout {
+                elapsed: *elapsed,
+                last_probe: last_probe.clone(),
+            }),
+            Self::PermissionDenied(path) => Some(Self::PermissionDenied(path.clone())),
+            Self::NotExported { request, package } => Some(Self::NotExported {
+                request: request.clone(),
+                package: package.clone(),
+            }),
+            Self::StoppedAt { request, boundary } => Some(Self::StoppedAt {
+                request: request.clone(),
+                boundary: boundary.clone(),
+            }),
+            Self::NotFound { request, paths } => Some(Self::NotFound {
+                request: request.clone(),
+                paths: paths.clone(),
+            }),
+        }
+    }
 }
 
 impl From<std::io::Error> for Error {
@@ -15,3 +83,53 @@ impl From<std::io::Error> for Error {
         Self::Io(value)
     }
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::UnexpectedJson((path, error)) => {
+                write!(f, "Can't parse {}: {error}", path.display())
+            }
+            Self::UnexpectedValue(message) => write!(f, "{message}"),
+            Self::ResolveFailedTag => write!(f, "Resolve failed"),
+            Self::Overflow => write!(f, "Too many nested resolutions"),
+            Self::CantFindTsConfig(path) => write!(f, "Can't find tsconfig {}", path.display()),
+            Self::Cancelled => write!(f, "Resolution was cancelled"),
+            Self::Timeout {
+                elapsed,
+                last_probe,
+            } => write!(
+                f,
+                "Resolution timed out after {elapsed:?}, the last probe was {}",
+                last_probe.display()
+            ),
+            Self::PermissionDenied(path) => write!(f, "Permission denied: {}", path.display()),
+            Self::NotExported { request, package } => write!(
+                f,
+                "Package path {request} is not exported in {}/package.json",
+                package.display()
+            ),
+            Self::StoppedAt { request, boundary } => write!(
+                f,
+                "Can't resolve '{request}', the lookup of modules stopped at '{}' as `stop_at` bounds it",
+                boundary.display()
+            ),
+            Self::NotFound { request, paths } => write!(
+                f,
+                "Can't find module '{request}' in the {} modules directories",
+                paths.len()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::UnexpectedJson((_, error)) => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::io_error::{retry_transient, IoErrorClass};
+use dashmap::DashMap;
+use rustc_hash::FxHasher;
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    hash::BuildHasherDefault,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    File,
+    Dir,
+    /// Exists, but neither a file nor a directory.
+    Other,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FileMetadata {
+    pub kind: FileKind,
+    /// `None` if the platform does not provide it.
+    pub modified: Option<SystemTime>,
+}
+
+/// The IO used by the resolver, all the paths passed in are absolute.
+pub trait FileSystem: Debug + Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Same as `std::fs::metadata`, it follows symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata>;
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Returns the paths of the children in the directory, in any order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Whether `path` exists, it follows symlinks. It is used when the kind
+    /// of the file does not matter, so it can be cheaper than `metadata`.
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// Reads a file and lends its content to `parse`, such as the
+    /// description files, so the content can be mapped instead of copied,
+    /// see the `mmap-description` feature. `parse` can be called again if
+    /// the file was changed while it was parsed, the last call wins.
+    fn read_mapped(&self, path: &Path, parse: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+        parse(self.read_to_string(path)?.as_bytes());
+        Ok(())
+    }
+
+    /// Same as `metadata` for each path, the implementations can batch the
+    /// syscalls of them, see `IoUringFileSystem`.
+    fn metadata_batch(&self, paths: &[PathBuf]) -> Vec<io::Result<FileMetadata>> {
+        paths.iter().map(|path| self.metadata(path)).collect()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(long_path(path))
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(long_path(path))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let meta = long_path(path).metadata()?;
+        let file_type = meta.file_type();
+        let kind = if file_type.is_file() {
+            FileKind::File
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else {
+            FileKind::Other
+        };
+        // This field might not be available on all platforms,
+        // and will return an Err on platforms where it is not available.
+        let modified = meta.modified().ok();
+        Ok(FileMetadata { kind, modified })
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        long_path(path).read_link()
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        dunce::canonicalize(long_path(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        // join the names to `path`, so the children don't carry the prefix added by `long_path`.
+        std::fs::read_dir(long_path(path))?
+            .map(|entry| entry.map(|entry| path.join(entry.file_name())))
+            .collect()
+    }
+
+    /// `access` only checks the permission of the path, so the kernel
+    /// does not fill a `stat` buffer for it.
+    #[cfg(unix)]
+    fn exists(&self, path: &Path) -> bool {
+        use std::os::unix::ffi::OsStrExt;
+        let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: `path` is a valid C string which outlives the call.
+        unsafe { libc::access(path.as_ptr(), libc::F_OK) == 0 }
+    }
+
+    #[cfg(all(unix, feature = "mmap-description"))]
+    fn read_mapped(&self, path: &Path, parse: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+        crate::mmap::read_mapped(&long_path(path), parse)
+    }
+}
+
+/// The Win32 APIs reject the paths longer than `MAX_PATH` unless they are
+/// prefixed with `\\?\`, so the prefix is applied to such paths instead of
+/// reporting an opaque `NotFound`.
+#[cfg(windows)]
+fn long_path(path: &Path) -> Cow<'_, Path> {
+    match path.to_str().and_then(with_long_path_prefix) {
+        Some(prefixed) => Cow::Owned(PathBuf::from(prefixed)),
+        None => Cow::Borrowed(path),
+    }
+}
+
+#[cfg(not(windows))]
+fn long_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+const MAX_PATH: usize = 260;
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn with_long_path_prefix(path: &str) -> Option<String> {
+    if path.len() < MAX_PATH || path.starts_with(r"\\?\") {
+        return None;
+    }
+    // the prefix disables the normalization, so `/` is not a separator anymore.
+    let path = path.replace('/', "\\");
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        Some(format!(r"\\?\UNC\{unc}"))
+    } else if path.as_bytes().get(1) == Some(&b':') {
+        Some(format!(r"\\?\{path}"))
+    } else {
+        // relative paths can't be prefixed.
+        None
+    }
+}
+
+/// Files opened by hosts, such as the unsaved buffers in editors, take
+/// precedence over the files in `inner`. The transient errors of `inner`
+/// are retried, see `IoErrorClass::Transient`.
+#[derive(Debug)]
+pub(crate) struct OverlayFileSystem {
+    documents: DashMap<PathBuf, Arc<str>, BuildHasherDefault<FxHasher>>,
+    inner: Arc<dyn FileSystem>,
+}
+
+impl OverlayFileSystem {
+    pub fn new(inner: Arc<dyn FileSystem>) -> Self {
+        Self {
+            documents: Default::default(),
+            inner,
+        }
+    }
+
+    pub fn open_document(&self, path: PathBuf, content: Arc<str>) {
+        self.documents.insert(path, content);
+    }
+
+    pub fn close_document(&self, path: &Path) -> bool {
+        self.documents.remove(path).is_some()
+    }
+}
+
+impl FileSystem for OverlayFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.documents.get(path) {
+            Some(content) => Ok(content.as_bytes().to_vec()),
+            None => retry_transient(|| self.inner.read(path)),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.documents.get(path) {
+            Some(content) => Ok(content.to_string()),
+            None => retry_transient(|| self.inner.read_to_string(path)),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        if self.documents.contains_key(path) {
+            Ok(FileMetadata {
+                kind: FileKind::File,
+                modified: None,
+            })
+        } else {
+            retry_transient(|| self.inner.metadata(path))
+        }
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        retry_transient(|| self.inner.read_link(path))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        retry_transient(|| self.inner.canonicalize(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut children = retry_transient(|| self.inner.read_dir(path))?;
+        for document in self.documents.iter() {
+            let document = document.key();
+            if document.parent() == Some(path) && !children.contains(document) {
+                children.push(document.clone());
+            }
+        }
+        Ok(children)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.documents.contains_key(path) || self.inner.exists(path)
+    }
+
+    fn read_mapped(&self, path: &Path, parse: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+        match self.documents.get(path) {
+            Some(content) => {
+                parse(content.as_bytes());
+                Ok(())
+            }
+            None => retry_transient(|| self.inner.read_mapped(path, parse)),
+        }
+    }
+
+    fn metadata_batch(&self, paths: &[PathBuf]) -> Vec<io::Result<FileMetadata>> {
+        if self.documents.is_empty() {
+            // the transient failures of the batch are retried one by one.
+            let mut stats = self.inner.metadata_batch(paths);
+            for (path, stat) in paths.iter().zip(&mut stats) {
+                if matches!(stat, Err(error) if IoErrorClass::of(error) == IoErrorClass::Transient)
+                {
+                    *stat = retry_transient(|| self.inner.metadata(path));
+                }
+            }
+            return stats;
+        }
+        paths.iter().map(|path| self.metadata(path)).collect()
+    }
+}
+
+#[test]
+fn test_with_long_path_prefix() {
+    let long = "a".repeat(MAX_PATH);
+    assert_eq!(with_long_path_prefix(r"C:\a\b"), None);
+    assert_eq!(
+        with_long_path_prefix(&format!(r"C:\{long}/b")),
+        Some(format!(r"\\?\C:\{long}\b"))
+    );
+    assert_eq!(
+        with_long_path_prefix(&format!(r"\\server\share\{long}")),
+        Some(format!(r"\\?\UNC\server\share\{long}"))
+    );
+    assert_eq!(with_long_path_prefix(&format!(r"\\?\C:\{long}")), None);
+    assert_eq!(with_long_path_prefix(&long), None);
+}
+
+#[test]
+fn os_file_system_exists_test() {
+    let fixtures = crate::test_helper::p(vec![]);
+    let fs = OsFileSystem;
+    assert!(fs.exists(&fixtures.join("a.js")));
+    assert!(fs.exists(&fixtures.join("alias")));
+    assert!(!fs.exists(&fixtures.join("missing.js")));
+    assert!(!fs.exists(&fixtures.join("a.js").join("b")));
+    assert!(!fs.exists(&fixtures.join("a\0.js")));
+}
@@ -1,14 +1,14 @@
 use crate::{
     description::{DescriptionData, PkgJSON},
     entry::EntryStat,
-    tsconfig::TsConfig,
-    RResult,
+    file_system::{FileSystem, OsFileSystem, OverlayFileSystem},
+    Error, RResult,
 };
 use rustc_hash::FxHasher;
 use std::{
     fmt::Debug,
-    fs,
     hash::BuildHasherDefault,
+    io,
     path::{Path, PathBuf},
     sync::Arc,
     time::SystemTime,
@@ -18,8 +18,11 @@ use dashmap::DashMap;
 
 use std::time::Duration;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct CachedFS {
+    /// The IO of all the caches.
+    file_system: Arc<OverlayFileSystem>,
+
     /// Caches raw files
     entries: CachedMap<String>,
 
@@ -27,6 +30,7 @@ pub struct CachedFS {
     descriptions: CachedMap<DescriptionData>,
 
     /// Caches tsconfig.json
+    #[cfg(feature = "tsconfig")]
     tsconfigs: CachedMap<serde_json::Value>,
 }
 
@@ -62,31 +66,71 @@ impl<T: Sized> CachedEntry<T> {
 
 const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
 
+impl Default for CachedFS {
+    fn default() -> Self {
+        Self::new(Arc::new(OsFileSystem))
+    }
+}
+
 impl CachedFS {
+    pub fn new(file_system: Arc<dyn FileSystem>) -> Self {
+        Self {
+            file_system: Arc::new(OverlayFileSystem::new(file_system)),
+            entries: Default::default(),
+            descriptions: Default::default(),
+            #[cfg(feature = "tsconfig")]
+            tsconfigs: Default::default(),
+        }
+    }
+
+    pub fn file_system(&self) -> Arc<dyn FileSystem> {
+        self.file_system.clone()
+    }
+
+    pub(crate) fn overlay(&self) -> &OverlayFileSystem {
+        &self.file_system
+    }
+
     pub fn read_file(&self, path: &Path, file_stat: EntryStat) -> RResult<Arc<String>> {
         if let Some(cached) = self.entries.get(path) {
             if self.is_modified(file_stat.modified(), cached.stat.modified()) {
                 return Ok(cached.value().content());
             }
         }
-        let string = fs::read_to_string(path)?;
+        let string = self.file_system.read_to_string(path)?;
         let entry = CachedEntry::new(string, file_stat);
         self.entries.insert(path.to_path_buf(), entry.clone());
         Ok(entry.content())
     }
 
+    /// Only the fields in `retained` are kept if it is `Some`.
     pub fn read_description_file(
         &self,
         path: &Path,
         file_stat: EntryStat,
+        retained: Option<&[&str]>,
     ) -> RResult<Arc<DescriptionData>> {
         if let Some(cached) = self.descriptions.get(path) {
             if self.is_modified(file_stat.modified(), cached.stat.modified()) {
                 return Ok(cached.value().content());
             }
         }
-        let string = fs::read_to_string(path)?;
-        let json = PkgJSON::parse(&string, path)?;
+        // the content is dropped once parsed, so it is borrowed from the mapping
+        // of the large files with the `mmap-description` feature.
+        let mut parsed = None;
+        self.file_system.read_mapped(path, &mut |content| {
+            parsed = Some(match std::str::from_utf8(content) {
+                Ok(string) => PkgJSON::parse(string, path, retained),
+                Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error).into()),
+            });
+        })?;
+        // the implementations of `FileSystem` may return without parsing.
+        let json = parsed.unwrap_or_else(|| {
+            Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is read without its content", path.display()),
+            )))
+        })?;
         let dir = path.parent().unwrap().to_path_buf();
         let info = DescriptionData::new(json, dir);
         let entry = CachedEntry::new(info, file_stat);
@@ -94,6 +138,7 @@ impl CachedFS {
         Ok(entry.content())
     }
 
+    #[cfg(feature = "tsconfig")]
     pub fn read_tsconfig(
         &self,
         path: &Path,
@@ -104,13 +149,24 @@ impl CachedFS {
                 return Ok(cached.value().content());
             }
         }
-        let string = fs::read_to_string(path)?;
-        let serde_json = TsConfig::parse(&string, path)?;
+        let string = self.file_system.read_to_string(path)?;
+        let serde_json = crate::tsconfig::TsConfig::parse(&string, path)?;
         let entry = CachedEntry::new(serde_json, file_stat);
         self.tsconfigs.insert(path.to_path_buf(), entry.clone());
         Ok(entry.content())
     }
 
+    /// Drops all the descriptions, see `Cache::retain_fields`.
+    pub(crate) fn clear_descriptions(&self) {
+        self.descriptions.clear();
+    }
+
+    /// Drops the descriptions which are no longer used by any entry.
+    pub(crate) fn gc(&self) {
+        self.descriptions
+            .retain(|_, cached| cached.content.is_used());
+    }
+
     fn is_modified(&self, before: Option<SystemTime>, after: Option<SystemTime>) -> bool {
         if let (Some(before), Some(after)) = (before, after) {
             if before.duration_since(after).expect("after > before") < DEBOUNCE_INTERVAL {
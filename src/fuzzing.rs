@@ -0,0 +1,43 @@
+//! Internal functions exposed for the targets in `fuzz/`,
+//! it is not a part of public API.
+
+use crate::{
+    map::{ExportsField, Field, ImportsField},
+    parse::Request,
+    resolve::split_slash_from_request,
+    RResult,
+};
+use std::collections::HashSet;
+
+pub fn exports_field(
+    root: &serde_json::Value,
+    target: &str,
+    condition_names: &HashSet<String>,
+) -> RResult<Vec<String>> {
+    ExportsField::field_process(root, target, condition_names)
+}
+
+pub fn imports_field(
+    root: &serde_json::Value,
+    target: &str,
+    condition_names: &HashSet<String>,
+) -> RResult<Vec<String>> {
+    ImportsField::field_process(root, target, condition_names)
+}
+
+pub fn check_target(target: &str) -> Result<(), String> {
+    ExportsField::check_target(target)
+}
+
+pub fn parse_request(request: &str) -> (String, String, String) {
+    let request = Request::from_request(request);
+    (
+        request.target().to_string(),
+        request.query().to_string(),
+        request.fragment().to_string(),
+    )
+}
+
+pub fn split_request(target: &str) -> Option<(&str, &str)> {
+    split_slash_from_request(target).map(|index| target.split_at(index))
+}
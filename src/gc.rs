@@ -0,0 +1,37 @@
+use crate::Resolver;
+use std::sync::Arc;
+
+/// A build, such as a rebuild of a dev server, in which the cache entries
+/// were used, see `Resolver::gc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Generation(pub(crate) u64);
+
+impl Resolver {
+    /// Starts a new generation, the entries used after it are retained by
+    /// `gc(generation)`. It is shared by the resolvers which share the cache.
+    pub fn next_generation(&self) -> Generation {
+        self.cache.next_generation()
+    }
+
+    /// Evicts the entries which were not used since `retain_since`, so the
+    /// memory of the cache is proportional to the live module graph, for
+    /// example, `let generation = resolver.next_generation();` before a
+    /// rebuild and `resolver.gc(generation)` after it.
+    ///
+    /// Returns the number of the evicted entries.
+    pub fn gc(&self, retain_since: Generation) -> usize {
+        let before = self.entries().len();
+        // the ancestors of the retained entries are kept, because they are
+        // referenced by the children, so it is repeated until nothing is evicted.
+        loop {
+            let len = self.entries().len();
+            self.entries()
+                .retain(|_, entry| entry.touched() >= retain_since || Arc::strong_count(entry) > 1);
+            if self.entries().len() == len {
+                break;
+            }
+        }
+        self.cache.fs.gc();
+        before - self.entries().len()
+    }
+}
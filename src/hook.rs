@@ -0,0 +1,56 @@
+use crate::{Info, ResolveResult, Resolver, State};
+use std::path::{Path, PathBuf};
+
+/// The arguments of `Resolver::resolve` before the request is parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawRequest {
+    /// The directory which the request is issued from.
+    pub path: PathBuf,
+    pub request: String,
+}
+
+/// Rewrites the request before resolving, such as stripping the
+/// bundler-specific prefixes like `-!loader!` or `virtual:`.
+pub trait BeforeResolve: std::fmt::Debug + Send + Sync {
+    fn before_resolve(&self, request: &mut RawRequest);
+}
+
+/// What to do with the result, returned by `AfterResolve`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AfterResolveAction {
+    /// Keep the result.
+    Accept,
+    /// Replace the path of the result, the query and fragment are kept.
+    Rewrite(PathBuf),
+    /// Drop the result and continue with `fallback`, the resolution fails
+    /// if there is no fallback or the fallback is rejected too.
+    Reject,
+}
+
+/// Inspects the successful result before the symlinks of it are resolved,
+/// such as mapping `dist/esm` to `dist/cjs`.
+pub trait AfterResolve: std::fmt::Debug + Send + Sync {
+    fn after_resolve(&self, request: &RawRequest, resolved: &Path) -> AfterResolveAction;
+}
+
+impl Resolver {
+    /// `origin` is the info before resolving and the request which it parsed from.
+    pub(crate) fn apply_after_resolve(
+        &self,
+        state: State,
+        origin: Option<&(Info, RawRequest)>,
+    ) -> State {
+        let (Some(hook), Some((info, raw))) = (self.options.after_resolve.as_ref(), origin) else {
+            return state;
+        };
+        state.map_success(
+            |resolved| match hook.after_resolve(raw, &resolved.to_resolved_path()) {
+                AfterResolveAction::Accept => State::Success(ResolveResult::Resource(resolved)),
+                AfterResolveAction::Rewrite(path) => {
+                    State::Success(ResolveResult::Resource(resolved.with_path(path)))
+                }
+                AfterResolveAction::Reject => State::Failed(info.clone()),
+            },
+        )
+    }
+}
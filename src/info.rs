@@ -18,20 +18,65 @@ fn has_trailing_slash(p: &Path) -> bool {
     p.as_os_str().as_bytes().last() == Some(&b'/')
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// A path normalized by the rules of the resolver, which are the rules of
+/// the keys of the cache: `.` and `..` are removed lexically without
+/// following the symlinks, `..` above the root stays at the root, the
+/// trailing slash is kept, and the drive letter is uppercase on Windows.
+///
+/// The hosts should compute the ids of the modules with it, so the ids
+/// match the paths returned by the resolver.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct NormalizedPath(Arc<Path>);
 
 impl NormalizedPath {
+    #[must_use]
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         // perf: this method does not re-allocate memory if the path does not contain any dots.
         let normalized = path.as_ref().absolutize_from(Path::new("")).unwrap();
-        let path = if has_trailing_slash(path.as_ref()) {
+        let path: Arc<Path> = if has_trailing_slash(path.as_ref()) {
             Path::new(&format!("{}/", normalized.display())).into()
         } else {
             normalized.into()
         };
+        #[cfg(windows)]
+        let path = match path.to_str().and_then(with_upper_drive_letter) {
+            Some(upper) => Path::new(&upper).into(),
+            None => path,
+        };
         NormalizedPath(path)
     }
+
+    /// Joins `request` as the resolver joins the relative requests, an
+    /// absolute `request` replaces the path, and its trailing slash is kept.
+    #[must_use]
+    pub fn join(&self, request: &str) -> Self {
+        Self::new(self.0.join(request))
+    }
+
+    #[must_use]
+    pub fn as_path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Whether the path ends with a slash, such as `./dir/`, which is
+    /// resolved as a directory only.
+    #[must_use]
+    pub fn has_trailing_slash(&self) -> bool {
+        has_trailing_slash(&self.0)
+    }
+}
+
+/// `c:\a` and `C:\a` are the same file on Windows, so the drive letter
+/// is always uppercase to make the paths comparable.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn with_upper_drive_letter(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    if bytes.len() < 2 || bytes[1] != b':' || !bytes[0].is_ascii_lowercase() {
+        return None;
+    }
+    let mut path = path.to_string();
+    path[..1].make_ascii_uppercase();
+    Some(path)
 }
 
 impl AsRef<Path> for NormalizedPath {
@@ -40,7 +85,7 @@ impl AsRef<Path> for NormalizedPath {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Info {
     path: NormalizedPath,
     request: Request,
@@ -98,8 +143,33 @@ impl Info {
         if self.request.target().is_empty() || self.request.target() == "." {
             Cow::Borrowed(&self.path.0)
         } else {
-            let p = NormalizedPath::new(self.path.as_ref().join(self.request.target()));
-            Cow::Owned(p.0.to_path_buf())
+            Cow::Owned(self.path.join(self.request.target()).0.to_path_buf())
         }
     }
 }
+
+#[test]
+fn test_with_upper_drive_letter() {
+    assert_eq!(
+        with_upper_drive_letter(r"c:\a\b"),
+        Some(String::from(r"C:\a\b"))
+    );
+    assert_eq!(with_upper_drive_letter(r"C:\a"), None);
+    assert_eq!(with_upper_drive_letter("/a/b"), None);
+    assert_eq!(with_upper_drive_letter("c"), None);
+}
+
+#[cfg(not(windows))]
+#[test]
+fn normalized_path_test() {
+    let path = NormalizedPath::new("/a/./b/../c");
+    assert_eq!(path.as_path(), Path::new("/a/c"));
+    assert!(!path.has_trailing_slash());
+    assert_eq!(NormalizedPath::new("/a/../../b").as_path(), Path::new("/b"));
+
+    let dir = path.join("./d/");
+    assert_eq!(dir.as_path().to_str(), Some("/a/c/d/"));
+    assert!(dir.has_trailing_slash());
+    assert_eq!(dir.join("../e").as_path(), Path::new("/a/c/e"));
+    assert_eq!(dir.join("/f").as_path(), Path::new("/f"));
+}
@@ -0,0 +1,111 @@
+use std::{io, thread, time::Duration};
+
+/// The class of an IO error of the file system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum IoErrorClass {
+    /// The path does not exist, or one of its ancestors is not a directory.
+    NotFound,
+    /// The path exists, but it can't be accessed.
+    PermissionDenied,
+    /// The error may not happen again, such as too many open files or a
+    /// busy network file system, it was retried before it is reported.
+    Transient,
+    Other,
+}
+
+impl IoErrorClass {
+    #[must_use]
+    pub fn of(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => return Self::NotFound,
+            io::ErrorKind::PermissionDenied => return Self::PermissionDenied,
+            io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                return Self::Transient
+            }
+            _ => {}
+        }
+        match error.raw_os_error() {
+            Some(code) if NOT_FOUND.contains(&code) => Self::NotFound,
+            Some(code) if TRANSIENT.contains(&code) => Self::Transient,
+            _ => Self::Other,
+        }
+    }
+}
+
+#[cfg(unix)]
+const NOT_FOUND: &[i32] = &[libc::ENOTDIR];
+#[cfg(unix)]
+const TRANSIENT: &[i32] = &[
+    libc::EAGAIN,
+    libc::EBUSY,
+    libc::EMFILE,
+    libc::ENFILE,
+    libc::ESTALE,
+];
+
+/// `ERROR_DIRECTORY`.
+#[cfg(windows)]
+const NOT_FOUND: &[i32] = &[267];
+/// `ERROR_SHARING_VIOLATION`, `ERROR_LOCK_VIOLATION`, `ERROR_UNEXP_NET_ERR`
+/// and `ERROR_NETNAME_DELETED`.
+#[cfg(windows)]
+const TRANSIENT: &[i32] = &[32, 33, 59, 64];
+
+#[cfg(not(any(unix, windows)))]
+const NOT_FOUND: &[i32] = &[];
+#[cfg(not(any(unix, windows)))]
+const TRANSIENT: &[i32] = &[];
+
+/// The times a transient error is retried, the delay is doubled each time.
+const RETRIES: u32 = 3;
+const FIRST_DELAY: Duration = Duration::from_millis(1);
+
+/// Calls `io` again while it fails with a transient error.
+pub(crate) fn retry_transient<T>(mut io: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = FIRST_DELAY;
+    for _ in 0..RETRIES {
+        match io() {
+            Err(error) if IoErrorClass::of(&error) == IoErrorClass::Transient => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    io()
+}
+
+#[test]
+fn io_error_class_test() {
+    let class = |kind| IoErrorClass::of(&io::Error::from(kind));
+    assert_eq!(class(io::ErrorKind::NotFound), IoErrorClass::NotFound);
+    assert_eq!(
+        class(io::ErrorKind::PermissionDenied),
+        IoErrorClass::PermissionDenied
+    );
+    assert_eq!(class(io::ErrorKind::Interrupted), IoErrorClass::Transient);
+    assert_eq!(class(io::ErrorKind::InvalidData), IoErrorClass::Other);
+    #[cfg(unix)]
+    {
+        let class = |code| IoErrorClass::of(&io::Error::from_raw_os_error(code));
+        assert_eq!(class(libc::ENOTDIR), IoErrorClass::NotFound);
+        assert_eq!(class(libc::ENFILE), IoErrorClass::Transient);
+        assert_eq!(class(libc::EACCES), IoErrorClass::PermissionDenied);
+    }
+
+    let mut calls = 0;
+    let result = retry_transient(|| {
+        calls += 1;
+        match calls {
+            1 | 2 => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+            _ => Ok(calls),
+        }
+    });
+    assert_eq!(result.unwrap(), 3);
+    let result: io::Result<()> = retry_transient(|| Err(io::Error::from(io::ErrorKind::TimedOut)));
+    assert_eq!(
+        IoErrorClass::of(&result.unwrap_err()),
+        IoErrorClass::Transient
+    );
+}
@@ -0,0 +1,227 @@
+use crate::{FileKind, FileMetadata, FileSystem, OsFileSystem};
+use ::io_uring::{opcode, types, IoUring};
+use std::{
+    ffi::CString,
+    io,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// The number of the submissions in a batch.
+const RING_ENTRIES: u32 = 256;
+
+/// A `FileSystem` of Linux which batches the stats by `io_uring`, so a batch
+/// of stats, such as the children listed by `Resolver::warm_up`, costs one
+/// syscall instead of one per file.
+///
+/// The others are the same as `OsFileSystem`, which is also used if
+/// `io_uring` is not available, such as it is disabled by the kernel or
+/// by the seccomp filters of containers, or once the ring failed.
+pub struct IoUringFileSystem {
+    /// `None` if `io_uring` is not available, or the ring failed and was
+    /// torn down, see `statx_batch`.
+    ring: Mutex<Option<IoUring>>,
+}
+
+impl std::fmt::Debug for IoUringFileSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IoUringFileSystem")
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+impl Default for IoUringFileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoUringFileSystem {
+    pub fn new() -> Self {
+        let ring = match IoUring::new(RING_ENTRIES) {
+            Ok(ring) => Some(ring),
+            Err(error) => {
+                tracing::debug!("io_uring is not available, fall back to the stats: {error}");
+                None
+            }
+        };
+        Self {
+            ring: Mutex::new(ring),
+        }
+    }
+
+    /// Whether `io_uring` is used, `false` if it fell back to `OsFileSystem`.
+    pub fn is_enabled(&self) -> bool {
+        self.ring.lock().unwrap().is_some()
+    }
+
+    /// Stats `paths`, which should not be more than the entries of the ring.
+    ///
+    /// It returns the error if the ring failed before all the submissions
+    /// were completed, the kernel may still write into their buffers then,
+    /// so the buffers are leaked, and the ring should be torn down, as the
+    /// submissions which are not completed would be read by the next batch.
+    fn statx_batch(
+        ring: &mut IoUring,
+        paths: &[PathBuf],
+    ) -> io::Result<Vec<io::Result<FileMetadata>>> {
+        let names: Vec<Option<CString>> = paths
+            .iter()
+            .map(|path| CString::new(path.as_os_str().as_bytes()).ok())
+            .collect();
+        // SAFETY: `statx` is plain data.
+        let mut buffers: Vec<libc::statx> = vec![unsafe { std::mem::zeroed() }; paths.len()];
+        let mut submitted = 0;
+        {
+            let mut sq = ring.submission();
+            for (index, (name, buffer)) in names.iter().zip(buffers.iter_mut()).enumerate() {
+                let Some(name) = name else {
+                    continue;
+                };
+                let entry = opcode::Statx::new(
+                    types::Fd(libc::AT_FDCWD),
+                    name.as_ptr(),
+                    (buffer as *mut libc::statx).cast::<types::statx>(),
+                )
+                .mask(libc::STATX_TYPE | libc::STATX_MTIME)
+                .build()
+                .user_data(index as u64);
+                // SAFETY: `name` and `buffer` are alive until the entry is
+                // completed, or they are leaked if the ring fails.
+                unsafe { sq.push(&entry) }.expect("the batch fits in the ring");
+                submitted += 1;
+            }
+        }
+        let mut results = vec![-libc::ENOENT; paths.len()];
+        let mut completed = 0;
+        while completed < submitted {
+            match ring.submit_and_wait(submitted - completed) {
+                Ok(_) => {}
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    std::mem::forget(names);
+                    std::mem::forget(buffers);
+                    return Err(error);
+                }
+            }
+            for cqe in ring.completion() {
+                if let Some(result) = results.get_mut(cqe.user_data() as usize) {
+                    *result = cqe.result();
+                }
+                completed += 1;
+            }
+        }
+        Ok(results
+            .into_iter()
+            .zip(buffers)
+            .zip(paths)
+            .map(|((result, buffer), path)| match result {
+                0 => Ok(to_metadata(&buffer)),
+                // the kernels before 5.6 don't support `statx` in the ring.
+                error if -error == libc::EINVAL => OsFileSystem.metadata(path),
+                error => Err(io::Error::from_raw_os_error(-error)),
+            })
+            .collect())
+    }
+}
+
+fn to_metadata(statx: &libc::statx) -> FileMetadata {
+    let kind = match u32::from(statx.stx_mode) & libc::S_IFMT {
+        libc::S_IFREG => FileKind::File,
+        libc::S_IFDIR => FileKind::Dir,
+        _ => FileKind::Other,
+    };
+    let modified = (statx.stx_mask & libc::STATX_MTIME != 0).then(|| {
+        let time = statx.stx_mtime;
+        let nanos = Duration::from_nanos(u64::from(time.tv_nsec));
+        if time.tv_sec >= 0 {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(time.tv_sec as u64) + nanos
+        } else {
+            SystemTime::UNIX_EPOCH - Duration::from_secs(time.tv_sec.unsigned_abs()) + nanos
+        }
+    });
+    FileMetadata { kind, modified }
+}
+
+impl FileSystem for IoUringFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        OsFileSystem.read(path)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        OsFileSystem.read_to_string(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        OsFileSystem.metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        OsFileSystem.read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        OsFileSystem.canonicalize(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        OsFileSystem.read_dir(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        OsFileSystem.exists(path)
+    }
+
+    fn read_mapped(&self, path: &Path, parse: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+        OsFileSystem.read_mapped(path, parse)
+    }
+
+    fn metadata_batch(&self, paths: &[PathBuf]) -> Vec<io::Result<FileMetadata>> {
+        let mut ring = self.ring.lock().unwrap();
+        let Some(entries) = ring.as_ref().map(|ring| ring.params().sq_entries()) else {
+            return paths.iter().map(|path| self.metadata(path)).collect();
+        };
+        let mut metadata = Vec::with_capacity(paths.len());
+        for chunk in paths.chunks(entries as usize) {
+            let batch = match ring.as_mut() {
+                Some(uring) => Self::statx_batch(uring, chunk).map_err(|error| {
+                    tracing::debug!("io_uring failed, fall back to the stats: {error}");
+                    *ring = None;
+                }),
+                None => Err(()),
+            };
+            match batch {
+                Ok(batch) => metadata.extend(batch),
+                Err(()) => metadata.extend(chunk.iter().map(|path| self.metadata(path))),
+            }
+        }
+        metadata
+    }
+}
+
+#[test]
+fn metadata_batch_test() {
+    let fixtures = crate::test_helper::p(vec![]);
+    let paths: Vec<PathBuf> = ["a.js", "alias", "missing.js", "a.js/b"]
+        .iter()
+        .map(|name| fixtures.join(name))
+        .chain(std::iter::once(PathBuf::from("a\0b")))
+        .collect();
+    let fs = IoUringFileSystem::new();
+    let batched = fs.metadata_batch(&paths);
+    assert_eq!(batched.len(), paths.len());
+    for (path, batched) in paths.iter().zip(batched) {
+        let expected = OsFileSystem.metadata(path);
+        match (batched, expected) {
+            (Ok(batched), Ok(expected)) => {
+                assert_eq!(batched.kind, expected.kind, "{path:?}");
+                assert_eq!(batched.modified, expected.modified, "{path:?}");
+            }
+            (Err(_), Err(_)) => {}
+            (batched, expected) => panic!("{path:?}: {batched:?} != {expected:?}"),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    context::Context,
+    description::DescriptionData,
+    resolve::get_module_name_from_request,
+    semver::{self, Version},
+    Info, NormalizedPath, Resolver, State,
+};
+use std::path::{Path, PathBuf};
+
+/// The directories of the isolated stores, such as `node_modules/.pnpm` of
+/// pnpm and `node_modules/.store` of the linked installs of npm.
+const STORES: [&str; 2] = [".pnpm", ".store"];
+
+/// The fields whose dependencies can be required by the package.
+const DEPENDENCIES: [&str; 3] = ["dependencies", "peerDependencies", "optionalDependencies"];
+
+/// The store which `path` is inside, such as `node_modules/.pnpm`.
+fn store_of(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|dir| {
+        dir.file_name()
+            .map_or(false, |name| STORES.iter().any(|store| name == *store))
+            && dir
+                .parent()
+                .and_then(Path::file_name)
+                .map_or(false, |name| name == "node_modules")
+    })
+}
+
+/// The range of `name` declared by the package, such as `^17.0.0`.
+fn declared_range<'a>(pkg_info: &'a DescriptionData, name: &str) -> Option<&'a str> {
+    let raw = pkg_info.data().raw();
+    DEPENDENCIES
+        .iter()
+        .find_map(|field| raw.get(field)?.get(name)?.as_str())
+}
+
+impl Resolver {
+    /// Looks up the module request issued inside an isolated store by the
+    /// dependencies declared by the issuer, see `Options::isolated_mode`.
+    /// It is `Resolving` if the request is not issued inside a store, or the
+    /// declared range is not a version range, such as `workspace:*`.
+    pub(crate) fn apply_isolated_mode(&self, info: Info, context: &mut Context) -> State {
+        if !self.options.isolated_mode || context.finding_up_modules.get() {
+            return State::Resolving(info);
+        }
+        let issuer = info.normalized_path().as_ref();
+        let Some(store) = store_of(issuer) else {
+            return State::Resolving(info);
+        };
+        let pkg_info = match self.load_entry(issuer).pkg_info(self) {
+            Ok(Some(pkg_info)) => pkg_info.clone(),
+            Ok(None) => return State::Resolving(info),
+            Err(error) => return State::Error(error),
+        };
+        let name = get_module_name_from_request(info.request().target());
+        if pkg_info.data().name() == Some(name) {
+            return State::Resolving(info);
+        }
+        let Some(range) = declared_range(&pkg_info, name) else {
+            tracing::debug!(
+                "'{name}' is not declared by '{}', it is not looked up in the isolated mode",
+                pkg_info.dir().as_ref().display()
+            );
+            return State::Failed(info);
+        };
+        // such as `workspace:*` and `link:../a`, which are not installed in the store.
+        if semver::satisfies(&Version::parse("0.0.0").unwrap(), range).is_none() {
+            return State::Resolving(info);
+        }
+        let Some(dir) = self.isolated_dir(issuer, store, name, range) else {
+            return State::Failed(info);
+        };
+        let dir = NormalizedPath::new(dir);
+        let modules = dir.as_ref().join("node_modules");
+        let redirected = info.clone().with_path(&dir);
+        match self._resolve_as_modules(redirected, &dir, &modules, context) {
+            State::Resolving(_) => State::Failed(info),
+            state => state,
+        }
+    }
+
+    /// The directory whose `node_modules` holds the copy of `name` which
+    /// satisfies `range`, the nearest one of the ancestors of `issuer` comes
+    /// first, then the highest one in `store`.
+    fn isolated_dir(
+        &self,
+        issuer: &Path,
+        store: &Path,
+        name: &str,
+        range: &str,
+    ) -> Option<PathBuf> {
+        let satisfied = |copy: &Path| {
+            let entry = self.load_entry(copy);
+            let pkg_info = entry.pkg_info(self).ok()?.as_ref()?;
+            if pkg_info.dir().as_ref() != copy {
+                return None;
+            }
+            let version = pkg_info.data().raw().get("version")?.as_str()?;
+            let version = Version::parse(version)?;
+            semver::satisfies(&version, range)?.then_some(version)
+        };
+        let nearest = issuer
+            .ancestors()
+            .take_while(|dir| dir.starts_with(store))
+            .find(|dir| satisfied(&dir.join("node_modules").join(name)).is_some());
+        if let Some(dir) = nearest {
+            return Some(dir.to_path_buf());
+        }
+        // `@scope/name` is stored as `@scope+name@1.0.0`.
+        let prefix = format!("{}@", name.replace('/', "+"));
+        let children = self.cache.fs.file_system().read_dir(store).ok()?;
+        children
+            .into_iter()
+            .filter(|child| {
+                child
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| name.starts_with(&prefix))
+            })
+            .filter_map(|child| {
+                let version = satisfied(&child.join("node_modules").join(name))?;
+                Some((version, child))
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, child)| child)
+    }
+}
+
+#[test]
+fn store_of_test() {
+    let store = Path::new("/app/node_modules/.pnpm");
+    assert_eq!(
+        store_of(&store.join("a@1.0.0/node_modules/a/lib")),
+        Some(store)
+    );
+    assert_eq!(store_of(Path::new("/app/node_modules/a/lib")), None);
+    assert_eq!(store_of(Path::new("/app/.pnpm/a")), None);
+}
@@ -40,55 +40,211 @@
 //! ```
 //!
 
+mod archive;
+mod arena;
+mod bin;
 mod cache;
+mod cancel;
+mod coalesce;
 mod context;
+mod context_module;
+pub mod core;
+mod debug_bundle;
 mod description;
+mod dir_resolver;
+mod dir_snapshot;
+mod duplicate;
 mod entry;
 mod error;
+mod file_system;
 mod fs;
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
+mod gc;
+mod hook;
 mod info;
+mod io_error;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod io_uring;
+mod isolated;
 mod kind;
+mod loader;
+#[cfg(feature = "excerpt")]
+mod locate;
+mod lockfile;
 mod log;
 mod map;
+mod matrix;
+mod metrics;
+#[cfg(all(unix, feature = "mmap-description"))]
+mod mmap;
+mod module_type;
+mod modules_paths;
+mod node_path;
+mod observer;
 mod options;
+#[cfg(feature = "oracle")]
+pub mod oracle;
+mod overrides;
+mod package_entries;
+mod package_store;
 mod parse;
 mod plugin;
+mod pool;
+mod protocol;
+mod provenance;
+mod render;
 mod resolve;
+mod resolver_factory;
 mod resource;
+mod root_package;
+mod roots;
+mod sandbox;
+mod semver;
+#[cfg(feature = "serde")]
+mod serialize;
 mod state;
+mod suspicious;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(feature = "tsconfig")]
 mod tsconfig;
+#[cfg(feature = "tsconfig")]
 mod tsconfig_path;
+mod walk;
+mod warm_up;
 
+pub use archive::ZipFileSystem;
 pub use cache::Cache;
-use context::Context;
-pub use description::DescriptionData;
+pub use cancel::CancelToken;
+use context::{Context, Visited};
+pub use context_module::{ContextFilter, ContextOptions};
+pub use core::{evaluate_exports, evaluate_imports};
+pub use debug_bundle::DebugBundle;
+pub use description::{DescriptionData, PkgJSON};
+use dir_resolver::DirContext;
+pub use dir_resolver::DirResolver;
+pub use dir_snapshot::DirSnapshotFileSystem;
+use duplicate::PackageCollector;
+pub use duplicate::{DuplicatePackage, PackageInstance};
+pub use entry::{EntryRef, Symlink};
 pub use error::Error;
+pub use file_system::{FileKind, FileMetadata, FileSystem, OsFileSystem};
+pub use gc::Generation;
+pub use hook::{AfterResolve, AfterResolveAction, BeforeResolve, RawRequest};
 use info::Info;
+pub use info::NormalizedPath;
+pub use io_error::IoErrorClass;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub use io_uring::IoUringFileSystem;
 use kind::PathKind;
+pub use loader::{LoaderPrefix, LoaderResolution};
 use log::{color, depth};
+pub use map::{ExportsField, Field, ImportsField, MappingEntry};
+pub use matrix::{ConditionSensitivity, DualPackageHazard};
+pub use metrics::{Metrics, MetricsSnapshot};
+pub use module_type::{JsFormat, ModuleType};
+pub use modules_paths::ModulesPath;
+pub use observer::Observer;
 use options::EnforceExtension::{Auto, Disabled, Enabled};
-pub use options::{AliasMap, EnforceExtension, Options};
+#[cfg(feature = "tsconfig")]
+pub use options::TsconfigPathsOrder;
+pub use options::{
+    Algorithm, AliasMap, DescriptionRetention, DotRequests, EnforceExtension,
+    ExtensionConflictPolicy, LoaderSyntax, MalformedDescription, MalformedDescriptionCallback,
+    NestedMainFields, Options, PathStyle, ProbeOrder,
+};
+pub use package_entries::PackageEntry;
+pub use package_store::{JsrProtocol, NpmProtocol};
+use parse::Request;
+pub use parse::StructuredRequest;
 use plugin::{
     AliasPlugin, BrowserFieldPlugin, ImportsFieldPlugin, ParsePlugin, Plugin, PreferRelativePlugin,
     SymlinkPlugin,
 };
-pub use resource::Resource;
+pub use pool::{ResolverHandle, ResolverPool};
+pub use protocol::{
+    split_protocol, FileProtocol, NodeProtocol, ProtocolHandler, ProtocolResolution,
+};
+pub use provenance::{Lookup, Provenance, Stage};
+pub use render::{ErrorRenderer, ErrorReport, Excerpt, PrettyRenderer};
+pub use resolver_factory::{OptionsDelta, ResolverCreated, ResolverFactory};
+pub use resource::{Resource, ResultKind, LEGACY_MAIN_FIELDS};
+pub use sandbox::SandboxedResult;
 use state::State;
+pub use suspicious::{SuspiciousAction, SuspiciousResult};
+pub use walk::WalkExclude;
+pub use warm_up::{WarmUpCallback, WarmUpProgress};
 
 #[derive(Debug)]
 pub struct Resolver {
     pub options: Options,
     pub(crate) cache: std::sync::Arc<Cache>,
+    pub(crate) packages: PackageCollector,
+    pub(crate) metrics: Metrics,
+    /// The options before they are overridden by the algorithm.
+    pub(crate) raw_options: Options,
+    /// The normalized `options.roots`, see `roots::normalize`.
+    pub(crate) roots: Vec<std::path::PathBuf>,
+    /// See `Options::use_node_path`.
+    pub(crate) global_folders: Vec<std::path::PathBuf>,
+    /// See `Options::coalesce_requests`.
+    pub(crate) in_flight: coalesce::InFlight,
+    /// See `Options::package_overrides`.
+    pub(crate) overrides: once_cell::sync::OnceCell<overrides::Overrides>,
+    /// The entries of a sandboxed resolver which shares `external_cache`,
+    /// they are linked to the blocked ancestors, so they are kept out of the
+    /// shared ones, see `Resolver::entries`.
+    pub(crate) sandbox_entries: Option<cache::Entries>,
+    /// The resolvers of `resolve_matrix` by the sorted condition names.
+    pub(crate) by_conditions: dashmap::DashMap<
+        Vec<String>,
+        std::sync::Arc<Resolver>,
+        std::hash::BuildHasherDefault<rustc_hash::FxHasher>,
+    >,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ResolveResult<T: Clone> {
     Resource(T),
     Ignored,
+    /// An optional peer dependency of the issuer which is not installed,
+    /// see `Options::report_optional_peers`.
+    OptionalMissing,
 }
 
 pub type RResult<T> = Result<T, Error>;
 
+/// How a request is resolved by `Resolver::resolve_once`.
+struct ResolveCall<'a> {
+    /// The request before it is joined, see `Resolver::resolve_request`.
+    split: Option<&'a StructuredRequest>,
+    /// The context derived from the directory ahead, see `DirResolver`.
+    dir: Option<&'a DirContext>,
+    /// Reused instead of allocating a context, see `ResolverPool`.
+    scratch: Option<&'a mut Context>,
+    /// Aborts the resolution, see `CancelToken`.
+    cancel: Option<&'a CancelToken>,
+    /// What the caller needs from the result.
+    kind: ResultKind,
+}
+
+impl ResolveCall<'_> {
+    fn new(kind: ResultKind) -> Self {
+        Self {
+            split: None,
+            dir: None,
+            scratch: None,
+            cancel: None,
+            kind,
+        }
+    }
+}
+
 impl Resolver {
     #[must_use]
     pub fn new(options: Options) -> Self {
@@ -99,7 +255,38 @@ impl Resolver {
         } else {
             std::sync::Arc::new(Cache::default())
         };
+        let roots = roots::normalize(&options.roots, cache.fs.file_system().as_ref());
+        let global_folders = if options.use_node_path {
+            node_path::global_folders()
+        } else {
+            vec![]
+        };
+        Self::assemble(options, cache, roots, global_folders)
+    }
+
+    /// Creates a resolver which shares the cache with this one, with
+    /// `options` derived from `raw_options`, so `roots` and `global_folders`
+    /// are reused instead of being normalized and scanned again.
+    fn derive(&self, options: Options) -> Self {
+        Self::assemble(
+            Options {
+                external_cache: Some(self.cache.clone()),
+                ..options
+            },
+            self.cache.clone(),
+            self.roots.clone(),
+            self.global_folders.clone(),
+        )
+    }
 
+    fn assemble(
+        options: Options,
+        cache: std::sync::Arc<Cache>,
+        roots: Vec<std::path::PathBuf>,
+        global_folders: Vec<std::path::PathBuf>,
+    ) -> Self {
+        let raw_options = options.clone();
+        let options = options.algorithm.apply(options);
         let enforce_extension = match options.enforce_extension {
             Auto => {
                 if options.extensions.iter().any(|ext| ext.is_empty()) {
@@ -111,11 +298,68 @@ impl Resolver {
             _ => options.enforce_extension,
         };
 
+        let workspace_root = options.workspace_root.as_deref().map(roots::absolute);
+        let root_package = options.root_package.as_deref().map(roots::absolute);
+        let stop_at = options.stop_at.as_deref().map(roots::absolute);
+        let description_search_roots = options
+            .description_search_roots
+            .iter()
+            .map(|root| roots::absolute(root))
+            .collect();
         let options = Options {
             enforce_extension,
+            workspace_root,
+            root_package,
+            stop_at,
+            description_search_roots,
             ..options
         };
-        Self { options, cache }
+        let sandbox_entries =
+            (options.sandbox && options.external_cache.is_some()).then(Default::default);
+        let resolver = Self {
+            options,
+            cache,
+            packages: Default::default(),
+            metrics: Default::default(),
+            raw_options,
+            roots,
+            global_folders,
+            in_flight: Default::default(),
+            overrides: Default::default(),
+            sandbox_entries,
+            by_conditions: Default::default(),
+        };
+        if let Some(fields) = resolver.resolution_fields() {
+            resolver.cache.retain_fields(&fields);
+        }
+        resolver
+    }
+
+    /// Creates a resolver which shares the cache with this one, but resolves
+    /// with `algorithm`, such as resolving `import` and `require()` of the
+    /// same project.
+    #[must_use]
+    pub fn with_algorithm(&self, algorithm: Algorithm) -> Self {
+        self.derive(Options {
+            algorithm,
+            ..self.raw_options.clone()
+        })
+    }
+
+    /// Creates a resolver which shares the cache with this one, but resolves
+    /// with `added` and without `removed` in `condition_names`, such as
+    /// `--conditions=development` of Node.js.
+    ///
+    /// The collected packages and the metrics are its own.
+    #[must_use]
+    pub fn clone_with_conditions(&self, added: &[&str], removed: &[&str]) -> Self {
+        let mut condition_names = self.options.condition_names.clone();
+        condition_names.retain(|condition| !removed.contains(&condition.as_str()));
+        condition_names.extend(added.iter().map(|condition| condition.to_string()));
+        self.derive(Options {
+            condition_names,
+            ..self.raw_options.clone()
+        })
     }
 
     pub fn resolve(
@@ -123,30 +367,208 @@ impl Resolver {
         path: &std::path::Path,
         request: &str,
     ) -> RResult<ResolveResult<Resource>> {
+        self.resolve_in(path, request, None, None, None)
+    }
+
+    /// Same as `resolve`, but the request is split by the host, so the `?`
+    /// and the `#` in the specifier are not parsed again, and the fragment is
+    /// not tried as a part of the path, as `resolve` does for `./a#b`.
+    ///
+    /// It is parsed again if `Options::before_resolve` rewrites it or a
+    /// protocol handler delegates it. The requests are not coalesced, see
+    /// `Options::coalesce_requests`, as the joined strings are ambiguous.
+    pub fn resolve_request(
+        &self,
+        path: &std::path::Path,
+        request: &StructuredRequest,
+    ) -> RResult<ResolveResult<Resource>> {
+        let call = ResolveCall {
+            split: Some(request),
+            ..ResolveCall::new(ResultKind::FullMetadata)
+        };
+        self.resolve_once(path, &request.to_string(), call)
+    }
+
+    /// Same as `resolve`, but only produces the parts of the result which
+    /// `kind` needs, see `ResultKind`. The requests are not coalesced, see
+    /// `Options::coalesce_requests`, as the results of the other kinds
+    /// differ.
+    pub fn resolve_with_kind(
+        &self,
+        path: &std::path::Path,
+        request: &str,
+        kind: ResultKind,
+    ) -> RResult<ResolveResult<Resource>> {
+        self.resolve_once(path, request, ResolveCall::new(kind))
+    }
+
+    /// `dir` is the context derived from `path` ahead, see `DirResolver`.
+    /// `scratch` is reused instead of allocating a context, see `ResolverPool`.
+    /// `cancel` aborts the resolution, see `CancelToken`.
+    pub(crate) fn resolve_in(
+        &self,
+        path: &std::path::Path,
+        request: &str,
+        dir: Option<&DirContext>,
+        scratch: Option<&mut Context>,
+        cancel: Option<&CancelToken>,
+    ) -> RResult<ResolveResult<Resource>> {
+        let call = ResolveCall {
+            dir,
+            scratch,
+            cancel,
+            ..ResolveCall::new(ResultKind::FullMetadata)
+        };
+        if !self.options.coalesce_requests {
+            return self.resolve_once(path, request, call);
+        }
+        let (result, shared) = self
+            .in_flight
+            .coalesce(path, request, || self.resolve_once(path, request, call));
+        if shared {
+            self.metrics.record_coalesced();
+        }
+        result
+    }
+
+    fn resolve_once(
+        &self,
+        path: &std::path::Path,
+        request: &str,
+        call: ResolveCall,
+    ) -> RResult<ResolveResult<Resource>> {
+        let ResolveCall {
+            split,
+            dir,
+            scratch,
+            cancel,
+            kind,
+        } = call;
+        // the rewritten request is used in the rest of resolving, including the events.
+        let raw = self.options.before_resolve.as_ref().map(|hook| {
+            let mut raw = RawRequest {
+                path: path.to_path_buf(),
+                request: request.to_string(),
+            };
+            hook.before_resolve(&mut raw);
+            raw
+        });
+        let joined = request;
+        let (path, request) = match raw.as_ref() {
+            Some(raw) => (raw.path.as_path(), raw.request.as_str()),
+            None => (path, request),
+        };
+        let request = self.apply_loader_syntax(path, request)?;
+        let split = split.filter(|_| request == joined);
+        let decoded = match self.options.algorithm {
+            Algorithm::NodeEsm => Some(Request::decode_url(request)?),
+            _ => None,
+        };
+        let request = decoded.as_deref().unwrap_or(request);
+        // the requests with a registered protocol, such as `node:fs`, are resolved by the handler.
+        let (protocol, delegated_path, delegated, handled) =
+            match self.handle_protocol(path, request) {
+                Some((protocol, protocol::Handled::Delegated(path, request))) => {
+                    (Some(protocol), path, Some(request), None)
+                }
+                Some((protocol, protocol::Handled::Finished(state))) => {
+                    (Some(protocol), None, None, Some(state))
+                }
+                None => (None, None, None, None),
+            };
+        let path = delegated_path.as_deref().unwrap_or(path);
+        let request = delegated.as_deref().unwrap_or(request);
         tracing::debug!(
             "{:-^30}\nTry to resolve '{}' in '{}'",
             color::green(&"[RESOLVER]"),
             color::cyan(&request),
             color::cyan(&path.display().to_string())
         );
-        // let start = std::time::Instant::now();
-        let parsed = Self::parse(request);
-        let info = Info::new(path, parsed);
-        let mut context = Context::new(
-            self.options.fully_specified,
-            self.options.resolve_to_context,
-        );
-        let result = if let Some(tsconfig_location) = self.options.tsconfig.as_ref() {
-            self._resolve_with_tsconfig(info, tsconfig_location, &mut context)
-        } else {
-            self._resolve(info, &mut context)
+        if let Some(observer) = self.options.observer.as_ref() {
+            observer.on_resolve_start(path, request);
+        }
+        let start = std::time::Instant::now();
+        let split = split.filter(|_| delegated.is_none());
+        let parsed = match split {
+            Some(split) => {
+                let specifier = match self.options.algorithm {
+                    Algorithm::NodeEsm => Request::decode_url(&split.specifier)?,
+                    _ => split.specifier.as_str().into(),
+                };
+                Request::from_parts(&specifier, &split.query, &split.fragment)
+            }
+            None => Self::parse(request),
+        };
+        let parsed = self.apply_dot_requests(parsed)?;
+        // the context is stale if the directory was rewritten by `before_resolve`
+        // or a protocol handler.
+        let dir = dir.filter(|dir| {
+            raw.as_ref().map_or(true, |raw| raw.path == dir.path) && delegated_path.is_none()
+        });
+        let info = match dir {
+            Some(dir) => Info::from(dir.normalized.clone()).with_request(parsed),
+            None => Info::new(path, parsed),
+        };
+        let origin = self.options.after_resolve.as_ref().map(|_| {
+            let raw = RawRequest {
+                path: path.to_path_buf(),
+                request: request.to_string(),
+            };
+            (info.clone(), raw)
+        });
+        let mut owned;
+        let context = match scratch {
+            Some(scratch) => {
+                scratch.reset(
+                    self.options.fully_specified,
+                    self.options.resolve_to_context,
+                );
+                scratch
+            }
+            None => {
+                owned = Context::new(
+                    self.options.fully_specified,
+                    self.options.resolve_to_context,
+                );
+                &mut owned
+            }
+        };
+        context.cancel = cancel.cloned();
+        context.result_kind = kind;
+        context.split_by_host = split.is_some();
+        context.timeout = self.options.timeout.map(|timeout| (start, timeout));
+        context.issuer_pkg_info = match dir {
+            Some(dir) => dir.issuer_pkg_info.clone(),
+            None => self.issuer_pkg_info(path),
         };
+        let result = match handled {
+            Some(state) => state,
+            None => self.resolve_with_tsconfig(info, context),
+        };
+        if let Some(protocol) = protocol {
+            context.record_stage(&result, Stage::Protocol, protocol);
+        }
+        let result = self.apply_after_resolve(result, origin.as_ref());
 
         let result = result.map_failed(|info| {
-            type FallbackPlugin<'a> = AliasPlugin<'a>;
-            FallbackPlugin::new(&self.options.fallback).apply(self, info, &mut context)
+            // the stages recorded before failing are not the provenance of the fallback.
+            context.stage = None;
+            context.extension = None;
+            let result = AliasPlugin::fallback(&self.options.fallback).apply(self, info, context);
+            self.apply_after_resolve(result, origin.as_ref())
+        });
+        let mut logical_path = None;
+        let full = kind == ResultKind::FullMetadata;
+        let result = result.map_success(|info| {
+            if self.options.dedupe_by_realpath && full {
+                logical_path = Some(info.normalized_path().as_ref().to_path_buf());
+            }
+            // the symlinks can't fail the resolution if they can escape the roots.
+            if kind == ResultKind::Existence && self.options.allow_symlink_escape {
+                return State::Success(ResolveResult::Resource(info));
+            }
+            SymlinkPlugin::apply(self, info, context)
         });
-        let result = result.map_success(|info| SymlinkPlugin::apply(self, info, &mut context));
 
         // let duration = start.elapsed().as_millis();
         // println!("time cost: {:?} us", duration); // us
@@ -159,15 +581,107 @@ impl Resolver {
         //     );
         // }
 
-        match result {
+        let result = match result {
             State::Success(ResolveResult::Ignored) => Ok(ResolveResult::Ignored),
+            State::Success(ResolveResult::OptionalMissing) => Ok(ResolveResult::OptionalMissing),
             State::Success(ResolveResult::Resource(info)) => {
-                let resource = Resource::new(info, self);
-                Ok(ResolveResult::Resource(resource))
+                let real = info.normalized_path().as_ref().to_path_buf();
+                let mut resource = Resource::new(info, self, kind);
+                if full {
+                    resource.main_field = context.main_field.take();
+                    resource.provenance = context.take_provenance();
+                    resource.symlink_chain = std::mem::take(&mut context.symlink_chain);
+                }
+                if let Some(logical_path) = logical_path {
+                    let real_path = entry::real_path(&resource.symlink_chain, &logical_path);
+                    resource.logical_path = Some(self.output_path(logical_path));
+                    resource.real_path = Some(self.output_path(real_path));
+                }
+                if let Some(description) = resource.description.as_ref() {
+                    self.packages.record(description);
+                }
+                self.check_suspicious(path, request, &real, &resource)
+                    .map(|_| ResolveResult::Resource(resource))
             }
             State::Error(err) => Err(err),
+            State::Resolving(_) | State::Failed(_) if self.is_optional_peer(request, context) => {
+                Ok(ResolveResult::OptionalMissing)
+            }
+            State::Resolving(_) | State::Failed(_) if context.stopped_at_boundary => {
+                let stop_at = self.options.stop_at.as_deref().unwrap_or(path);
+                Err(Error::StoppedAt {
+                    request: request.to_string(),
+                    boundary: stop_at.into(),
+                })
+            }
+            State::Resolving(_) | State::Failed(_) if !context.modules_dirs.is_empty() => {
+                Err(Error::NotFound {
+                    request: request.to_string(),
+                    paths: context.take_modules_paths(),
+                })
+            }
             State::Resolving(_) | State::Failed(_) => Err(Error::ResolveFailedTag),
+        };
+
+        self.metrics
+            .record_resolution(start.elapsed(), result.is_err());
+        if let Some(observer) = self.options.observer.as_ref() {
+            match &result {
+                Ok(result) => observer.on_success(path, request, result),
+                Err(error) => observer.on_failure(path, request, error),
+            }
+        }
+
+        result
+    }
+
+    /// Whether `request` is an optional peer dependency of the issuer which
+    /// is not installed in any of the looked up modules directories, see
+    /// `Options::report_optional_peers`.
+    fn is_optional_peer(&self, request: &str, context: &Context) -> bool {
+        if !self.options.report_optional_peers {
+            return false;
+        }
+        let request = Self::parse(request);
+        if !matches!(request.kind(), PathKind::Normal) {
+            return false;
+        }
+        let name = resolve::get_module_name_from_request(request.target());
+        let is_optional = context.issuer_pkg_info.as_ref().map_or(false, |pkg_info| {
+            pkg_info
+                .data()
+                .raw()
+                .get("peerDependenciesMeta")
+                .and_then(|meta| meta.get(name)?.get("optional")?.as_bool())
+                .unwrap_or(false)
+        });
+        // an installed peer which fails, such as by a missing subpath, is
+        // reported as it is.
+        is_optional
+            && context
+                .modules_dirs
+                .iter()
+                .all(|dir| !self.load_entry(&dir.path().join(name)).is_dir())
+    }
+
+    pub(crate) fn issuer_pkg_info(
+        &self,
+        path: &std::path::Path,
+    ) -> Option<std::sync::Arc<DescriptionData>> {
+        // the error of description file will be returned when it is used.
+        self.load_entry(path)
+            .pkg_info(self)
+            .ok()
+            .and_then(|pkg_info| pkg_info.clone())
+    }
+
+    /// Maps the request by `options.tsconfig` first if there is one.
+    fn resolve_with_tsconfig(&self, info: Info, context: &mut Context) -> State {
+        #[cfg(feature = "tsconfig")]
+        if let Some(location) = self.options.tsconfig.as_ref() {
+            return self._resolve_with_tsconfig(info, location, context);
         }
+        self._resolve(info, context)
     }
 
     fn _resolve(&self, info: Info, context: &mut Context) -> State {
@@ -181,6 +695,17 @@ impl Resolver {
         if context.depth.cmp(127).is_ge() {
             return State::Error(Error::Overflow);
         }
+        if let Some(error) = context.interrupted(info.normalized_path().as_ref()) {
+            return State::Error(error);
+        }
+        let key = (info.clone(), context.flags());
+        if let Some(visited) = context.visited.get(&key) {
+            context.depth.decrease();
+            return match visited.clone() {
+                Visited::Failed(info) => State::Failed(info),
+                Visited::Resolving(info) => State::Resolving(info),
+            };
+        }
 
         let state = ParsePlugin::apply(self, info, context)
             .then(|info| AliasPlugin::new(&self.options.alias).apply(self, info, context))
@@ -188,20 +713,30 @@ impl Resolver {
             .then(|info| {
                 let request = info.to_resolved_path();
                 let entry = self.load_entry(&request);
-                let pkg_info = match entry.pkg_info(self) {
-                    Ok(pkg_info) => pkg_info,
-                    Err(error) => return State::Error(error),
+                let (imports_info, browser_info) = match (
+                    self.description_for(&entry, "imports"),
+                    self.description_for(&entry, "browser"),
+                ) {
+                    (Ok(imports_info), Ok(browser_info)) => (imports_info, browser_info),
+                    (Err(error), _) | (_, Err(error)) => return State::Error(error),
                 };
-                if let Some(pkg_info) = pkg_info {
-                    ImportsFieldPlugin::new(pkg_info)
-                        .apply(self, info, context)
-                        .then(|info| {
-                            BrowserFieldPlugin::new(pkg_info, false).apply(self, info, context)
-                        })
-                } else {
-                    State::Resolving(info)
-                }
+                // The module request is remapped by the `browser` field of the issuer,
+                // rather than the package which `info.to_resolved_path()` located.
+                let issuer_pkg_info = matches!(info.request().kind(), PathKind::Normal)
+                    .then(|| context.issuer_pkg_info.clone())
+                    .flatten();
+                let state = match imports_info.as_deref() {
+                    Some(pkg_info) => ImportsFieldPlugin::new(pkg_info).apply(self, info, context),
+                    None => State::Resolving(info),
+                };
+                state.then(|info| match issuer_pkg_info.or(browser_info).as_deref() {
+                    Some(pkg_info) => {
+                        BrowserFieldPlugin::new(pkg_info, false).apply(self, info, context)
+                    }
+                    None => State::Resolving(info),
+                })
             })
+            .then(|info| self.apply_root_package(info, context))
             .then(|info| {
                 if matches!(
                     info.request().kind(),
@@ -209,13 +744,22 @@ impl Resolver {
                 ) {
                     self.resolve_as_context(info, context)
                         .then(|info| self.resolve_as_fully_specified(info, context))
-                        .then(|info| self.resolve_as_file(info, context))
-                        .then(|info| self.resolve_as_dir(info, context))
+                        .then(|info| {
+                            self.resolve_as_file_or_dir(info, self.options.probe_order, context)
+                        })
                 } else {
                     self.resolve_as_modules(info, context)
                 }
             });
 
+        let visited = match &state {
+            State::Failed(info) => Some(Visited::Failed(info.clone())),
+            State::Resolving(info) => Some(Visited::Resolving(info.clone())),
+            State::Success(_) | State::Error(_) => None,
+        };
+        if let Some(visited) = visited {
+            context.visited.insert(key, visited);
+        }
         context.depth.decrease();
         state
     }
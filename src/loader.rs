@@ -0,0 +1,117 @@
+use crate::{Error, LoaderSyntax, RResult, ResolveResult, Resolver, Resource};
+use std::path::Path;
+
+/// The prefix of a request with the inline loaders, which disables the
+/// loaders configured in webpack.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LoaderPrefix {
+    None,
+    /// `!`, disables the normal loaders.
+    NoNormal,
+    /// `-!`, disables the pre and the normal loaders.
+    NoPreNormal,
+    /// `!!`, disables all the configured loaders.
+    NoPrePostNormal,
+}
+
+/// A request resolved with its inline loaders, see
+/// `Resolver::resolve_with_loaders`.
+#[derive(Debug, Clone)]
+pub struct LoaderResolution {
+    pub prefix: LoaderPrefix,
+    /// The loaders in the order of the request, the queries of them are
+    /// their options, such as `css-loader?modules`.
+    pub loaders: Vec<ResolveResult<Resource>>,
+    pub resource: ResolveResult<Resource>,
+}
+
+/// Splits the inline loaders of webpack off `request`, such as
+/// `-!style-loader!css-loader?modules!./a.css` into the prefix, the loaders
+/// and `./a.css`, it is `None` if there is no `!`.
+pub(crate) fn split_loaders(request: &str) -> Option<(LoaderPrefix, Vec<&str>, &str)> {
+    if !request.contains('!') {
+        return None;
+    }
+    let (prefix, rest) = [
+        ("-!", LoaderPrefix::NoPreNormal),
+        ("!!", LoaderPrefix::NoPrePostNormal),
+        ("!", LoaderPrefix::NoNormal),
+    ]
+    .into_iter()
+    .find_map(|(prefix, kind)| Some((kind, request.strip_prefix(prefix)?)))
+    .unwrap_or((LoaderPrefix::None, request));
+    let mut segments: Vec<&str> = rest.split('!').collect();
+    let resource = segments.pop()?;
+    segments.retain(|loader| !loader.is_empty());
+    Some((prefix, segments, resource))
+}
+
+impl Resolver {
+    /// Handles the inline loaders in `request` by `Options::loader_syntax`.
+    pub(crate) fn apply_loader_syntax<'a>(
+        &self,
+        path: &Path,
+        request: &'a str,
+    ) -> RResult<&'a str> {
+        match self.options.loader_syntax {
+            LoaderSyntax::PassThrough => Ok(request),
+            LoaderSyntax::Strip | LoaderSyntax::Resolve => {
+                Ok(split_loaders(request).map_or(request, |(_, _, resource)| resource))
+            }
+            LoaderSyntax::Error if request.contains('!') => Err(Error::UnexpectedValue(format!(
+                "Can't resolve '{request}' in '{}', the inline loaders are not supported",
+                path.display()
+            ))),
+            LoaderSyntax::Error => Ok(request),
+        }
+    }
+
+    /// Resolves the inline loaders of webpack in `request` and its resource,
+    /// such as `style-loader!css-loader?modules!./a.css`, so the bundlers
+    /// can delegate the whole request. The loaders are resolved as the
+    /// modules in `path`.
+    ///
+    /// The request is only split with `LoaderSyntax::Resolve`, otherwise it
+    /// is the resource, which is handled by `Options::loader_syntax`.
+    pub fn resolve_with_loaders(&self, path: &Path, request: &str) -> RResult<LoaderResolution> {
+        let split = match self.options.loader_syntax {
+            LoaderSyntax::Resolve => split_loaders(request),
+            _ => None,
+        };
+        let (prefix, loaders, resource) = split.unwrap_or((LoaderPrefix::None, vec![], request));
+        Ok(LoaderResolution {
+            prefix,
+            loaders: loaders
+                .into_iter()
+                .map(|loader| self.resolve(path, loader))
+                .collect::<RResult<_>>()?,
+            resource: self.resolve(path, resource)?,
+        })
+    }
+}
+
+#[test]
+fn split_loaders_test() {
+    assert_eq!(split_loaders("./a.css"), None);
+    assert_eq!(
+        split_loaders("style-loader!css-loader?modules!./a.css"),
+        Some((
+            LoaderPrefix::None,
+            vec!["style-loader", "css-loader?modules"],
+            "./a.css"
+        ))
+    );
+    assert_eq!(
+        split_loaders("-!raw-loader!./a"),
+        Some((LoaderPrefix::NoPreNormal, vec!["raw-loader"], "./a"))
+    );
+    assert_eq!(
+        split_loaders("!!./a"),
+        Some((LoaderPrefix::NoPrePostNormal, vec![], "./a"))
+    );
+    assert_eq!(
+        split_loaders("!./a"),
+        Some((LoaderPrefix::NoNormal, vec![], "./a"))
+    );
+}
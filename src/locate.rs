@@ -0,0 +1,124 @@
+use crate::{
+    description::DescriptionData,
+    kind::PathKind,
+    resolve::{get_module_name_from_request, get_path_from_request},
+    Error, Excerpt, Resolver,
+};
+use jsonc_parser::{
+    ast::{ObjectPropName, Value},
+    common::Ranged,
+};
+use std::{path::Path, sync::Arc};
+
+impl Resolver {
+    /// Returns the part of the file which caused `error`, which is returned
+    /// by resolving `request` in `path`, so editors can jump to it.
+    ///
+    /// It is the position of the error for a malformed description file,
+    /// and the key of `exports` or `imports` which `request` matched when
+    /// processing the field failed.
+    #[must_use]
+    pub fn error_excerpt(&self, path: &Path, request: &str, error: &Error) -> Option<Excerpt> {
+        let fs = self.cache.fs.file_system();
+        match error {
+            Error::UnexpectedJson((path, error)) => {
+                let content = fs.read_to_string(path).ok()?;
+                Some(Excerpt::new(path, &content, error.line(), error.column()))
+            }
+            Error::UnexpectedValue(_) | Error::NotExported { .. } => {
+                let (pkg_info, keys) = self.offending_field(path, request)?;
+                let path = pkg_info.dir().as_ref().join(&self.options.description_file);
+                let content = fs.read_to_string(&path).ok()?;
+                let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+                let offset = key_offset(&content, &keys)?;
+                Some(Excerpt::at_offset(&path, &content, offset))
+            }
+            _ => None,
+        }
+    }
+
+    /// The description file and the keys of the field which `request` is
+    /// mapped by, such as `["exports", "./feature"]`.
+    fn offending_field(
+        &self,
+        path: &Path,
+        request: &str,
+    ) -> Option<(Arc<DescriptionData>, Vec<String>)> {
+        let parsed = Self::parse(request);
+        let target = parsed.target();
+        let issuer = self.issuer_pkg_info(path);
+        if target.starts_with('#') {
+            let pkg_info = issuer?;
+            let keys = field_keys(&pkg_info, &[String::from("imports")], target);
+            return Some((pkg_info, keys));
+        }
+        if !matches!(parsed.kind(), PathKind::Normal) {
+            return None;
+        }
+        let name = get_module_name_from_request(target);
+        let subpath = match get_path_from_request(target) {
+            Some(rest) => format!(".{rest}"),
+            None => String::from("."),
+        };
+        let pkg_info = self
+            .find_package(path, name)
+            .or_else(|| issuer.filter(|pkg_info| pkg_info.data().name() == Some(name)))?;
+        let field = self.options.exports_field.first()?.clone();
+        let keys = field_keys(&pkg_info, &field, &subpath);
+        Some((pkg_info, keys))
+    }
+}
+
+/// `field` followed by the key in it which `subpath` matches, if any.
+fn field_keys(pkg_info: &DescriptionData, field: &[String], subpath: &str) -> Vec<String> {
+    let mut keys = field.to_vec();
+    let map = pkg_info
+        .data()
+        .get_filed(&keys)
+        .and_then(serde_json::Value::as_object);
+    let matched = map.and_then(|map| {
+        if map.contains_key(subpath) {
+            return Some(subpath);
+        }
+        // the longest pattern or folder mapping which matches.
+        map.keys()
+            .map(String::as_str)
+            .filter(|key| match key.split_once('*') {
+                Some((prefix, suffix)) => {
+                    subpath.len() >= prefix.len() + suffix.len()
+                        && subpath.starts_with(prefix)
+                        && subpath.ends_with(suffix)
+                }
+                None => key.ends_with('/') && subpath.starts_with(key),
+            })
+            .max_by_key(|key| key.find('*').unwrap_or(key.len()))
+    });
+    if let Some(matched) = matched {
+        keys.push(matched.to_string());
+    }
+    keys
+}
+
+/// The offset of the name of the deepest property in `keys` which exists.
+fn key_offset(content: &str, keys: &[&str]) -> Option<usize> {
+    let ast = jsonc_parser::parse_to_ast(content, &Default::default(), &Default::default()).ok()?;
+    let mut value = ast.value?;
+    let mut offset = None;
+    for key in keys {
+        let Value::Object(object) = value else {
+            break;
+        };
+        let Some(prop) = object.properties.into_iter().find(|prop| {
+            let name = match &prop.name {
+                ObjectPropName::String(name) => name.value.as_ref(),
+                ObjectPropName::Word(name) => name.value,
+            };
+            name == *key
+        }) else {
+            break;
+        };
+        offset = Some(prop.name.range().start);
+        value = prop.value;
+    }
+    offset
+}
@@ -0,0 +1,258 @@
+use crate::{Error, PackageInstance, RResult, Resolver};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// A package recorded in a lockfile, and the directory where it is installed.
+#[derive(Debug, PartialEq, Eq)]
+struct LockedPackage {
+    name: String,
+    version: Option<String>,
+    dir: PathBuf,
+}
+
+impl Resolver {
+    /// Learns the packages installed by `package-lock.json`,
+    /// `npm-shrinkwrap.json`, `pnpm-lock.yaml` or `yarn.lock` at `path`.
+    ///
+    /// The directories of the packages and the `node_modules` between them
+    /// are stated in one batch, see `FileSystem::metadata_batch`, so the
+    /// walks looking for the packages hit the cache, and the installed
+    /// packages are recorded for `duplicate_report` ahead of resolving them.
+    ///
+    /// `yarn.lock` does not record where the packages are hoisted, so only
+    /// `node_modules/<name>` beside it is learned.
+    ///
+    /// Returns the number of the installed packages which were learned.
+    pub fn prime_from_lockfile<P: AsRef<Path>>(&self, path: P) -> RResult<usize> {
+        let path = path.as_ref();
+        let root = path.parent().unwrap_or(Path::new("/"));
+        let content = self.cache.fs.file_system().read_to_string(path)?;
+        let file_name = path.file_name().and_then(|name| name.to_str());
+        let packages = match file_name {
+            Some("package-lock.json" | "npm-shrinkwrap.json") => {
+                let json: serde_json::Value = serde_json::from_str(&content)
+                    .map_err(|error| Error::UnexpectedJson((path.into(), error)))?;
+                parse_package_lock(&json, root)
+            }
+            Some("pnpm-lock.yaml") => parse_pnpm_lock(&content, root),
+            Some("yarn.lock") => parse_yarn_lock(&content, root),
+            _ => {
+                return Err(Error::UnexpectedValue(format!(
+                    "{} is not a lockfile of npm, pnpm or yarn",
+                    path.display()
+                )))
+            }
+        };
+
+        // the `node_modules` between the packages are stated along with them.
+        let mut seen = HashSet::new();
+        let mut dirs = vec![];
+        for package in &packages {
+            for dir in package.dir.ancestors() {
+                if dir == root || !seen.insert(dir) {
+                    break;
+                }
+                dirs.push(dir.to_path_buf());
+            }
+        }
+        let stats = self.cache.fs.file_system().metadata_batch(&dirs);
+        for (dir, stat) in dirs.iter().zip(&stats) {
+            self.load_entry(dir).init_stat(stat);
+        }
+
+        let mut learned = 0;
+        for package in packages {
+            if !self.load_entry(&package.dir).is_dir() {
+                continue;
+            }
+            let instance = PackageInstance {
+                version: package.version,
+                path: package.dir,
+            };
+            self.packages.record_instance(&package.name, instance);
+            learned += 1;
+        }
+        Ok(learned)
+    }
+}
+
+/// `packages` of the lockfile v2 and v3, or `dependencies` of v1.
+fn parse_package_lock(json: &serde_json::Value, root: &Path) -> Vec<LockedPackage> {
+    let mut packages = vec![];
+    if let Some(map) = json.get("packages").and_then(|v| v.as_object()) {
+        for (key, value) in map {
+            if key.is_empty() || value.get("link").and_then(|v| v.as_bool()) == Some(true) {
+                continue;
+            }
+            // the installed name differs from `name` of the aliases, such as `npm:pkg@1`.
+            let name = value
+                .get("name")
+                .and_then(|v| v.as_str())
+                .or_else(|| key.rsplit_once("node_modules/").map(|(_, name)| name));
+            let Some(name) = name else {
+                continue;
+            };
+            packages.push(LockedPackage {
+                name: name.to_string(),
+                version: value
+                    .get("version")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                dir: root.join(key),
+            });
+        }
+    } else if let Some(map) = json.get("dependencies").and_then(|v| v.as_object()) {
+        collect_dependencies(map, root, &mut packages);
+    }
+    packages
+}
+
+fn collect_dependencies(
+    map: &serde_json::Map<String, serde_json::Value>,
+    dir: &Path,
+    packages: &mut Vec<LockedPackage>,
+) {
+    for (name, value) in map {
+        let package_dir = dir.join("node_modules").join(name);
+        if let Some(nested) = value.get("dependencies").and_then(|v| v.as_object()) {
+            collect_dependencies(nested, &package_dir, packages);
+        }
+        packages.push(LockedPackage {
+            name: name.to_string(),
+            version: value
+                .get("version")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            dir: package_dir,
+        });
+    }
+}
+
+/// The keys of `packages` and `snapshots`, which are the paths of the packages
+/// in the virtual store, such as `/@scope/pkg@1.0.0(peer@2.0.0)` of v6 and
+/// `/@scope/pkg/1.0.0_peer@2.0.0` of v5.
+fn parse_pnpm_lock(content: &str, root: &Path) -> Vec<LockedPackage> {
+    let store = root.join("node_modules").join(".pnpm");
+    let mut packages = vec![];
+    let mut in_packages = false;
+    for line in content.lines() {
+        if !line.starts_with(' ') && !line.is_empty() {
+            in_packages = matches!(line.trim_end(), "packages:" | "snapshots:");
+            continue;
+        }
+        let Some(key) = line
+            .strip_prefix("  ")
+            .filter(|key| !key.starts_with(' '))
+            .and_then(|key| key.trim_end().strip_suffix(':'))
+        else {
+            continue;
+        };
+        if !in_packages {
+            continue;
+        }
+        let key = key.trim_matches(|c| c == '\'' || c == '"');
+        let key = key.strip_prefix('/').unwrap_or(key);
+        let Some((name, rest)) = split_pnpm_key(key) else {
+            continue;
+        };
+        // the local packages, such as `file:` and `link:`, are not in the store.
+        if rest.contains(':') {
+            continue;
+        }
+        let version = rest.split(['(', '_']).next().unwrap_or(rest);
+        let peers = rest[version.len()..]
+            .replace(")(", "_")
+            .replace('(', "_")
+            .replace(')', "");
+        let entry = format!("{name}@{version}{peers}").replace('/', "+");
+        let package = LockedPackage {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            dir: store.join(entry).join("node_modules").join(name),
+        };
+        if !packages.contains(&package) {
+            packages.push(package);
+        }
+    }
+    packages
+}
+
+/// Splits the key into the name and the version with the peers.
+fn split_pnpm_key(key: &str) -> Option<(&str, &str)> {
+    let name_start = if key.starts_with('@') {
+        key.find('/')? + 1
+    } else {
+        0
+    };
+    // v5 separates the version by `/`.
+    let index = name_start + key[name_start..].find(['@', '/'])?;
+    Some((&key[..index], &key[index + 1..]))
+}
+
+/// The entries of the classic lockfile and the lockfile of Berry, such as
+/// `"@scope/pkg@^1.0.0", "@scope/pkg@^1.1.0":` followed by `version "1.2.0"`.
+fn parse_yarn_lock(content: &str, root: &Path) -> Vec<LockedPackage> {
+    let mut packages: Vec<LockedPackage> = vec![];
+    let mut name: Option<&str> = None;
+    for line in content.lines() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            name = line
+                .trim_end_matches(':')
+                .split(", ")
+                .next()
+                .map(|descriptor| descriptor.trim_matches('"'))
+                .and_then(|descriptor| {
+                    let scoped = usize::from(descriptor.starts_with('@'));
+                    descriptor[scoped..]
+                        .find('@')
+                        .map(|index| &descriptor[..index + scoped])
+                })
+                .filter(|name| *name != "__metadata");
+            continue;
+        }
+        let Some(current) = name else {
+            continue;
+        };
+        let Some(version) = line
+            .trim()
+            .strip_prefix("version")
+            .map(|version| version.trim_start_matches(':').trim().trim_matches('"'))
+        else {
+            continue;
+        };
+        // only the hoisted copy is known, so the version is kept if it is the only one.
+        match packages.iter_mut().find(|package| package.name == current) {
+            Some(package) if package.version.as_deref() != Some(version) => {
+                package.version = None;
+            }
+            Some(_) => {}
+            None => packages.push(LockedPackage {
+                name: current.to_string(),
+                version: Some(version.to_string()),
+                dir: root.join("node_modules").join(current),
+            }),
+        }
+        name = None;
+    }
+    packages
+}
+
+#[test]
+fn split_pnpm_key_test() {
+    assert_eq!(split_pnpm_key("pkg@1.0.0"), Some(("pkg", "1.0.0")));
+    assert_eq!(
+        split_pnpm_key("@scope/pkg@1.0.0(react@18.2.0)"),
+        Some(("@scope/pkg", "1.0.0(react@18.2.0)"))
+    );
+    assert_eq!(split_pnpm_key("pkg/1.0.0"), Some(("pkg", "1.0.0")));
+    assert_eq!(
+        split_pnpm_key("@scope/pkg/1.0.0_react@18.2.0"),
+        Some(("@scope/pkg", "1.0.0_react@18.2.0"))
+    );
+    assert_eq!(split_pnpm_key("pkg"), None);
+}
@@ -1,6 +1,13 @@
 use crate::context::Depth;
+#[cfg(feature = "trace")]
 use tracing_subscriber::prelude::*;
 
+/// Prints the trace to stderr if `RESOLVER_TRACE` is set, it does nothing
+/// without the `trace` feature.
+#[cfg(not(feature = "trace"))]
+pub fn enable_by_env() {}
+
+#[cfg(feature = "trace")]
 pub fn enable_by_env() {
     let is_enabled = std::env::var("RESOLVER_TRACE").map_or(false, |var| {
         matches!(var.as_str(), "TRACE" | "DEBUG" | "INFO" | "WARN" | "ERROR")
@@ -15,9 +22,11 @@ pub fn enable_by_env() {
         .init();
 }
 
+#[cfg(feature = "trace")]
 #[derive(Default)]
 struct Formatter {}
 
+#[cfg(feature = "trace")]
 impl<S> tracing_subscriber::Layer<S> for Formatter
 where
     S: tracing::Subscriber + std::fmt::Debug,
@@ -27,8 +36,10 @@ where
     }
 }
 
+#[cfg(feature = "trace")]
 struct Data;
 
+#[cfg(feature = "trace")]
 impl tracing::field::Visit for Data {
     fn record_debug(&mut self, _field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
         eprintln!("{value:?}");
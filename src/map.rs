@@ -62,26 +62,66 @@ fn conditional_mapping<'a>(
 /// TODO: should seal all functions except
 ///  `build_field` and `field_process`.
 pub trait Field {
+    /// Reject the target which escapes the package directory, such as
+    /// `../a.js`, `/a.js`, `./%2e%2e/a.js` and `./node_modules/a/index.js`,
+    /// or has the empty, `.` and `..` segments after the leading `./`, such
+    /// as `./a//b.js` and `./a/../b.js`, see `ERR_INVALID_PACKAGE_TARGET` in
+    /// Node.js.
     fn check_target(relative_path: &str) -> Result<(), String> {
-        let relative_path_chars = relative_path.chars().collect::<Vec<char>>();
-        let slash_index_list = get_next_list(&relative_path_chars, '/');
-        let mut last_non_slash_index = 0;
+        if is_absolute_target(relative_path) {
+            return Err(format!(
+                "Target should not be an absolute path. Requesting {relative_path}"
+            ));
+        }
+        let rest = relative_path
+            .strip_prefix("./")
+            .or_else(|| relative_path.strip_prefix(".\\"))
+            .unwrap_or(relative_path);
         let mut cd = 0;
-        while let Some(&Some(slash_index)) = slash_index_list.get(last_non_slash_index) {
-            if relative_path_chars[last_non_slash_index] == '.'
-                && relative_path_chars[last_non_slash_index + 1] == '.'
-            {
-                cd -= 1;
-                if cd < 0 {
+        let mut has_dot_dot = false;
+        let mut segments = rest.split(['/', '\\']).peekable();
+        while let Some(segment) = segments.next() {
+            match segment {
+                // the folder targets end with a separator.
+                "" if segments.peek().is_none() => {}
+                "" | "." => {
                     return Err(format!(
-                        "Trying to access out of package scope. Requesting {relative_path}"
+                        "Target should not contain empty or \".\" segments. Requesting {relative_path}"
                     ));
                 }
-            } else if relative_path_chars[last_non_slash_index] == '.' {
-            } else {
-                cd += 1;
+                ".." => {
+                    cd -= 1;
+                    if cd < 0 {
+                        return Err(format!(
+                            "Trying to access out of package scope. Requesting {relative_path}"
+                        ));
+                    }
+                    has_dot_dot = true;
+                }
+                _ => {
+                    let decoded = percent_decode_segment(segment);
+                    if decoded.contains(['/', '\\']) {
+                        return Err(format!(
+                            "Target should not contain encoded separators. Requesting {relative_path}"
+                        ));
+                    } else if decoded == "." || decoded == ".." {
+                        return Err(format!(
+                            "Target should not contain encoded dot segments. Requesting {relative_path}"
+                        ));
+                    } else if decoded.eq_ignore_ascii_case("node_modules") {
+                        return Err(format!(
+                            "Target should not contain node_modules segments. Requesting {relative_path}"
+                        ));
+                    }
+                    cd += 1;
+                }
             }
-            last_non_slash_index = slash_index + 1;
+        }
+        if has_dot_dot {
+            // the `..` segments which stay in the package are still rejected.
+            return Err(format!(
+                "Target should not contain \"..\" segments. Requesting {relative_path}"
+            ));
         }
         Ok(())
     }
@@ -336,18 +376,125 @@ impl Field for ImportsField {
     }
 }
 
-fn get_next_list(path: &[char], target: char) -> Vec<Option<usize>> {
-    // TODO: rewrite it use fp.
-    let len = path.len();
-    let mut last_index = None;
-    let mut index_list = vec![None; len];
-    for i in (0..len).rev() {
-        if path[i] == target {
-            last_index = Some(i);
+/// A leaf of the `exports` or `imports` tree, see `ExportsField::entries`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappingEntry<'a> {
+    /// The subpath, such as `"."`, `"./feature/*"` or `"#internal"`.
+    pub key: &'a str,
+    /// The condition names from the outermost to the innermost,
+    /// such as `["node", "import"]`.
+    pub conditions: Vec<&'a str>,
+    /// `None` if the target is `null`, which excludes the subpath.
+    pub target: Option<&'a str>,
+}
+
+impl ExportsField {
+    /// Flattens the value of `exports` into its leaves in declaration order,
+    /// the fallbacks of an array are listed one by one.
+    ///
+    /// The keys are validated as `field_process` does, the values which are
+    /// neither strings, arrays, objects nor `null` are skipped.
+    pub fn entries(root: &serde_json::Value) -> RResult<Vec<MappingEntry<'_>>> {
+        Self::find_match(root, ".")?;
+        let mut entries = vec![];
+        match root.as_object() {
+            Some(map) if map.keys().any(|key| key.starts_with('.')) => {
+                for (key, value) in map {
+                    flatten(key, value, &mut vec![], &mut entries)?;
+                }
+            }
+            // sugar of `{ ".": root }`
+            _ => flatten(".", root, &mut vec![], &mut entries)?,
+        }
+        Ok(entries)
+    }
+}
+
+impl ImportsField {
+    /// The same as `ExportsField::entries`, but for the value of `imports`.
+    pub fn entries(root: &serde_json::Value) -> RResult<Vec<MappingEntry<'_>>> {
+        Self::find_match(root, "#")?;
+        let mut entries = vec![];
+        if let Some(map) = root.as_object() {
+            for (key, value) in map {
+                flatten(key, value, &mut vec![], &mut entries)?;
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn flatten<'a>(
+    key: &'a str,
+    value: &'a MappingValue,
+    conditions: &mut Vec<&'a str>,
+    entries: &mut Vec<MappingEntry<'a>>,
+) -> RResult<()> {
+    match value {
+        MappingValue::String(target) => entries.push(MappingEntry {
+            key,
+            conditions: conditions.clone(),
+            target: Some(target),
+        }),
+        MappingValue::Null => entries.push(MappingEntry {
+            key,
+            conditions: conditions.clone(),
+            target: None,
+        }),
+        MappingValue::Array(items) => {
+            for item in items {
+                flatten(key, item, conditions, entries)?;
+            }
+        }
+        MappingValue::Object(map) => {
+            let len = map.len();
+            for (i, (condition, value)) in map.iter().enumerate() {
+                if condition == DEFAULT_MARK && i != len - 1 {
+                    return Err(Error::UnexpectedValue(
+                        "Default condition should be last one".to_string(),
+                    ));
+                }
+                conditions.push(condition);
+                flatten(key, value, conditions, entries)?;
+                conditions.pop();
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn is_absolute_target(target: &str) -> bool {
+    let bytes = target.as_bytes();
+    matches!(bytes.first(), Some(b'/' | b'\\'))
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Decode `%xx` in `segment`, the invalid escape sequences are kept as they are.
+fn percent_decode_segment(segment: &str) -> std::borrow::Cow<'_, str> {
+    if !segment.contains('%') {
+        return std::borrow::Cow::Borrowed(segment);
+    }
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
         }
-        index_list[i] = last_index;
     }
-    index_list
+    std::borrow::Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
 }
 
 fn pattern_key_compare(a: &str, b: &str) -> std::cmp::Ordering {
@@ -376,6 +523,23 @@ fn pattern_key_compare(a: &str, b: &str) -> std::cmp::Ordering {
     }
 }
 
+/// The key of `root` which `request` matches, such as `"./feature/*"`,
+/// it is `"."` for the sugar of `exports`, such as `{ "import": "./a.js" }`.
+pub(crate) fn matched_key<'a, F: Field>(
+    root: &'a serde_json::Value,
+    request: &'a str,
+) -> Option<&'a str> {
+    let (mapping, ..) = F::find_match(root, request).ok()??;
+    let key = match root {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .find(|(_, value)| std::ptr::eq(*value, mapping))
+            .map_or(".", |(key, _)| key.as_str()),
+        _ => ".",
+    };
+    Some(key)
+}
+
 fn find_normalized_match_in_object<'a>(
     field: &'a ConditionalMapping,
     request: &'a str,
@@ -3072,4 +3236,108 @@ fn check_target_test() {
     assert!(ExportsField::check_target("./a/../b/../../c.js").is_err());
     assert!(ExportsField::check_target("./a/../b/../../").is_err());
     assert!(ExportsField::check_target("./././../").is_err());
+    assert!(ExportsField::check_target("./a//b.js").is_err());
+    assert!(ExportsField::check_target("./a/./b.js").is_err());
+    assert!(ExportsField::check_target(".//a.js").is_err());
+    assert!(ExportsField::check_target("./a\\.\\b.js").is_err());
+    assert!(ExportsField::check_target("./dist/").is_ok());
+    assert!(ExportsField::check_target("./node_modules/a.js").is_err());
+    assert!(ExportsField::check_target("./a/node%5Fmodules/b.js").is_err());
+    assert!(ExportsField::check_target("./a/%6Eode_modules/b.js").is_err());
+    assert!(ExportsField::check_target("./a/%4eODE_MODULES/b.js").is_err());
+    assert!(ExportsField::check_target("./a/%4e%6F%64%45_modules/b.js").is_err());
+    assert!(ExportsField::check_target("./a/NODE_MODULES/b.js").is_err());
+    assert!(ExportsField::check_target("./a/Node_modules/b.js").is_err());
+    assert!(ExportsField::check_target("./a/../x/y.js").is_err());
+    assert!(ExportsField::check_target("./a\\..\\b.js").is_err());
+}
+
+#[test]
+fn check_target_escape_test() {
+    assert!(ExportsField::check_target("./a.js").is_ok());
+    assert!(ExportsField::check_target("./a/../b.js").is_err());
+    assert!(ExportsField::check_target("./中文/😀.js").is_ok());
+    assert!(ExportsField::check_target("./a%20b.js").is_ok());
+
+    assert!(ExportsField::check_target("/a.js").is_err());
+    assert!(ExportsField::check_target("\\a.js").is_err());
+    assert!(ExportsField::check_target("C:/a.js").is_err());
+    assert!(ExportsField::check_target("c:\\a.js").is_err());
+    assert!(ExportsField::check_target("./..").is_err());
+    assert!(ExportsField::check_target("./a\\..\\..\\b.js").is_err());
+    assert!(ExportsField::check_target("./%2e%2e/a.js").is_err());
+    assert!(ExportsField::check_target("./%2E%2E/a.js").is_err());
+    assert!(ExportsField::check_target("./.%2e/a.js").is_err());
+    assert!(ExportsField::check_target("./%2e/a.js").is_err());
+    assert!(ExportsField::check_target("./a%2f..%2f..%2fb.js").is_err());
+    assert!(ExportsField::check_target("./a%5cb.js").is_err());
+    assert!(ExportsField::check_target("./node_modules/a/index.js").is_err());
+    assert!(ExportsField::check_target("./a/node%5fmodules/b.js").is_err());
+    assert!(ExportsField::check_target("./a/node_modules").is_err());
+}
+
+#[test]
+fn check_target_fuzz_test() {
+    // xorshift, so that the failed case can be reproduced.
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+    let segments = [
+        "",
+        ".",
+        "..",
+        "a",
+        "b.js",
+        "%2e",
+        "%2E%2e",
+        ".%2e",
+        "%2f",
+        "%5C",
+        "node_modules",
+        "Node_Modules",
+        "node%5Fmodules",
+        "%",
+        "%zz",
+        "中文",
+        "😀",
+        "\\",
+        ":",
+        "C:",
+    ];
+    for _ in 0..20000 {
+        let len = next() % 6 + 1;
+        let mut target = String::from(if next() % 4 == 0 { "" } else { "./" });
+        for i in 0..len {
+            if i != 0 {
+                target.push(if next() % 5 == 0 { '\\' } else { '/' });
+            }
+            target.push_str(segments[(next() % segments.len() as u64) as usize]);
+        }
+
+        if ExportsField::check_target(&target).is_err() {
+            continue;
+        }
+        // the accepted target has no dot, empty or node_modules segments
+        // after the leading `./`, except the trailing separator of folders.
+        let rest = target
+            .strip_prefix("./")
+            .or_else(|| target.strip_prefix(".\\"))
+            .unwrap_or(&target);
+        let segments: Vec<&str> = rest.split(['/', '\\']).collect();
+        for (index, segment) in segments.iter().enumerate() {
+            let decoded = percent_decode_segment(segment);
+            assert!(!decoded.eq_ignore_ascii_case("node_modules"), "{target}");
+            assert!(!decoded.contains(['/', '\\']), "{target}");
+            assert!(decoded != "." && decoded != "..", "{target}");
+            assert!(
+                !segment.is_empty() || index + 1 == segments.len(),
+                "{target}"
+            );
+        }
+        assert!(!is_absolute_target(&target), "{target}");
+    }
 }
@@ -0,0 +1,180 @@
+use crate::{Options, PackageInstance, RResult, ResolveResult, Resolver, Resource};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+impl Resolver {
+    /// Resolves `request` in `path` under each of `condition_sets`, such as
+    /// the client and server builds of SSR, the results are in the same order.
+    ///
+    /// The resolutions share the cache of this resolver, so the directories
+    /// and the description files are only read once, and the resolvers of
+    /// the other condition sets are created once and kept by this one.
+    pub fn resolve_matrix(
+        &self,
+        path: &Path,
+        request: &str,
+        condition_sets: &[HashSet<String>],
+    ) -> Vec<RResult<ResolveResult<Resource>>> {
+        condition_sets
+            .iter()
+            .map(|condition_names| {
+                if *condition_names == self.options.condition_names {
+                    return self.resolve(path, request);
+                }
+                let mut key: Vec<String> = condition_names.iter().cloned().collect();
+                key.sort_unstable();
+                let resolver = self
+                    .by_conditions
+                    .entry(key)
+                    .or_insert_with(|| {
+                        let mut resolver = self.derive(Options {
+                            condition_names: condition_names.clone(),
+                            ..self.raw_options.clone()
+                        });
+                        resolver.metrics = self.metrics.clone();
+                        Arc::new(resolver)
+                    })
+                    .clone();
+                resolver.resolve(path, request)
+            })
+            .collect()
+    }
+}
+
+/// `import` and `require()` of the same request are resolved to different
+/// files, so the package may be instantiated twice and its state is split.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DualPackageHazard {
+    pub import: PathBuf,
+    pub require: PathBuf,
+    /// The package which `import` is resolved in.
+    pub import_package: Option<PackageInstance>,
+    /// The package which `require()` is resolved in.
+    pub require_package: Option<PackageInstance>,
+}
+
+impl Resolver {
+    /// Resolves `request` in `path` with the `"import"` and the `"require"`
+    /// conditions, in addition to `condition_names`, and returns the hazard
+    /// if both succeed with different files.
+    pub fn dual_package_hazard(
+        &self,
+        path: &Path,
+        request: &str,
+    ) -> RResult<Option<DualPackageHazard>> {
+        let condition_sets = ["import", "require"].map(|condition| {
+            let mut condition_names = self.options.condition_names.clone();
+            condition_names.remove("import");
+            condition_names.remove("require");
+            condition_names.insert(String::from(condition));
+            condition_names
+        });
+        let results = self.resolve_matrix(path, request, &condition_sets);
+        let Ok([import, require]) = <[_; 2]>::try_from(results) else {
+            unreachable!("a result for each condition set")
+        };
+        let (ResolveResult::Resource(import), ResolveResult::Resource(require)) =
+            (import?, require?)
+        else {
+            return Ok(None);
+        };
+        if import.path == require.path {
+            return Ok(None);
+        }
+        Ok(Some(DualPackageHazard {
+            import_package: import.description.as_deref().map(PackageInstance::new),
+            require_package: require.description.as_deref().map(PackageInstance::new),
+            import: import.path,
+            require: require.path,
+        }))
+    }
+}
+
+/// A condition which changes how a request is resolved once it is toggled,
+/// see `Resolver::condition_sensitivity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConditionSensitivity {
+    pub condition: String,
+    /// Whether the condition is added to the base conditions, otherwise it
+    /// is removed from them.
+    pub added: bool,
+    /// The resolved path under the base conditions, `None` if it is ignored
+    /// or an optional peer which is missing, or the message of the error.
+    pub base: Result<Option<PathBuf>, String>,
+    /// The resolved path once the condition is toggled.
+    pub toggled: Result<Option<PathBuf>, String>,
+}
+
+impl Resolver {
+    /// Resolves `request` in `path` under `base_conditions`, and again with
+    /// each of `toggles` added to them, or removed if they contain it, and
+    /// returns the toggles which change the result, such as the packages
+    /// which are resolved to another build in the browsers. The toggles
+    /// which don't change the result are left out.
+    pub fn condition_sensitivity(
+        &self,
+        path: &Path,
+        request: &str,
+        base_conditions: &HashSet<String>,
+        toggles: &[&str],
+    ) -> Vec<ConditionSensitivity> {
+        let condition_sets: Vec<HashSet<String>> = std::iter::once(base_conditions.clone())
+            .chain(toggles.iter().map(|condition| {
+                let mut condition_names = base_conditions.clone();
+                if !condition_names.remove(*condition) {
+                    condition_names.insert(condition.to_string());
+                }
+                condition_names
+            }))
+            .collect();
+        let mut outcomes = self
+            .resolve_matrix(path, request, &condition_sets)
+            .into_iter()
+            .map(|result| match result {
+                Ok(ResolveResult::Resource(resource)) => Ok(Some(resource.path)),
+                Ok(ResolveResult::Ignored | ResolveResult::OptionalMissing) => Ok(None),
+                Err(error) => Err(error.to_string()),
+            });
+        let base = outcomes.next().expect("a result for the base conditions");
+        toggles
+            .iter()
+            .zip(outcomes)
+            .filter(|(_, toggled)| *toggled != base)
+            .map(|(condition, toggled)| ConditionSensitivity {
+                condition: condition.to_string(),
+                added: !base_conditions.contains(*condition),
+                base: base.clone(),
+                toggled,
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn resolve_matrix_reuse_test() {
+    let case_path = super::test_helper::p(vec!["compat-corpus"]);
+    let resolver = Resolver::new(Options {
+        condition_names: HashSet::from([String::from("node")]),
+        ..Default::default()
+    });
+    let condition_sets = ["import", "require"]
+        .map(|condition| HashSet::from([String::from("node"), String::from(condition)]));
+    let first = resolver.resolve_matrix(&case_path, "uuid", &condition_sets);
+    let again = resolver.resolve_matrix(&case_path, "uuid", &condition_sets);
+    assert_eq!(resolver.by_conditions.len(), 2);
+    let paths = |results: Vec<RResult<ResolveResult<Resource>>>| -> Vec<PathBuf> {
+        results
+            .into_iter()
+            .map(|result| match result {
+                Ok(ResolveResult::Resource(resource)) => resource.path,
+                _ => unreachable!("uuid is resolved"),
+            })
+            .collect()
+    };
+    assert_eq!(paths(first), paths(again));
+}
@@ -0,0 +1,124 @@
+use crate::Resolver;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[derive(Debug, Default)]
+struct Counters {
+    resolutions: AtomicU64,
+    failures: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    coalesced: AtomicU64,
+    path_allocations: AtomicU64,
+    /// In nanoseconds.
+    duration: AtomicU64,
+}
+
+/// The counters of a resolver, it is cheap to clone and can be read
+/// from other threads while resolving.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(Arc<Counters>);
+
+/// The values of `Metrics` at some moment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricsSnapshot {
+    /// The times `Resolver::resolve` was called.
+    pub resolutions: u64,
+    /// The resolutions which returned an error.
+    pub failures: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// The resolutions which took the result of an identical one in
+    /// progress, see `Options::coalesce_requests`, they are not counted
+    /// by `resolutions`.
+    pub coalesced: u64,
+    /// The buffers of the candidate paths which were allocated, the others
+    /// reused the buffers of the earlier probes, see `ResolverPool`.
+    pub path_allocations: u64,
+}
+
+impl Metrics {
+    pub(crate) fn record_resolution(&self, duration: Duration, failed: bool) {
+        self.0.resolutions.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            self.0.failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.0.duration.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_cache(&self, hit: bool) {
+        if hit {
+            self.0.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.0.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_coalesced(&self) {
+        self.0.coalesced.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_path_allocation(&self) {
+        self.0.path_allocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            resolutions: self.0.resolutions.load(Ordering::Relaxed),
+            failures: self.0.failures.load(Ordering::Relaxed),
+            cache_hits: self.0.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.0.cache_misses.load(Ordering::Relaxed),
+            coalesced: self.0.coalesced.load(Ordering::Relaxed),
+            path_allocations: self.0.path_allocations.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The ratio of the entry lookups which were served by the cache,
+    /// `0.0` if there was no lookup.
+    #[must_use]
+    pub fn cache_hit_rate(&self) -> f64 {
+        let hits = self.0.cache_hits.load(Ordering::Relaxed);
+        let misses = self.0.cache_misses.load(Ordering::Relaxed);
+        if hits + misses == 0 {
+            0.0
+        } else {
+            hits as f64 / (hits + misses) as f64
+        }
+    }
+
+    /// `Duration::ZERO` if there was no resolution.
+    #[must_use]
+    pub fn average_duration(&self) -> Duration {
+        let resolutions = self.0.resolutions.load(Ordering::Relaxed);
+        if resolutions == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos(self.0.duration.load(Ordering::Relaxed) / resolutions)
+        }
+    }
+
+    pub fn reset(&self) {
+        self.0.resolutions.store(0, Ordering::Relaxed);
+        self.0.failures.store(0, Ordering::Relaxed);
+        self.0.cache_hits.store(0, Ordering::Relaxed);
+        self.0.cache_misses.store(0, Ordering::Relaxed);
+        self.0.coalesced.store(0, Ordering::Relaxed);
+        self.0.path_allocations.store(0, Ordering::Relaxed);
+        self.0.duration.store(0, Ordering::Relaxed);
+    }
+}
+
+impl Resolver {
+    /// Returns a handle which shares the counters with this resolver.
+    #[must_use]
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
+}
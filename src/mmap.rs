@@ -0,0 +1,130 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek},
+    os::unix::io::AsRawFd,
+    path::Path,
+};
+
+/// The description files smaller than this are read as usual, as mapping
+/// them costs more than copying them.
+const MMAP_THRESHOLD: u64 = 64 * 1024;
+
+/// A read-only private mapping of a whole file, its bytes are only lent to
+/// the parser by `read_mapped`.
+struct MappedFile {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+impl MappedFile {
+    fn new(file: &File, len: usize) -> io::Result<Self> {
+        // SAFETY: `file` is open for reading, and `len` is not zero.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    /// # Safety
+    ///
+    /// The file should not be changed by others while the bytes are
+    /// borrowed, the pages which are not copied yet show the changes, and
+    /// the pages past the end of a truncated file raise `SIGBUS`.
+    unsafe fn bytes(&self) -> &[u8] {
+        std::slice::from_raw_parts(self.ptr as *const u8, self.len)
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `len` are what `mmap` returned.
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+/// Passes the content of the file to `parse`, the file is mapped if it is
+/// larger than `MMAP_THRESHOLD`, otherwise it is read.
+///
+/// The mapping never outlives `parse`. The file is stated again after it is
+/// parsed, and read as usual and parsed again if it was changed meanwhile,
+/// so a result parsed from the bytes of a concurrent write is discarded.
+/// A file truncated while it is parsed still raises `SIGBUS`, which is why
+/// the `mmap-description` feature should not be enabled for the trees which
+/// are changed while they are resolved, such as by `npm install` next to a
+/// dev server.
+pub(crate) fn read_mapped(path: &Path, parse: &mut dyn FnMut(&[u8])) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let before = file.metadata()?;
+    if before.len() >= MMAP_THRESHOLD {
+        let len = usize::try_from(before.len())
+            .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        let mapped = MappedFile::new(&file, len)?;
+        // SAFETY: the bytes are only borrowed by `parse`, see the above for
+        // the changes of the file meanwhile.
+        parse(unsafe { mapped.bytes() });
+        drop(mapped);
+        let after = file.metadata()?;
+        if after.len() == before.len() && after.modified().ok() == before.modified().ok() {
+            return Ok(());
+        }
+        tracing::debug!(
+            "{} was changed while it was parsed, read it again",
+            path.display()
+        );
+        file.rewind()?;
+    }
+    let mut bytes = Vec::with_capacity(before.len() as usize);
+    file.read_to_end(&mut bytes)?;
+    parse(&bytes);
+    Ok(())
+}
+
+#[test]
+fn read_mapped_test() {
+    let read = |path: &Path| {
+        let mut content = vec![];
+        read_mapped(path, &mut |bytes| content = bytes.to_vec()).map(|_| content)
+    };
+    let dir = std::env::temp_dir().join(format!("mmap-description-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let small = dir.join("small.json");
+    std::fs::write(&small, "{}").unwrap();
+    assert_eq!(read(&small).unwrap(), b"{}");
+    let large = dir.join("large.json");
+    let content = format!(
+        "{{\"padding\": \"{}\"}}",
+        "a".repeat(MMAP_THRESHOLD as usize)
+    );
+    std::fs::write(&large, &content).unwrap();
+    assert_eq!(read(&large).unwrap(), content.as_bytes());
+
+    // the file changed while it was parsed is parsed again from the new content.
+    let changed = format!(
+        "{{\"padding\": \"{}\"}}",
+        "b".repeat(MMAP_THRESHOLD as usize * 2)
+    );
+    let mut parsed = vec![];
+    read_mapped(&large, &mut |bytes| {
+        if parsed.is_empty() {
+            std::fs::write(&large, &changed).unwrap();
+        }
+        parsed.push(bytes.len());
+    })
+    .unwrap();
+    assert_eq!(parsed, vec![content.len(), changed.len()]);
+
+    assert!(read(&dir.join("missing.json")).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
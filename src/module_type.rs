@@ -0,0 +1,94 @@
+use crate::Resource;
+use std::path::Path;
+
+/// The kind of the resolved file, which decides the loader of it.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ModuleType {
+    Js,
+    Json,
+    /// The addons compiled from C++, such as `addon.node`.
+    NativeAddon,
+    Wasm,
+    Css,
+    Unknown,
+}
+
+/// The module format of a JavaScript file, see `Resource::js_format`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum JsFormat {
+    CommonJs,
+    Module,
+}
+
+impl ModuleType {
+    /// The default of `Options::module_types`.
+    #[must_use]
+    pub fn default_table() -> Vec<(String, ModuleType)> {
+        [
+            (".js", Self::Js),
+            (".mjs", Self::Js),
+            (".cjs", Self::Js),
+            (".jsx", Self::Js),
+            (".ts", Self::Js),
+            (".mts", Self::Js),
+            (".cts", Self::Js),
+            (".tsx", Self::Js),
+            (".json", Self::Json),
+            (".node", Self::NativeAddon),
+            (".wasm", Self::Wasm),
+            (".css", Self::Css),
+        ]
+        .into_iter()
+        .map(|(extension, module_type)| (String::from(extension), module_type))
+        .collect()
+    }
+
+    /// Returns the type of the first extension in `table` which the file name
+    /// of `path` ends with.
+    pub(crate) fn infer(path: &Path, table: &[(String, ModuleType)]) -> Self {
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            return Self::Unknown;
+        };
+        table
+            .iter()
+            .find(|(extension, _)| file_name.ends_with(extension.as_str()))
+            .map_or(Self::Unknown, |(_, module_type)| *module_type)
+    }
+}
+
+impl Resource {
+    /// Returns the format of a JavaScript file as Node.js determines it,
+    /// `.mjs` and `.mts` are ES modules, `.cjs` and `.cts` are CommonJS,
+    /// and the others follow the `"type"` of the package.
+    ///
+    /// It is `None` if `module_type` is not `ModuleType::Js`.
+    #[must_use]
+    pub fn js_format(&self) -> Option<JsFormat> {
+        if self.module_type != ModuleType::Js {
+            return None;
+        }
+        let extension = self.path.extension().and_then(|ext| ext.to_str());
+        let format = match extension {
+            Some("mjs" | "mts") => JsFormat::Module,
+            Some("cjs" | "cts") => JsFormat::CommonJs,
+            _ => {
+                let is_module = self.description.as_ref().map_or(false, |description| {
+                    description
+                        .data()
+                        .raw()
+                        .get("type")
+                        .and_then(|ty| ty.as_str())
+                        == Some("module")
+                });
+                if is_module {
+                    JsFormat::Module
+                } else {
+                    JsFormat::CommonJs
+                }
+            }
+        };
+        Some(format)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    info::NormalizedPath,
+    kind::PathKind,
+    resolve::{get_module_name_from_request, ModulesDirStrategy},
+    Resolver,
+};
+use rustc_hash::FxHashSet;
+use std::path::{Path, PathBuf};
+
+/// A directory which a module request is looked up in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModulesPath {
+    pub path: PathBuf,
+    pub exists: bool,
+}
+
+impl Resolver {
+    /// Returns the directories which `request` is looked up in from `path`,
+    /// in the order of `modules`, as `require.resolve.paths` in Node.js does.
+    /// It helps to explain why a module request failed, though the
+    /// directories which the failed resolution looked up are carried by
+    /// `Error::NotFound`.
+    ///
+    /// It is `None` if `request` is not a module request, such as `./a`.
+    pub fn modules_paths(&self, path: &Path, request: &str) -> Option<Vec<ModulesPath>> {
+        let request = Self::parse(request);
+        if !matches!(request.kind(), PathKind::Normal) {
+            return None;
+        }
+        let module_name = get_module_name_from_request(request.target());
+        let mut visited = FxHashSet::default();
+        let mut paths = vec![];
+        self.collect_modules_paths(
+            NormalizedPath::new(path).as_ref(),
+            module_name,
+            false,
+            &mut visited,
+            &mut paths,
+        );
+        Some(paths)
+    }
+
+    /// Follows the order of `resolve_as_modules`.
+    fn collect_modules_paths(
+        &self,
+        dir: &Path,
+        module_name: &str,
+        finding_up: bool,
+        visited: &mut FxHashSet<PathBuf>,
+        paths: &mut Vec<ModulesPath>,
+    ) {
+        if !visited.insert(dir.to_path_buf()) {
+            return;
+        }
+        for module in &self.options.modules {
+            let strategy = ModulesDirStrategy::new(module);
+            if !strategy.is_in_scope(self, module_name) {
+                continue;
+            }
+            match strategy {
+                ModulesDirStrategy::Hierarchical(name) => {
+                    self.push_modules_path(dir.join(name), paths);
+                    if self.is_stop_at(dir) {
+                        continue;
+                    }
+                    if let Some(parent) = dir.parent() {
+                        self.collect_modules_paths(parent, module_name, true, visited, paths);
+                    }
+                }
+                ModulesDirStrategy::Absolute(_) if finding_up => continue,
+                ModulesDirStrategy::Absolute(store) => {
+                    self.push_modules_path(store.to_path_buf(), paths);
+                }
+            }
+        }
+        if !finding_up {
+            for folder in &self.global_folders {
+                self.push_modules_path(folder.clone(), paths);
+            }
+        }
+    }
+
+    fn push_modules_path(&self, path: PathBuf, paths: &mut Vec<ModulesPath>) {
+        if paths.iter().any(|item| item.path == path) {
+            return;
+        }
+        let exists = self.load_entry(&path).is_dir();
+        paths.push(ModulesPath { path, exists });
+    }
+}
@@ -0,0 +1,78 @@
+use crate::roots;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+/// The folders which `require` looks up at last, as `Module.globalPaths` of
+/// Node.js: the paths in `NODE_PATH`, `$HOME/.node_modules`,
+/// `$HOME/.node_libraries` and `$PREFIX/lib/node`, where `$PREFIX` is the
+/// installation of the `node` found in `PATH`.
+pub(crate) fn global_folders() -> Vec<PathBuf> {
+    let home = std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" });
+    collect(
+        std::env::var_os("NODE_PATH"),
+        home.map(PathBuf::from),
+        node_prefix(),
+    )
+}
+
+fn collect(
+    node_path: Option<OsString>,
+    home: Option<PathBuf>,
+    prefix: Option<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = node_path
+        .iter()
+        .flat_map(std::env::split_paths)
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(|path| roots::absolute(&path))
+        .collect();
+    if let Some(home) = home.filter(|home| !home.as_os_str().is_empty()) {
+        folders.push(home.join(".node_modules"));
+        folders.push(home.join(".node_libraries"));
+    }
+    if let Some(prefix) = prefix {
+        folders.push(prefix.join("lib").join("node"));
+    }
+    folders
+}
+
+/// The directory which `node` is installed in, `process.execPath` is
+/// `$PREFIX/bin/node`, or `$PREFIX\node.exe` on Windows.
+fn node_prefix() -> Option<PathBuf> {
+    let name = if cfg!(windows) { "node.exe" } else { "node" };
+    let exec_path = std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())?;
+    // `process.execPath` is the real path of the executable.
+    let exec_path = dunce::canonicalize(&exec_path).unwrap_or(exec_path);
+    let bin = exec_path.parent()?;
+    if cfg!(windows) {
+        Some(bin.to_path_buf())
+    } else {
+        bin.parent().map(Path::to_path_buf)
+    }
+}
+
+#[test]
+fn collect_test() {
+    let separator = if cfg!(windows) { ";" } else { ":" };
+    let root = if cfg!(windows) { "C:\\" } else { "/" };
+    let node_path = format!("{root}a{separator}{separator}{root}b");
+    assert_eq!(
+        collect(
+            Some(node_path.into()),
+            Some(PathBuf::from(root).join("home")),
+            Some(PathBuf::from(root).join("usr"))
+        ),
+        vec![
+            PathBuf::from(root).join("a"),
+            PathBuf::from(root).join("b"),
+            PathBuf::from(root).join("home").join(".node_modules"),
+            PathBuf::from(root).join("home").join(".node_libraries"),
+            PathBuf::from(root).join("usr").join("lib").join("node"),
+        ]
+    );
+    assert!(collect(None, None, None).is_empty());
+}
@@ -0,0 +1,26 @@
+use crate::{Error, ResolveResult, Resource};
+use std::path::Path;
+
+/// Receives the events of resolution, it can be used to log, collect metrics
+/// or display the progress without enabling `RESOLVER_TRACE`.
+///
+/// All methods are no-op by default, so it only needs to implement the
+/// events which are cared about.
+pub trait Observer: std::fmt::Debug + Send + Sync {
+    /// Called once when `Resolver::resolve` is invoked.
+    fn on_resolve_start(&self, _path: &Path, _request: &str) {}
+
+    /// Called when a path is probed as the possible result.
+    fn on_candidate(&self, _path: &Path) {}
+
+    /// Called when the request had been resolved or ignored.
+    fn on_success(&self, _path: &Path, _request: &str, _result: &ResolveResult<Resource>) {}
+
+    /// Called when the result is inside the directory `dir` of
+    /// `Options::suspicious_results` whose action is `Warn`, before
+    /// `on_success`.
+    fn on_suspicious_result(&self, _path: &Path, _request: &str, _result: &Resource, _dir: &str) {}
+
+    /// Called when the request can't be resolved.
+    fn on_failure(&self, _path: &Path, _request: &str, _error: &Error) {}
+}
@@ -1,23 +1,251 @@
-use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use crate::Cache;
+use crate::{
+    AfterResolve, BeforeResolve, Cache, Error, ErrorRenderer, ModuleType, Observer,
+    ProtocolHandler, SuspiciousResult, WalkExclude,
+};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AliasMap {
     Target(String),
     Ignored,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum EnforceExtension {
     Enabled,
     Disabled,
     Auto,
 }
 
+/// When to try the candidates mapped by `baseUrl` and `paths` in tsconfig.
+#[cfg(feature = "tsconfig")]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TsconfigPathsOrder {
+    /// Try the mapped candidates first, which is the behavior of `tsc`.
+    BeforeModules,
+    /// Resolve the request as usual first, try the mapped candidates if it failed.
+    AfterModules,
+}
+
+/// The resolution algorithm, see `Options::algorithm`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Algorithm {
+    /// Resolve as `enhanced-resolve` does, every option is respected.
+    Enhanced,
+    /// Follow the ESM resolution of Node.js: the relative requests must be
+    /// fully specified, directories are not resolved to their index files,
+    /// the `exports` of packages are always enforced, and the requests can
+    /// be `file:` URLs or percent-encoded.
+    NodeEsm,
+    /// Follow `require()` of Node.js: the extensions are `.js`, `.json` and
+    /// `.node`, the modules are only looked up in `node_modules`, and the
+    /// `exports` of packages are always enforced.
+    NodeCjs,
+}
+
+impl Algorithm {
+    /// Overrides the options which conflict with the algorithm.
+    pub(crate) fn apply(self, options: Options) -> Options {
+        let (condition, fully_specified) = match self {
+            Self::Enhanced => return options,
+            Self::NodeEsm => ("import", true),
+            Self::NodeCjs => ("require", false),
+        };
+        let condition_names = if options.condition_names.is_empty() {
+            HashSet::from([String::from("node"), String::from(condition)])
+        } else {
+            options.condition_names
+        };
+        Options {
+            // for ESM, the extensions and the main files are only used by
+            // the packages without `exports`, as `LEGACY_MAIN_RESOLVE`.
+            extensions: vec![
+                String::from(".js"),
+                String::from(".json"),
+                String::from(".node"),
+            ],
+            enforce_extension: EnforceExtension::Disabled,
+            main_files: vec![String::from("index")],
+            main_fields: vec![String::from("main")],
+            nested_main_fields: NestedMainFields::Main,
+            browser_field: false,
+            prefer_relative: false,
+            condition_names,
+            modules: vec![String::from("node_modules")],
+            exports_field: vec![vec![String::from("exports")]],
+            fully_specified,
+            resolve_to_context: false,
+            strict_directory_requests: true,
+            dot_requests: DotRequests::Node,
+            strict_exports: true,
+            allow_manifest_access: false,
+            algorithm: self,
+            ..options
+        }
+    }
+}
+
+/// How the requests naming a directory without the trailing slash, which
+/// are `""`, `.` and `..`, are resolved, see `Options::dot_requests`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DotRequests {
+    /// They are resolved as a file first, such as `.` in `src` to `src.js`,
+    /// then as a directory.
+    Lenient,
+    /// As Node.js, `""` is invalid, `.` and `..` are only resolved as the
+    /// directories, the same as `./` and `../`.
+    Node,
+}
+
+/// Whether a request is resolved as a file or a directory first, or only one of them.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ProbeOrder {
+    FileFirst,
+    /// Try the main fields and the main files of the directory first, such as
+    /// resolving `./button` to `./button/index.css` rather than `./button.css`.
+    DirFirst,
+    FileOnly,
+    DirOnly,
+}
+
+/// Which file wins when both the request and the request with an extension
+/// appended exist, such as `./a.js` and `./a.js.ts`, see
+/// `Options::extension_conflict_policy`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ExtensionConflictPolicy {
+    /// The file named by the request, as `enhanced-resolve` and Node.js do.
+    PreferExplicit,
+    /// The file with an extension appended, such as the sources generated
+    /// beside the outputs.
+    PreferAppended,
+}
+
+/// What to do with the inline loaders of webpack in a request, such as
+/// `style-loader!./a.css`, see `Options::loader_syntax`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LoaderSyntax {
+    /// Resolve the request as it is, `!` is a part of the path.
+    PassThrough,
+    /// Drop the loaders and the prefixes such as `-!`, and resolve the
+    /// resource, such as `./a.css`.
+    Strip,
+    /// Fail to resolve the requests with `!`.
+    Error,
+    /// Same as `Strip`, but `Resolver::resolve_with_loaders` resolves the
+    /// loaders too.
+    Resolve,
+}
+
+/// Which main fields are read from the nested description files of the
+/// packages, such as `esm/package.json`, see `Options::nested_main_fields`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum NestedMainFields {
+    /// Every field in `main_fields`, as `enhanced-resolve` does.
+    All,
+    /// Only `"main"`, as `require()` of Node.js does.
+    Main,
+    /// None of them, the directories are resolved to the main files.
+    Ignore,
+}
+
+/// A step of `ProbeOrder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Probe {
+    File,
+    Dir,
+}
+
+impl ProbeOrder {
+    pub(crate) fn probes(self) -> &'static [Probe] {
+        match self {
+            Self::FileFirst => &[Probe::File, Probe::Dir],
+            Self::DirFirst => &[Probe::Dir, Probe::File],
+            Self::FileOnly => &[Probe::File],
+            Self::DirOnly => &[Probe::Dir],
+        }
+    }
+}
+
+/// Which fields of the description files are kept in the cache.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DescriptionRetention {
+    /// Keep the whole file, which is exposed by `Resource::description`.
+    Full,
+    /// Only keep the fields used in resolving, they are `name`, `version`,
+    /// `type`, `main`, `browser`, `imports`, `exports`, and the fields in
+    /// `main_fields` and `exports_field`. The fields of all the resolvers
+    /// sharing `external_cache` are kept, such as the children of
+    /// `ResolverFactory` with different `main_fields`.
+    ResolutionFields,
+}
+
+/// The separators of the returned paths.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PathStyle {
+    /// `\` on Windows and `/` on the others.
+    Native,
+    /// Always `/`, for the hosts which key the modules by such IDs.
+    ForwardSlash,
+}
+
+impl PathStyle {
+    pub(crate) fn apply(self, path: PathBuf) -> PathBuf {
+        match self {
+            Self::Native => path,
+            // `\` is a valid character of file names on the others.
+            Self::ForwardSlash if cfg!(windows) => match path.to_str() {
+                Some(str) if str.contains('\\') => PathBuf::from(str.replace('\\', "/")),
+                _ => path,
+            },
+            Self::ForwardSlash => path,
+        }
+    }
+}
+
 pub type Alias = Vec<(String, Vec<AliasMap>)>;
 
+pub type MalformedDescriptionCallback = Arc<dyn Fn(&Path, &Error) + Send + Sync>;
+
+/// How to deal with the description file which can't be parsed.
+#[derive(Clone)]
+pub enum MalformedDescription {
+    /// Stop resolving and return the error.
+    Error,
+    /// Continue resolving as if the description file does not exist.
+    Ignore,
+    /// Same as `Ignore`, but call the callback with the path and the error first.
+    Warn(MalformedDescriptionCallback),
+}
+
+impl std::fmt::Debug for MalformedDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "Error"),
+            Self::Ignore => write!(f, "Ignore"),
+            Self::Warn(_) => write!(f, "Warn(<callback>)"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Options {
     /// Tried detect file with this extension.
     /// Default is `[".js", ".json", ".node"]`
@@ -25,6 +253,19 @@ pub struct Options {
     /// Enforce that a extension from extensions must be used.
     /// Default is `Auto`.
     pub enforce_extension: EnforceExtension,
+    /// Whether the requests which already end with an extension which is
+    /// not in `extensions`, such as `./logo.svg`, are fully specified, only
+    /// the literal path is tried as a file, so the assets don't waste the
+    /// probes, such as `./logo.svg.js`. Note that the names with dots are
+    /// also treated as so, such as `./jquery.min`.
+    /// Default is `false`.
+    pub resolve_unknown_extensions: bool,
+    /// Which file wins when both the request and the request with an item
+    /// of `extensions` appended exist, such as `./a.js` and `./a.js.ts`.
+    /// It does not matter if `enforce_extension` is `Enabled`, which never
+    /// tries the request itself.
+    /// Default is `ExtensionConflictPolicy::PreferExplicit`.
+    pub extension_conflict_policy: ExtensionConflictPolicy,
     /// Maps key to value.
     /// Default is `vec![]`.
     /// The reason for using `Vec` instead `HashMap` to keep the order.
@@ -39,41 +280,204 @@ pub struct Options {
     /// - If `external_cache` is `None`, use default cache in resolver.
     /// - If `external_cache.is_some()` is true, use this cache.
     ///
+    /// The cache keeps what is read under the options of the resolvers, so
+    /// the resolvers sharing it should use the same values of these fields,
+    /// otherwise one of them sees what another one read under its own:
+    ///
+    /// - `description_retention`, the resolvers which keep the whole files
+    ///   see the ones trimmed for the others.
+    /// - `description_search_roots`, the lookup of the description files is
+    ///   cached with the boundary which stopped it.
+    ///
     /// Default is `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub external_cache: Option<Arc<Cache>>,
+    /// Whether the identical requests resolved at the same time by many
+    /// threads share one resolution, the others wait for it and take its
+    /// result, instead of probing the same files again. The requests are
+    /// identical if the directories and the requests are the same before
+    /// `before_resolve`. The waiters are not reported to `observer`, and
+    /// are counted by `MetricsSnapshot::coalesced`.
+    /// The hooks and the protocol handlers must not resolve the same request
+    /// in the same directory again, which waits for itself.
+    /// Default is `false`.
+    pub coalesce_requests: bool,
+    /// The longest time of a resolution, it fails with `Error::Timeout` once
+    /// the time is exceeded, such as the stats hang on a network file system.
+    /// It is checked between the probes, so a probe which never returns
+    /// can't be interrupted.
+    /// Default is `None`.
+    pub timeout: Option<Duration>,
+    /// Whether the candidates which can't be accessed, such as the stats are
+    /// denied as a directory can't be searched, are skipped as the missing
+    /// files. Otherwise the resolution fails with `Error::PermissionDenied`.
+    /// Default is `false`.
+    pub ignore_permission_denied: bool,
     /// Whether to resolve the real path when the result
     /// is a symlink.
     /// Default is `true`.
     pub symlinks: bool,
+    /// Whether the real paths of the symlinks can be outside of `roots`,
+    /// if it is `false`, such results are rejected with an error, which
+    /// is useful to resolve untrusted projects.
+    /// Default is `true`.
+    pub allow_symlink_escape: bool,
+    /// Whether the directories which are the symlinks of the same package,
+    /// such as the copies linked into the workspaces by pnpm, share the
+    /// description file read at the real path, and the targets of `exports`
+    /// cached with it, rather than reading it for each of them. The results
+    /// report both paths by `Resource::logical_path` and `Resource::real_path`.
+    /// Default is `false`.
+    pub dedupe_by_realpath: bool,
+    /// The directories of the project, an empty list means the current
+    /// working directory.
+    /// Default is `[]`.
+    pub roots: Vec<PathBuf>,
+    /// Whether to restrict the probes to `roots`, the paths outside of them
+    /// are treated as missing without touching the file system, and can be
+    /// audited by `Resolver::resolve_sandboxed`. A sandboxed resolver which
+    /// shares `external_cache` keeps its own entries, only the files read
+    /// are shared with the others.
+    /// Default is `false`.
+    pub sandbox: bool,
     /// A JSON file to describing this lib information.
     /// Default is `"package.json"`.
     pub description_file: String,
+    /// The separators of the returned paths, which are always absolute and
+    /// without `.` or `..`, and the drive letters are uppercase on Windows.
+    /// Default is `PathStyle::Native`.
+    pub output_path_style: PathStyle,
+    /// If it is set, the returned paths are relative to it, and the symlinks
+    /// in it are not resolved to the real paths outside of it, so the results
+    /// are the same on all machines, which is required by remote caching.
+    /// Default is `None`.
+    pub workspace_root: Option<PathBuf>,
+    /// Which fields of the description files are kept in the cache, the
+    /// memory of huge dependency trees can be cut by dropping the others.
+    /// Default is `DescriptionRetention::Full`.
+    pub description_retention: DescriptionRetention,
+    /// The boundaries of looking up the description files in the ancestors,
+    /// such as the root of the project, so the network-mounted home
+    /// directories above it are never read. The description files in the
+    /// boundaries themselves are still read, and the boundary which stopped
+    /// the lookup is reported by `Resource::description_boundary`.
+    /// Default is `[]`, which looks up to the root of the file system.
+    pub description_search_roots: Vec<PathBuf>,
     /// Resolve to a context instead of a file.
     /// Default is `false`
     pub resolve_to_context: bool,
+    /// The files and the directories which are skipped by the directory
+    /// walks, which are `Resolver::resolve_context` and the expansion of the
+    /// patterns in `Resolver::package_entries`, such as `.git` and the tests.
+    /// Default is `WalkExclude::default()`, which skips nothing.
+    pub walk_exclude: WalkExclude,
     /// Main file in this directory.
     /// Default is `["index"]`.
     pub main_files: Vec<String>,
     /// Main fields in Description.
     /// Default is `["main"]`.
     pub main_fields: Vec<String>,
+    /// The main fields which are read from the nested description files
+    /// when their directories are resolved, which are the description files
+    /// without `name` inside a package, such as `esm/package.json` which
+    /// declares `"type"` and `"main"`.
+    /// Default is `All`.
+    pub nested_main_fields: NestedMainFields,
     /// Whether read and parse `"browser"` filed
     /// in package.json.
     /// Default is `false`
     pub browser_field: bool,
+    /// The directory of the application package, its `browser` and `imports`
+    /// fields are also applied to the requests issued inside it but outside
+    /// of `node_modules`, even if a nearer description file shadows it, such
+    /// as `src/package.json` which only declares `"type"`.
+    /// Default is `None`.
+    pub root_package: Option<PathBuf>,
+    /// The directory of the root package.json, its npm `overrides` and yarn
+    /// `resolutions` redirect the module requests to the chosen versions or
+    /// paths, such as `{ "bar": { "foo": "1.0.0" } }` for the requests of
+    /// `foo` from `bar`. The versions are chosen from the copies installed
+    /// in its `node_modules`, including the nested ones and the stores such
+    /// as `.pnpm`, which are scanned once with the rules, the requests are
+    /// looked up as usual if none of them satisfies the range.
+    /// Default is `None`.
+    pub package_overrides: Option<PathBuf>,
+    /// Whether the module requests issued inside the isolated stores, such as
+    /// `node_modules/.pnpm`, only find the packages declared in the
+    /// `dependencies`, `peerDependencies` and `optionalDependencies` of the
+    /// issuer, and the copies which satisfy the declared ranges, rather than
+    /// any copy found in the ancestors, such as a hoisted peer of another
+    /// version.
+    /// Default is `false`.
+    pub isolated_mode: bool,
+    /// Whether the module requests which can't be resolved return
+    /// `ResolveResult::OptionalMissing` rather than failing, if they are
+    /// the optional peer dependencies of the issuer, which are declared by
+    /// `peerDependenciesMeta`, so the bundlers can stub them quietly. The
+    /// peers found in any of the looked up modules directories still fail,
+    /// such as by a missing subpath.
+    /// Default is `false`.
+    pub report_optional_peers: bool,
+    /// The checks of the results which are rarely the modules, such as the
+    /// files inside `node_modules/.bin`, which catch the misconfigured
+    /// `exports` early, see `SuspiciousResult::recommended`.
+    /// Default is `[]`.
+    pub suspicious_results: Vec<SuspiciousResult>,
+    /// The fields of the description files which are read from the package
+    /// root, the nearest description file with `name`, instead of the nearest
+    /// one, which Node.js reads. It can be `"imports"` and `"browser"`, so the
+    /// nested description files, such as `esm/package.json` which only
+    /// declares `"type"`, don't shadow them.
+    /// Default is `[]`.
+    pub package_root_fields: Vec<String>,
     /// Condition names for exports filed. Note that its type is a `HashSet`,
     /// because the priority is related to the order in which the export field
     /// fields are written.
     /// Default is `[]`.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "crate::serialize::sorted"))]
     pub condition_names: HashSet<String>,
     /// When this filed exists, it tries to read `baseURL`
     /// and `paths` in the corresponding tsconfig,
     /// and processes the mappings.
     /// Default is `None`.
+    #[cfg(feature = "tsconfig")]
     pub tsconfig: Option<PathBuf>,
+    /// Whether the candidates mapped by tsconfig are tried before or
+    /// after resolving the request as usual.
+    /// Default is `TsconfigPathsOrder::BeforeModules`.
+    #[cfg(feature = "tsconfig")]
+    pub tsconfig_paths_order: TsconfigPathsOrder,
+    /// Whether to continue resolving the request as usual when it matched
+    /// a pattern in `paths` but none of the candidates can be resolved,
+    /// otherwise the resolution fails.
+    /// Default is `true`.
+    #[cfg(feature = "tsconfig")]
+    pub tsconfig_paths_fallthrough: bool,
     /// A list of directories to resolve modules from, can be absolute path or folder name.
     /// Default is `["node_modules"]`
     pub modules: Vec<String>,
+    /// Restricts the items of `modules` to the packages which match the patterns,
+    /// for example, `("/shared/store", ["@corp/*"])` only consults the store for `@corp/*`.
+    /// The items which are not listed are consulted for all packages.
+    /// Default is `[]`.
+    pub modules_scope: Vec<(String, Vec<String>)>,
+    /// The boundary of looking up the folders in `modules` in the ancestors,
+    /// such as the root of the project, so the requests issued inside it
+    /// never escape into the home directory or the global `node_modules`.
+    /// The folders in the boundary itself are still looked up.
+    /// Default is `None`.
+    pub stop_at: Option<PathBuf>,
+    /// Whether to look up the global folders of Node.js at last, which are
+    /// the paths in the `NODE_PATH` environment variable, `$HOME/.node_modules`,
+    /// `$HOME/.node_libraries` and `$PREFIX/lib/node`, so the CLI tools can
+    /// match `require` exactly. They are read when the resolver is created.
+    /// Default is `false`.
+    pub use_node_path: bool,
+    /// The requests starting with these prefixes are path aliases of the frameworks,
+    /// such as `@/components/button`, so they are never looked up in `modules`,
+    /// and can only be resolved by `alias`, `fallback` or tsconfig `paths`.
+    /// Default is `["@/"]`.
+    pub module_alias_prefixes: Vec<String>,
     /// Same as `alias`, but only used if default resolving fails.
     /// Default is `[]`.
     pub fallback: Alias,
@@ -81,12 +485,80 @@ pub struct Options {
     /// extensions or main files are not resolved for it.
     /// Default is `false`.
     pub fully_specified: bool,
+    /// Whether a request ending with `/` can only be resolved to a directory,
+    /// which is the behavior of Node.js. If it is `false`, the trailing slash
+    /// is ignored when looking for files, so `./a/` can be resolved to `./a.js`,
+    /// but the file is still preferred as the request without the slash.
+    /// Default is `true`.
+    pub strict_directory_requests: bool,
+    /// How `""`, `.` and `..` are resolved, `Algorithm::NodeCjs` and
+    /// `Algorithm::NodeEsm` always use `DotRequests::Node`.
+    /// Default is `DotRequests::Lenient`.
+    pub dot_requests: DotRequests,
+    /// The order to resolve the relative and absolute requests as a file
+    /// or a directory.
+    /// Default is `ProbeOrder::FileFirst`.
+    pub probe_order: ProbeOrder,
+    /// Same as `probe_order`, but for the requests which are located in
+    /// `modules`, such as `pkg` and `pkg/lib/button`.
+    /// Default is `ProbeOrder::FileFirst`.
+    pub modules_probe_order: ProbeOrder,
     /// A list of exports fields in descriptions files
     /// Default is `[["exports"]]`.
     pub exports_field: Vec<Vec<String>>,
+    /// Whether the subpaths which are not exported, including the ones blocked
+    /// by `null` such as `"./internal/*": null`, are rejected with an error.
+    /// If it is `false`, they fall through to the files in the package.
+    /// Default is `true`.
+    pub strict_exports: bool,
+    /// Whether the description file of a package, such as `pkg/package.json`,
+    /// can be resolved even if `exports` does not export it, as webpack does,
+    /// so the tools can read the manifests of the dependencies through the
+    /// resolver. Otherwise it fails with `Error::NotExported` as Node.js does.
+    /// Default is `false`.
+    pub allow_manifest_access: bool,
     /// A vector which maps extension to extension aliases.
     /// Default is `[]`.
     pub extension_alias: Vec<(String, Vec<String>)>,
+    /// Maps the extensions to the types of the results, the first extension
+    /// which the file name ends with is used, otherwise it is `ModuleType::Unknown`.
+    /// Default is `ModuleType::default_table()`.
+    pub module_types: Vec<(String, ModuleType)>,
+    /// The resolution algorithm, the options which conflict with it are
+    /// overridden when the resolver is created.
+    /// Default is `Algorithm::Enhanced`.
+    pub algorithm: Algorithm,
+    /// Receives the events during resolving.
+    /// Default is `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub observer: Option<Arc<dyn Observer>>,
+    /// What to do when the description file is malformed.
+    /// Default is `MalformedDescription::Error`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub on_malformed_description: MalformedDescription,
+    /// Rewrites the request before it is parsed.
+    /// Default is `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub before_resolve: Option<Arc<dyn BeforeResolve>>,
+    /// The inline loaders of webpack in the requests, such as
+    /// `style-loader!./a.css`, they are handled after `before_resolve`.
+    /// Default is `LoaderSyntax::PassThrough`.
+    pub loader_syntax: LoaderSyntax,
+    /// Accepts, rewrites or rejects the successful result.
+    /// Default is `None`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub after_resolve: Option<Arc<dyn AfterResolve>>,
+    /// The handlers keyed by the protocols, such as `"virtual:"`, the first
+    /// one matched the request resolves it. The requests with an unknown
+    /// protocol are resolved as usual.
+    /// Default is the handlers of `"node:"` and `"file:"`, see `NodeProtocol`
+    /// and `FileProtocol`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub protocols: Vec<(String, Arc<dyn ProtocolHandler>)>,
+    /// Formats the errors in `Resolver::render_error`.
+    /// Default is `None`, which means `PrettyRenderer`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub error_renderer: Option<Arc<dyn ErrorRenderer>>,
 }
 
 impl Default for Options {
@@ -98,40 +570,158 @@ impl Default for Options {
         ];
         let main_files = vec![String::from("index")];
         let main_fields = vec![String::from("main")];
+        let nested_main_fields = NestedMainFields::All;
         let description_file = String::from("package.json");
+        let description_retention = DescriptionRetention::Full;
+        let output_path_style = PathStyle::Native;
+        let workspace_root = None;
         let alias = vec![];
         let symlinks = true;
+        let allow_symlink_escape = true;
+        let dedupe_by_realpath = false;
+        let roots = vec![];
+        let sandbox = false;
         let browser_field = false;
+        let root_package = None;
+        let package_overrides = None;
+        let isolated_mode = false;
+        let report_optional_peers = false;
+        let suspicious_results = vec![];
+        let package_root_fields = vec![];
         let condition_names = HashSet::default();
         let prefer_relative = false;
         let enforce_extension = EnforceExtension::Auto;
+        let resolve_unknown_extensions = false;
+        let extension_conflict_policy = ExtensionConflictPolicy::PreferExplicit;
+        #[cfg(feature = "tsconfig")]
         let tsconfig = None;
+        #[cfg(feature = "tsconfig")]
+        let tsconfig_paths_order = TsconfigPathsOrder::BeforeModules;
+        #[cfg(feature = "tsconfig")]
+        let tsconfig_paths_fallthrough = true;
         let external_cache = None;
+        let coalesce_requests = false;
+        let timeout = None;
+        let ignore_permission_denied = false;
         let resolve_to_context = false;
+        let walk_exclude = WalkExclude::default();
+        let description_search_roots = vec![];
         let modules = vec![String::from("node_modules")];
+        let modules_scope = vec![];
+        let stop_at = None;
+        let use_node_path = false;
+        let module_alias_prefixes = vec![String::from("@/")];
         let fallback = vec![];
         let fully_specified = false;
+        let strict_directory_requests = true;
+        let dot_requests = DotRequests::Lenient;
+        let probe_order = ProbeOrder::FileFirst;
+        let modules_probe_order = ProbeOrder::FileFirst;
         let exports_field = vec![vec![String::from("exports")]];
+        let strict_exports = true;
+        let allow_manifest_access = false;
         let extension_alias = vec![];
+        let module_types = ModuleType::default_table();
+        let algorithm = Algorithm::Enhanced;
+        let observer = None;
+        let on_malformed_description = MalformedDescription::Error;
+        let before_resolve = None;
+        let loader_syntax = LoaderSyntax::PassThrough;
+        let after_resolve = None;
+        let protocols = crate::protocol::builtin();
+        let error_renderer = None;
         Self {
             fallback,
             modules,
+            modules_scope,
+            stop_at,
+            use_node_path,
+            module_alias_prefixes,
             extensions,
             enforce_extension,
+            resolve_unknown_extensions,
+            extension_conflict_policy,
             alias,
             prefer_relative,
             external_cache,
+            coalesce_requests,
+            timeout,
+            ignore_permission_denied,
             symlinks,
+            allow_symlink_escape,
+            dedupe_by_realpath,
+            roots,
+            sandbox,
             description_file,
+            description_retention,
+            description_search_roots,
+            output_path_style,
+            workspace_root,
             resolve_to_context,
+            walk_exclude,
             main_files,
             main_fields,
+            nested_main_fields,
             browser_field,
+            root_package,
+            package_overrides,
+            isolated_mode,
+            report_optional_peers,
+            suspicious_results,
+            package_root_fields,
             condition_names,
+            #[cfg(feature = "tsconfig")]
             tsconfig,
+            #[cfg(feature = "tsconfig")]
+            tsconfig_paths_order,
+            #[cfg(feature = "tsconfig")]
+            tsconfig_paths_fallthrough,
             fully_specified,
+            strict_directory_requests,
+            dot_requests,
+            probe_order,
+            modules_probe_order,
             exports_field,
+            strict_exports,
+            allow_manifest_access,
             extension_alias,
+            module_types,
+            algorithm,
+            observer,
+            on_malformed_description,
+            before_resolve,
+            loader_syntax,
+            after_resolve,
+            protocols,
+            error_renderer,
+        }
+    }
+}
+
+impl Options {
+    /// The options for the packages published by `wasm-pack`, the `.wasm`
+    /// files can be imported without the extension, the `"wasm"` condition
+    /// of `exports` is preferred, and the `"module"` field generated by
+    /// `--target bundler` and `--target web` is used before `"main"`.
+    #[must_use]
+    pub fn wasm_pack() -> Self {
+        let extensions = vec![
+            String::from(".js"),
+            String::from(".wasm"),
+            String::from(".json"),
+            String::from(".node"),
+        ];
+        let main_fields = vec![String::from("module"), String::from("main")];
+        let condition_names = HashSet::from([
+            String::from("wasm"),
+            String::from("import"),
+            String::from("module"),
+        ]);
+        Self {
+            extensions,
+            main_fields,
+            condition_names,
+            ..Default::default()
         }
     }
 }
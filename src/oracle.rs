@@ -0,0 +1,241 @@
+//! Compares the results of `Algorithm::NodeCjs` with `require.resolve` of
+//! Node.js, on the small package trees generated from seeds.
+//!
+//! The same seed always generates the same tree and requests, so a
+//! mismatch found by the nightly job can be replayed by
+//! `cargo run --example oracle --features oracle -- <seed>`.
+
+use crate::{test_util::VirtualTree, Algorithm, Options, ResolveResult, Resolver};
+use serde_json::json;
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// xorshift64*, which is enough to pick the shapes of the trees.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // zero is the fixed point of xorshift.
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.next() % 100 < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+/// A generated tree, and the requests issued from the directories in it,
+/// the directories are relative to the root.
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub tree: VirtualTree,
+    pub requests: Vec<(PathBuf, String)>,
+}
+
+const FILES: [&str; 4] = ["a", "b", "lib/c", "lib/index"];
+const EXTENSIONS: [&str; 3] = [".js", ".json", ""];
+const PACKAGES: [&str; 3] = ["pkg", "other", "@scope/pkg"];
+
+/// Generates the tree and the requests of `seed`.
+#[must_use]
+pub fn generate(seed: u64) -> Case {
+    let mut rng = Rng::new(seed);
+    let mut tree = VirtualTree::new("/").file("src/.keep", "");
+    for file in FILES {
+        if rng.chance(60) {
+            let ext = rng.pick(&EXTENSIONS);
+            let content = if ext == ".json" { "{}" } else { "" };
+            tree = tree.file(format!("src/{file}{ext}"), content);
+        }
+    }
+    for package in PACKAGES {
+        if rng.chance(20) {
+            continue;
+        }
+        // some packages are nested in the `node_modules` of the others.
+        let dir = if rng.chance(25) {
+            format!("node_modules/pkg/node_modules/{package}")
+        } else {
+            format!("node_modules/{package}")
+        };
+        tree = tree.dir(&dir);
+        for file in FILES {
+            if rng.chance(50) {
+                tree = tree.file(format!("{file}{}", rng.pick(&EXTENSIONS)), "");
+            }
+        }
+        if rng.chance(80) {
+            tree = tree.file("package.json", manifest(&mut rng, package));
+        }
+    }
+
+    let dirs = ["src", "src/lib", "node_modules/pkg"];
+    let requests = [
+        "./a",
+        "./a.js",
+        "./b",
+        "./lib",
+        "./lib/c",
+        "../src/a",
+        ".",
+        "..",
+        "pkg",
+        "pkg/a",
+        "pkg/lib",
+        "pkg/lib/c.js",
+        "pkg/package.json",
+        "other",
+        "other/b",
+        "@scope/pkg",
+        "@scope/pkg/lib/index",
+    ];
+    let requests = (0..12)
+        .map(|_| {
+            let dir = PathBuf::from(rng.pick(&dirs));
+            (dir, rng.pick(&requests).to_string())
+        })
+        .collect();
+    Case { tree, requests }
+}
+
+fn manifest(rng: &mut Rng, name: &str) -> serde_json::Value {
+    let target = |rng: &mut Rng| format!("./{}{}", rng.pick(&FILES), rng.pick(&EXTENSIONS));
+    let mut manifest = json!({ "name": name });
+    if rng.chance(60) {
+        manifest["main"] = json!(target(rng).trim_start_matches("./"));
+    }
+    if rng.chance(40) {
+        manifest["exports"] = match rng.below(3) {
+            0 => json!(target(rng)),
+            1 => json!({ ".": target(rng), "./a": target(rng), "./lib/*": "./lib/*.js" }),
+            _ => json!({
+                ".": { "import": target(rng), "require": target(rng) },
+                "./package.json": "./package.json",
+            }),
+        };
+    }
+    manifest
+}
+
+/// A request which is resolved differently, the paths are `None` if it
+/// can't be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub seed: u64,
+    pub dir: PathBuf,
+    pub request: String,
+    /// The result of Node.js.
+    pub expected: Option<PathBuf>,
+    pub actual: Option<PathBuf>,
+}
+
+/// Writes the tree of `seed` into a directory under `workspace`, and
+/// compares the results with `node`, which is the path of the executable.
+pub fn check(seed: u64, workspace: &Path, node: &Path) -> io::Result<Vec<Mismatch>> {
+    let case = generate(seed);
+    let root = workspace.join(format!("seed-{seed}"));
+    if root.exists() {
+        std::fs::remove_dir_all(&root)?;
+    }
+    case.tree.write_to(&root)?;
+    // Node.js returns the real paths.
+    let root = dunce::canonicalize(&root)?;
+    let expected = node_resolve(node, &root, &case.requests)?;
+    let resolver = Resolver::new(Options {
+        algorithm: Algorithm::NodeCjs,
+        ..Default::default()
+    });
+    let mut mismatches = vec![];
+    for ((dir, request), expected) in case.requests.into_iter().zip(expected) {
+        let actual = match resolver.resolve(&root.join(&dir), &request) {
+            Ok(ResolveResult::Resource(resource)) => Some(resource.path),
+            Ok(ResolveResult::Ignored | ResolveResult::OptionalMissing) | Err(_) => None,
+        };
+        if actual != expected {
+            mismatches.push(Mismatch {
+                seed,
+                dir,
+                request,
+                expected,
+                actual,
+            });
+        }
+    }
+    std::fs::remove_dir_all(&root)?;
+    Ok(mismatches)
+}
+
+const SCRIPT: &str = r#"
+const { createRequire } = require("module");
+const path = require("path");
+let input = "";
+process.stdin.on("data", (chunk) => (input += chunk));
+process.stdin.on("end", () => {
+  const { root, requests } = JSON.parse(input);
+  const results = requests.map(([dir, request]) => {
+    try {
+      return createRequire(path.join(root, dir, "__oracle__.js")).resolve(request);
+    } catch {
+      return null;
+    }
+  });
+  process.stdout.write(JSON.stringify(results));
+});
+"#;
+
+/// Resolves all the requests in one process of Node.js.
+fn node_resolve(
+    node: &Path,
+    root: &Path,
+    requests: &[(PathBuf, String)],
+) -> io::Result<Vec<Option<PathBuf>>> {
+    let mut child = Command::new(node)
+        .args(["-e", SCRIPT])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let input = json!({ "root": root, "requests": requests });
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.to_string().as_bytes())?;
+    let output = child.wait_with_output()?;
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if !output.status.success() {
+        return Err(invalid(&String::from_utf8_lossy(&output.stderr)));
+    }
+    let results: Vec<Option<PathBuf>> =
+        serde_json::from_slice(&output.stdout).map_err(|error| invalid(&error.to_string()))?;
+    if results.len() != requests.len() {
+        return Err(invalid("the results don't match the requests"));
+    }
+    Ok(results)
+}
+
+#[test]
+fn generate_test() {
+    let case = generate(7);
+    assert_eq!(case.requests.len(), 12);
+    assert_eq!(case.requests, generate(7).requests);
+    assert_ne!(case.requests, generate(8).requests);
+}
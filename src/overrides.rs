@@ -0,0 +1,348 @@
+use crate::{
+    context::Context,
+    resolve::{get_module_name_from_request, get_path_from_request},
+    semver::{self, Version},
+    Info, NormalizedPath, RResult, Resolver, State,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// The rules of `Options::package_overrides` and the copies of the packages
+/// installed under it, which are read once by the resolver, so the request
+/// is redirected to the same copy whatever was resolved before.
+#[derive(Debug, Default)]
+pub(crate) struct Overrides {
+    rules: Vec<OverrideRule>,
+    /// The versions and the directories of the installed copies by their
+    /// names, in the order they are found, see `Resolver::scan_modules`.
+    installed: FxHashMap<String, Vec<(Version, PathBuf)>>,
+}
+
+impl Overrides {
+    /// The directory whose `node_modules` holds the highest installed
+    /// version of `name` which satisfies `range`, the first found one if
+    /// the copies have the same version.
+    fn installed_dir(&self, name: &str, range: &str) -> Option<PathBuf> {
+        let mut matched: Option<&(Version, PathBuf)> = None;
+        for copy in self.installed.get(name)? {
+            if semver::satisfies(&copy.0, range) != Some(true) {
+                continue;
+            }
+            if matched.map_or(true, |(version, _)| copy.0 > *version) {
+                matched = Some(copy);
+            }
+        }
+        let (_, dir) = matched?;
+        // `<issuer>/node_modules/<name>`.
+        let modules = dir.ancestors().nth(name.split('/').count())?;
+        (modules.file_name()? == "node_modules")
+            .then(|| modules.parent().map(Path::to_path_buf))
+            .flatten()
+    }
+}
+
+/// A rule of npm `overrides` or yarn `resolutions` in the root package.json.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OverrideRule {
+    /// The package which depends on `name`, `None` for all the packages.
+    dependent: Option<String>,
+    name: String,
+    /// A version or a range, such as `^1.2.0`, or a path, such as `file:./foo`.
+    target: String,
+}
+
+/// The rules of `overrides` and `resolutions` in `manifest`, the references
+/// such as `$foo` are replaced by the dependencies of the manifest.
+fn parse_overrides(manifest: &Value) -> Vec<OverrideRule> {
+    let mut rules = vec![];
+    if let Some(overrides) = manifest.get("overrides").and_then(Value::as_object) {
+        collect_npm_overrides(overrides, None, &mut rules);
+    }
+    if let Some(resolutions) = manifest.get("resolutions").and_then(Value::as_object) {
+        for (key, target) in resolutions {
+            let Some(target) = target.as_str() else {
+                continue;
+            };
+            // `**/foo`, `bar/foo` and `bar/**/foo`, the nearest dependent is kept.
+            let mut names = package_names(key);
+            let Some(name) = names.pop() else {
+                continue;
+            };
+            rules.push(OverrideRule {
+                dependent: names.pop(),
+                name,
+                target: target.to_string(),
+            });
+        }
+    }
+    for rule in &mut rules {
+        if let Some(reference) = rule.target.strip_prefix('$') {
+            let version = ["dependencies", "devDependencies", "peerDependencies"]
+                .iter()
+                .find_map(|field| manifest.get(field)?.get(reference)?.as_str());
+            if let Some(version) = version {
+                rule.target = version.to_string();
+            }
+        }
+    }
+    rules
+}
+
+/// `{ "foo": "1.0.0", "bar": { ".": "2.0.0", "foo": "1.1.0" } }`, the
+/// nested rules only apply to the dependencies of the key.
+fn collect_npm_overrides(
+    overrides: &serde_json::Map<String, Value>,
+    dependent: Option<&str>,
+    rules: &mut Vec<OverrideRule>,
+) {
+    for (key, value) in overrides {
+        let name = strip_version(key);
+        match value {
+            Value::String(target) if key == "." => {
+                if let Some(dependent) = dependent {
+                    rules.push(OverrideRule {
+                        dependent: None,
+                        name: dependent.to_string(),
+                        target: target.clone(),
+                    });
+                }
+            }
+            Value::String(target) => rules.push(OverrideRule {
+                dependent: dependent.map(String::from),
+                name: name.to_string(),
+                target: target.clone(),
+            }),
+            Value::Object(nested) => collect_npm_overrides(nested, Some(name), rules),
+            _ => {}
+        }
+    }
+}
+
+/// `foo@^1.0.0` to `foo`, and `@scope/foo@1` to `@scope/foo`.
+fn strip_version(key: &str) -> &str {
+    match key.get(1..).and_then(|rest| rest.find('@')) {
+        Some(index) => &key[..index + 1],
+        None => key,
+    }
+}
+
+/// The names of the packages in the key of `resolutions`, such as
+/// `@scope/bar/**/foo` to `["@scope/bar", "foo"]`.
+fn package_names(key: &str) -> Vec<String> {
+    let mut names = vec![];
+    let mut segments = key.split('/').filter(|segment| *segment != "**");
+    while let Some(segment) = segments.next() {
+        let name = if segment.starts_with('@') {
+            format!("{segment}/{}", segments.next().unwrap_or_default())
+        } else {
+            segment.to_string()
+        };
+        names.push(strip_version(&name).to_string());
+    }
+    names
+}
+
+impl Resolver {
+    /// Redirects the module request by the `overrides` and the `resolutions`
+    /// of `options.package_overrides`, it is `Resolving` if the request is
+    /// not overridden or the override can't be resolved.
+    pub(crate) fn apply_overrides(&self, info: Info, context: &mut Context) -> State {
+        let Some(root) = self.options.package_overrides.as_deref() else {
+            return State::Resolving(info);
+        };
+        // the override had been applied before the ancestors were looked up.
+        if context.finding_up_modules.get() {
+            return State::Resolving(info);
+        }
+        let overrides = match self.overrides.get_or_try_init(|| self.load_overrides(root)) {
+            Ok(overrides) => overrides,
+            Err(error) => return State::Error(error),
+        };
+        let rules = &overrides.rules;
+        let target = info.request().target();
+        let name = get_module_name_from_request(target);
+        let dependent = match self
+            .load_entry(info.normalized_path().as_ref())
+            .pkg_info(self)
+        {
+            Ok(pkg_info) => pkg_info
+                .as_ref()
+                .and_then(|pkg_info| pkg_info.data().name().map(String::from)),
+            Err(error) => return State::Error(error),
+        };
+        // the rules of the dependent win over the global ones.
+        let rule = rules
+            .iter()
+            .filter(|rule| rule.name == name)
+            .find(|rule| rule.dependent.is_some() && rule.dependent == dependent)
+            .or_else(|| {
+                rules
+                    .iter()
+                    .find(|rule| rule.name == name && rule.dependent.is_none())
+            });
+        let Some(rule) = rule else {
+            return State::Resolving(info);
+        };
+        let subpath = get_path_from_request(target).unwrap_or_default();
+        let local = ["file:", "link:", "portal:"]
+            .iter()
+            .find_map(|protocol| rule.target.strip_prefix(protocol))
+            .or_else(|| rule.target.starts_with(['.', '/']).then_some(&rule.target));
+        let redirected = match local {
+            Some(dir) => {
+                let target = format!("{}{subpath}", root.join(dir).display());
+                self._resolve(info.clone().with_target(&target), context)
+            }
+            None => {
+                let (name, range) = match rule.target.strip_prefix("npm:") {
+                    Some(aliased) => match aliased.get(1..).and_then(|rest| rest.find('@')) {
+                        Some(index) => (&aliased[..index + 1], &aliased[index + 2..]),
+                        None => (aliased, "*"),
+                    },
+                    None => (name, rule.target.as_str()),
+                };
+                let Some(dir) = overrides.installed_dir(name, range) else {
+                    return State::Resolving(info);
+                };
+                // only the `node_modules` of the copy, the others are not overridden.
+                let dir = NormalizedPath::new(dir);
+                let target = format!("{name}{subpath}");
+                let redirected = info.clone().with_path(&dir).with_target(&target);
+                let modules = dir.as_ref().join("node_modules");
+                self._resolve_as_modules(redirected, &dir, &modules, context)
+            }
+        };
+        match redirected {
+            State::Failed(_) | State::Resolving(_) => State::Resolving(info),
+            state => state,
+        }
+    }
+
+    /// Parses the rules of the manifest in `root`, and scans the copies
+    /// installed in `root/node_modules`.
+    fn load_overrides(&self, root: &Path) -> RResult<Overrides> {
+        let rules = match self.load_entry(root).pkg_info(self)? {
+            Some(pkg_info) if pkg_info.dir().as_ref() == root => {
+                parse_overrides(pkg_info.data().raw())
+            }
+            _ => return Ok(Overrides::default()),
+        };
+        let mut installed = FxHashMap::default();
+        if !rules.is_empty() {
+            let mut visited = FxHashSet::default();
+            self.scan_modules(&root.join("node_modules"), &mut visited, &mut installed);
+        }
+        Ok(Overrides { rules, installed })
+    }
+
+    /// Collects the packages in `modules`, a `node_modules`, including the
+    /// scoped ones, the ones in the nested `node_modules` and the ones in
+    /// the stores, such as `.pnpm/foo@1.0.0/node_modules`. The contents of
+    /// the packages are not walked, and `visited` are the real paths of the
+    /// directories which were scanned, so the symlinks of the stores are
+    /// only scanned once.
+    fn scan_modules(
+        &self,
+        modules: &Path,
+        visited: &mut FxHashSet<PathBuf>,
+        installed: &mut FxHashMap<String, Vec<(Version, PathBuf)>>,
+    ) {
+        let fs = self.cache.fs.file_system();
+        let sorted_children = |dir: &Path| {
+            let mut children = fs.read_dir(dir).unwrap_or_default();
+            children.sort();
+            children
+        };
+        if !self.load_entry(modules).is_dir() {
+            return;
+        }
+        let real = fs
+            .canonicalize(modules)
+            .unwrap_or_else(|_| modules.to_path_buf());
+        if !visited.insert(real) {
+            return;
+        }
+        for child in sorted_children(modules) {
+            let Some(name) = child.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if name.starts_with('@') {
+                for package in sorted_children(&child) {
+                    if let Some(base) = package.file_name().and_then(|name| name.to_str()) {
+                        let name = format!("{name}/{base}");
+                        self.scan_package(&name, &package, visited, installed);
+                    }
+                }
+            } else if name.starts_with('.') {
+                for store in sorted_children(&child) {
+                    self.scan_modules(&store.join("node_modules"), visited, installed);
+                }
+            } else {
+                self.scan_package(name, &child, visited, installed);
+            }
+        }
+    }
+
+    fn scan_package(
+        &self,
+        name: &str,
+        dir: &Path,
+        visited: &mut FxHashSet<PathBuf>,
+        installed: &mut FxHashMap<String, Vec<(Version, PathBuf)>>,
+    ) {
+        let entry = self.load_entry(dir);
+        if !entry.is_dir() {
+            return;
+        }
+        let real = self
+            .cache
+            .fs
+            .file_system()
+            .canonicalize(dir)
+            .unwrap_or_else(|_| dir.to_path_buf());
+        if !visited.insert(real) {
+            return;
+        }
+        if let Ok(Some(pkg_info)) = entry.pkg_info(self) {
+            let version = pkg_info
+                .data()
+                .raw()
+                .get("version")
+                .and_then(Value::as_str)
+                .and_then(Version::parse);
+            if let Some(version) = version.filter(|_| pkg_info.dir().as_ref() == dir) {
+                let copies = installed.entry(name.to_string()).or_default();
+                copies.push((version, dir.to_path_buf()));
+            }
+        }
+        self.scan_modules(&dir.join("node_modules"), visited, installed);
+    }
+}
+
+#[test]
+fn parse_overrides_test() {
+    let manifest = serde_json::json!({
+        "dependencies": { "foo": "^1.2.0" },
+        "overrides": {
+            "foo": "$foo",
+            "bar@2": { ".": "2.1.0", "@scope/baz": "file:./vendor/baz" },
+        },
+        "resolutions": { "**/qux": "3.0.0", "bar/**/quux": "1.0.0" },
+    });
+    let rule = |dependent: Option<&str>, name: &str, target: &str| OverrideRule {
+        dependent: dependent.map(String::from),
+        name: name.to_string(),
+        target: target.to_string(),
+    };
+    assert_eq!(
+        parse_overrides(&manifest),
+        vec![
+            rule(None, "foo", "^1.2.0"),
+            rule(None, "bar", "2.1.0"),
+            rule(Some("bar"), "@scope/baz", "file:./vendor/baz"),
+            rule(None, "qux", "3.0.0"),
+            rule(Some("bar"), "quux", "1.0.0"),
+        ]
+    );
+}
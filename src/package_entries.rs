@@ -0,0 +1,223 @@
+use crate::{
+    info::NormalizedPath,
+    map::{ExportsField, Field},
+    walk, Error, RResult, ResolveResult, Resolver,
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// A public entry point of a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PackageEntry {
+    /// Relative to the package, such as `"."` or `"./feature"`.
+    pub subpath: String,
+    pub path: PathBuf,
+}
+
+impl Resolver {
+    /// Returns the entry points exposed by the package in `pkg_dir` under
+    /// `condition_names`, sorted by `subpath`.
+    ///
+    /// The patterns in `exports` are expanded against the files in the package,
+    /// folder mappings (keys ending with `/`) are skipped because they are deprecated.
+    /// If the package has no `exports`, only `"."` resolved by the main fields is returned.
+    pub fn package_entries(
+        &self,
+        pkg_dir: &Path,
+        condition_names: &HashSet<String>,
+    ) -> RResult<Vec<PackageEntry>> {
+        let pkg_dir = NormalizedPath::new(pkg_dir);
+        let pkg_info = self
+            .load_entry(pkg_dir.as_ref())
+            .pkg_info(self)?
+            .clone()
+            .filter(|pkg_info| pkg_info.dir().as_ref() == pkg_dir.as_ref())
+            .ok_or_else(|| {
+                Error::UnexpectedValue(format!(
+                    "Can't find {} in {}",
+                    self.options.description_file,
+                    pkg_dir.as_ref().display()
+                ))
+            })?;
+
+        let Some(root) = self
+            .options
+            .exports_field
+            .iter()
+            .find_map(|field| pkg_info.data().get_filed(field))
+        else {
+            return match self.resolve(pkg_dir.as_ref(), ".") {
+                Ok(ResolveResult::Resource(resource)) => Ok(vec![PackageEntry {
+                    subpath: String::from("."),
+                    path: resource.path,
+                }]),
+                Ok(ResolveResult::Ignored | ResolveResult::OptionalMissing)
+                | Err(Error::ResolveFailedTag | Error::NotFound { .. }) => Ok(vec![]),
+                Err(error) => Err(error),
+            };
+        };
+
+        let keys: Vec<&str> = match root.as_object() {
+            Some(map) if map.keys().any(|key| key.starts_with('.')) => {
+                map.keys().map(String::as_str).collect()
+            }
+            // sugar of `{ ".": root }`
+            _ => vec!["."],
+        };
+
+        let mut entries = BTreeMap::new();
+        for key in keys {
+            if key.ends_with('/') {
+                continue;
+            }
+            if !key.contains('*') {
+                if let Some(path) = self.exported_file(&pkg_dir, root, key, condition_names)? {
+                    entries.insert(key.to_string(), path);
+                }
+                continue;
+            }
+            // keep `*` in the targets, so they can be matched with the files.
+            let targets = ExportsField::mapping("*", true, false, &root[key], condition_names)?;
+            for target in targets {
+                let Some((prefix, suffix)) = target.split_once('*') else {
+                    continue;
+                };
+                if suffix.contains('*') {
+                    continue;
+                }
+                let dir = match prefix.rfind('/') {
+                    Some(index) => &prefix[..=index],
+                    None => continue,
+                };
+                let mut files = vec![];
+                let dir = pkg_dir.as_ref().join(dir);
+                self.collect_files(pkg_dir.as_ref(), &dir, &mut vec![], &mut files)?;
+                for file in files {
+                    let Ok(relative) = file.strip_prefix(pkg_dir.as_ref()) else {
+                        continue;
+                    };
+                    let relative =
+                        format!("./{}", relative.display().to_string().replace('\\', "/"));
+                    let star = relative
+                        .strip_prefix(prefix)
+                        .and_then(|rest| rest.strip_suffix(suffix));
+                    let Some(star) = star.filter(|star| !star.is_empty()) else {
+                        continue;
+                    };
+                    let subpath = key.replacen('*', star, 1);
+                    if entries.contains_key(&subpath) {
+                        continue;
+                    }
+                    // the subpath may be shadowed by a more specific key, or be excluded by `null`.
+                    if let Some(path) =
+                        self.exported_file(&pkg_dir, root, &subpath, condition_names)?
+                    {
+                        entries.insert(subpath, path);
+                    }
+                }
+            }
+        }
+
+        Ok(entries
+            .into_iter()
+            .map(|(subpath, path)| PackageEntry {
+                subpath,
+                path: self.output_path(path),
+            })
+            .collect())
+    }
+
+    /// Returns the bare specifiers, such as `pkg/feature`, which are resolved
+    /// to `file_path` by the package in `pkg_dir` under `condition_names`.
+    ///
+    /// It is empty if the file is not exposed, or the package has no `name`.
+    pub fn reverse_resolve(
+        &self,
+        pkg_dir: &Path,
+        file_path: &Path,
+        condition_names: &HashSet<String>,
+    ) -> RResult<Vec<String>> {
+        let entries = self.package_entries(pkg_dir, condition_names)?;
+        let pkg_info = self
+            .load_entry(NormalizedPath::new(pkg_dir).as_ref())
+            .pkg_info(self)?
+            .clone();
+        let Some(name) = pkg_info
+            .as_ref()
+            .and_then(|pkg_info| pkg_info.data().name())
+        else {
+            return Ok(vec![]);
+        };
+        let file_path = self.output_path(NormalizedPath::new(file_path).as_ref().to_path_buf());
+        Ok(entries
+            .into_iter()
+            .filter(|entry| entry.path == file_path)
+            .map(|entry| match entry.subpath.strip_prefix('.') {
+                Some(rest) => format!("{name}{rest}"),
+                None => name.to_string(),
+            })
+            .collect())
+    }
+
+    /// The first file which `subpath` is mapped to.
+    fn exported_file(
+        &self,
+        pkg_dir: &NormalizedPath,
+        root: &serde_json::Value,
+        subpath: &str,
+        condition_names: &HashSet<String>,
+    ) -> RResult<Option<PathBuf>> {
+        let targets = ExportsField::field_process(root, subpath, condition_names)?;
+        Ok(targets
+            .iter()
+            .filter(|target| ExportsField::check_target(target).is_ok())
+            .map(|target| NormalizedPath::new(pkg_dir.as_ref().join(target)))
+            .find(|path| self.load_entry(path.as_ref()).is_file())
+            .map(|path| path.as_ref().to_path_buf()))
+    }
+
+    /// `ancestors` are the real paths of the directories being walked, see
+    /// `walk::enter`.
+    fn collect_files(
+        &self,
+        root: &Path,
+        dir: &Path,
+        ancestors: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> RResult<()> {
+        let fs = self.cache.fs.file_system();
+        let children = match fs.read_dir(dir) {
+            Ok(children) => children,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        let Some(real) = walk::enter(fs.as_ref(), ancestors, dir) else {
+            return Ok(());
+        };
+        ancestors.push(real);
+        for child in children {
+            if child
+                .file_name()
+                .map_or(false, |name| name == "node_modules")
+            {
+                continue;
+            }
+            let relative = child.strip_prefix(root).unwrap_or(&child);
+            let relative = relative.display().to_string().replace('\\', "/");
+            if self.options.walk_exclude.is_excluded(&relative) {
+                continue;
+            }
+            let entry = self.load_entry(&child);
+            if entry.is_dir() {
+                self.collect_files(root, &child, ancestors, files)?;
+            } else if entry.is_file() {
+                files.push(child);
+            }
+        }
+        ancestors.pop();
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{
+    map::{ExportsField, Field},
+    semver::{self, Version},
+    Error, FileKind, FileSystem, ProtocolHandler, ProtocolResolution, RResult,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Resolves `npm:pkg@range/subpath` in a store of packages laid out as
+/// `node_modules/.deno` of Deno or `node_modules/.pnpm` of pnpm, such as
+/// `<store>/@scope+name@1.2.3/node_modules/@scope/name`.
+///
+/// The highest installed version which satisfies the range is selected,
+/// then the package is resolved as usual, including its `exports`.
+#[derive(Debug)]
+pub struct NpmProtocol {
+    store: PathBuf,
+    file_system: Arc<dyn FileSystem>,
+}
+
+impl NpmProtocol {
+    pub fn new(store: PathBuf, file_system: Arc<dyn FileSystem>) -> Self {
+        Self { store, file_system }
+    }
+}
+
+impl ProtocolHandler for NpmProtocol {
+    fn resolve(&self, _path: &Path, specifier: &str) -> RResult<ProtocolResolution> {
+        let (name, range, subpath) = parse_specifier("npm:", specifier)?;
+        let prefix = format!("{}@", name.replace('/', "+"));
+        // the suffix of the peer dependencies, such as `_react@18.2.0`, is skipped.
+        let (entry, _) = select_version(self.file_system.as_ref(), &self.store, range, |entry| {
+            entry
+                .strip_prefix(&prefix)
+                .map(|rest| rest.split('_').next().unwrap_or(rest))
+        })?
+        .ok_or_else(|| not_installed(name, range, &self.store))?;
+        Ok(ProtocolResolution::DelegateIn(
+            self.store.join(entry),
+            format!("{name}{subpath}"),
+        ))
+    }
+}
+
+/// Resolves `jsr:@scope/name@range/subpath` in the vendored packages of
+/// JSR laid out as the `jsr.io` directory of Deno, such as
+/// `<vendor>/@scope/name/1.2.3/mod.ts`.
+///
+/// The highest vendored version which satisfies the range is selected, the
+/// subpath is mapped by `exports` in `<version>_meta.json` beside the version,
+/// or in `jsr.json` or `deno.json` of the version.
+#[derive(Debug)]
+pub struct JsrProtocol {
+    vendor: PathBuf,
+    file_system: Arc<dyn FileSystem>,
+}
+
+impl JsrProtocol {
+    pub fn new(vendor: PathBuf, file_system: Arc<dyn FileSystem>) -> Self {
+        Self {
+            vendor,
+            file_system,
+        }
+    }
+
+    fn exports(&self, package_dir: &Path, version: &str) -> RResult<Option<serde_json::Value>> {
+        let candidates = [
+            package_dir.join(format!("{version}_meta.json")),
+            package_dir.join(version).join("jsr.json"),
+            package_dir.join(version).join("deno.json"),
+        ];
+        for path in candidates {
+            let content = match self.file_system.read_to_string(&path) {
+                Ok(content) => content,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            };
+            let mut json: serde_json::Value = serde_json::from_str(&content)
+                .map_err(|error| Error::UnexpectedJson((path.into(), error)))?;
+            if let Some(exports) = json.get_mut("exports") {
+                return Ok(Some(exports.take()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl ProtocolHandler for JsrProtocol {
+    fn resolve(&self, _path: &Path, specifier: &str) -> RResult<ProtocolResolution> {
+        let (name, range, subpath) = parse_specifier("jsr:", specifier)?;
+        if !name.starts_with('@') {
+            return Err(Error::UnexpectedValue(format!(
+                "The name of a JSR package should be in the form of \"@scope/name\", but got \"jsr:{specifier}\""
+            )));
+        }
+        let package_dir = self.vendor.join(name);
+        let (version, _) =
+            select_version(self.file_system.as_ref(), &package_dir, range, |entry| {
+                Some(entry)
+            })?
+            .ok_or_else(|| not_installed(name, range, &self.vendor))?;
+        let exports = self
+            .exports(&package_dir, &version)?
+            .unwrap_or_else(|| serde_json::Value::from("./mod.ts"));
+        let subpath = format!(".{subpath}");
+        let target = ExportsField::field_process(&exports, &subpath, &HashSet::new())?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                Error::UnexpectedValue(format!(
+                    "\"{subpath}\" is not exported by jsr:{name}@{version}"
+                ))
+            })?;
+        Ok(ProtocolResolution::DelegateIn(
+            package_dir.join(version),
+            target,
+        ))
+    }
+}
+
+/// Splits `pkg@range/subpath` into the name, the range and the subpath,
+/// the range is `*` if it is omitted, and the subpath is empty or starts with `/`.
+fn parse_specifier<'a>(protocol: &str, specifier: &'a str) -> RResult<(&'a str, &'a str, &'a str)> {
+    let invalid = || Error::UnexpectedValue(format!("Invalid specifier \"{protocol}{specifier}\""));
+    let rest = specifier.strip_prefix('/').unwrap_or(specifier);
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    let name_end = if rest.starts_with('@') {
+        let scope = rest.find('/').ok_or_else(invalid)?;
+        scope
+            + 1
+            + rest[scope + 1..]
+                .find('/')
+                .unwrap_or(rest.len() - scope - 1)
+    } else {
+        rest.find('/').unwrap_or(rest.len())
+    };
+    let (name_and_range, subpath) = rest.split_at(name_end);
+    let (name, range) = match name_and_range[1..].find('@') {
+        Some(index) => (&name_and_range[..index + 1], &name_and_range[index + 2..]),
+        None => (name_and_range, "*"),
+    };
+    if name.is_empty() || name.ends_with('/') || subpath == "/" {
+        return Err(invalid());
+    }
+    Ok((name, range, subpath))
+}
+
+/// The child of `dir` with the highest version which satisfies `range`,
+/// `version_of` extracts the version from the name of the child.
+fn select_version(
+    file_system: &dyn FileSystem,
+    dir: &Path,
+    range: &str,
+    version_of: impl Fn(&str) -> Option<&str>,
+) -> RResult<Option<(String, Version)>> {
+    let children = match file_system.read_dir(dir) {
+        Ok(children) => children,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    let mut selected: Option<(String, Version)> = None;
+    for child in children {
+        let Some(entry) = child.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(version) = version_of(entry).and_then(Version::parse) else {
+            continue;
+        };
+        let satisfied = semver::satisfies(&version, range)
+            .ok_or_else(|| Error::UnexpectedValue(format!("Invalid version range \"{range}\"")))?;
+        let is_dir = file_system
+            .metadata(&child)
+            .map_or(false, |meta| meta.kind == FileKind::Dir);
+        if satisfied
+            && is_dir
+            && selected
+                .as_ref()
+                .map_or(true, |(_, highest)| version > *highest)
+        {
+            selected = Some((entry.to_string(), version));
+        }
+    }
+    Ok(selected)
+}
+
+fn not_installed(name: &str, range: &str, dir: &Path) -> Error {
+    Error::UnexpectedValue(format!(
+        "No version of {name} in {} satisfies \"{range}\"",
+        dir.display()
+    ))
+}
+
+#[test]
+fn parse_specifier_test() {
+    assert_eq!(parse_specifier("npm:", "pkg").unwrap(), ("pkg", "*", ""));
+    assert_eq!(
+        parse_specifier("npm:", "pkg@^1.2/a/b.js").unwrap(),
+        ("pkg", "^1.2", "/a/b.js")
+    );
+    assert_eq!(
+        parse_specifier("npm:", "/@scope/pkg@1").unwrap(),
+        ("@scope/pkg", "1", "")
+    );
+    assert_eq!(
+        parse_specifier("jsr:", "@std/path/join").unwrap(),
+        ("@std/path", "*", "/join")
+    );
+    assert!(parse_specifier("npm:", "").is_err());
+    assert!(parse_specifier("npm:", "/").is_err());
+    assert!(parse_specifier("jsr:", "@std").is_err());
+    assert!(parse_specifier("npm:", "pkg/").is_err());
+}
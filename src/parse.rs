@@ -1,19 +1,50 @@
 use crate::kind::PathKind;
-use crate::Resolver;
+use crate::{DotRequests, Error, RResult, Resolver};
+use once_cell::sync::Lazy;
+use std::{borrow::Cow, sync::Arc};
 
-#[derive(Clone, Debug)]
+/// The segments are immutable and shared, so cloning an `Info` during
+/// the resolution does not copy the strings.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Request {
-    target: Box<str>,
-    query: Option<Box<str>>,
-    fragment: Option<Box<str>>,
+    target: Arc<str>,
+    query: Option<Arc<str>>,
+    fragment: Option<Arc<str>>,
     kind: PathKind,
     is_directory: bool,
 }
 
+/// A request which the host has already split, see `Resolver::resolve_request`,
+/// so it is not parsed again by the rules of the resolver.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct StructuredRequest {
+    pub specifier: String,
+    /// The query with the leading `?`, such as `?raw`, empty if there is none.
+    pub query: String,
+    /// The fragment with the leading `#`, empty if there is none.
+    pub fragment: String,
+}
+
+impl StructuredRequest {
+    #[must_use]
+    pub fn new(specifier: impl Into<String>) -> Self {
+        Self {
+            specifier: specifier.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl std::fmt::Display for StructuredRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.specifier, self.query, self.fragment)
+    }
+}
+
 impl Default for Request {
     fn default() -> Self {
         Self {
-            target: "".into(),
+            target: shared(""),
             query: None,
             fragment: None,
             kind: PathKind::Relative,
@@ -32,17 +63,24 @@ impl Request {
     #[must_use]
     pub fn from_request(request: &str) -> Self {
         let (target, query, fragment) = Self::parse_identifier(request);
-        let is_directory = Self::is_target_directory(&target);
+        Self::from_parts(target, query.unwrap_or(""), fragment.unwrap_or(""))
+    }
+
+    /// Same as `from_request`, but the request is already split, the `?` and
+    /// the `#` in `target` are a part of it.
+    #[must_use]
+    pub fn from_parts(target: &str, query: &str, fragment: &str) -> Self {
+        let is_directory = Self::is_target_directory(target);
         let target = if is_directory {
-            target[0..target.len() - 1].into()
+            &target[0..target.len() - 1]
         } else {
             target
         };
         Request {
-            kind: Resolver::get_target_kind(&target),
-            target,
-            query,
-            fragment,
+            kind: Resolver::get_target_kind(target),
+            target: shared(target),
+            query: (!query.is_empty()).then(|| Arc::from(query)),
+            fragment: (!fragment.is_empty()).then(|| Arc::from(fragment)),
             is_directory,
         }
     }
@@ -71,9 +109,15 @@ impl Request {
 
     pub fn with_target(self, target: &str) -> Self {
         let is_directory = Self::is_target_directory(target);
+        // copy on write, the target is usually unchanged or empty.
+        let target = if *self.target == *target {
+            self.target
+        } else {
+            shared(target)
+        };
         Self {
-            kind: Resolver::get_target_kind(target),
-            target: target.into(),
+            kind: Resolver::get_target_kind(&target),
+            target,
             is_directory,
             ..self
         }
@@ -81,58 +125,63 @@ impl Request {
 
     pub fn with_query(self, query: &str) -> Self {
         Self {
-            query: (!query.is_empty()).then(|| query.into()),
+            query: (!query.is_empty()).then(|| Arc::from(query)),
             ..self
         }
     }
 
     pub fn with_fragment(self, fragment: &str) -> Self {
         Self {
-            fragment: (!fragment.is_empty()).then(|| fragment.into()),
+            fragment: (!fragment.is_empty()).then(|| Arc::from(fragment)),
             ..self
         }
     }
 
-    fn parse_identifier(ident: &str) -> (Box<str>, Option<Box<str>>, Option<Box<str>>) {
-        let mut query: Option<usize> = None;
-        let mut fragment: Option<usize> = None;
-        let mut stats = ParseStats::Start;
-        for (index, c) in ident.as_bytes().iter().enumerate() {
-            match c {
-                b'#' => match stats {
-                    ParseStats::Request | ParseStats::Query => {
-                        stats = ParseStats::Fragment;
-                        fragment = Some(index);
-                    }
-                    ParseStats::Start => {
-                        stats = ParseStats::Request;
-                    }
-                    ParseStats::Fragment => (),
-                },
-                b'?' => match stats {
-                    ParseStats::Request | ParseStats::Query | ParseStats::Start => {
-                        stats = ParseStats::Query;
-                        query = Some(index);
-                    }
-                    ParseStats::Fragment => (),
-                },
-                _ => {
-                    if let ParseStats::Start = stats {
-                        stats = ParseStats::Request;
-                    }
-                }
-            }
-        }
+    /// The fragment starts at the first `#` which is not the first byte, such
+    /// as the `#a` of `imports`, and the query at the last `?` before it.
+    fn parse_identifier(ident: &str) -> (&str, Option<&str>, Option<&str>) {
+        let bytes = ident.as_bytes();
+        let fragment = bytes
+            .get(1..)
+            .and_then(|rest| memchr::memchr(b'#', rest))
+            .map(|index| index + 1);
+        let query = memchr::memrchr(b'?', &bytes[..fragment.unwrap_or(bytes.len())]);
 
         match (query, fragment) {
-            (None, None) => (ident.into(), None, None),
-            (None, Some(j)) => (ident[0..j].into(), None, Some(ident[j..].into())),
-            (Some(i), None) => (ident[0..i].into(), Some(ident[i..].into()), None),
-            (Some(i), Some(j)) => (
-                ident[0..i].into(),
-                Some(ident[i..j].into()),
-                Some(ident[j..].into()),
-            ),
+            (None, None) => (ident, None, None),
+            (None, Some(j)) => (&ident[0..j], None, Some(&ident[j..])),
+            (Some(i), None) => (&ident[0..i], Some(&ident[i..]), None),
+            (Some(i), Some(j)) => (&ident[0..i], Some(&ident[i..j]), Some(&ident[j..])),
+        }
+    }
+
+    /// The requests of ESM are URLs, so `file:` URLs are converted to paths
+    /// and the relative or absolute requests are percent-decoded.
+    pub(crate) fn decode_url(request: &str) -> RResult<Cow<'_, str>> {
+        if let Some(url) = request.strip_prefix("file:") {
+            let invalid = || Error::UnexpectedValue(format!("Invalid file URL \"{request}\""));
+            let rest = url.strip_prefix("//").ok_or_else(invalid)?;
+            let (host, path) = rest.split_at(rest.find('/').ok_or_else(invalid)?);
+            if !host.is_empty() && host != "localhost" {
+                return Err(Error::UnexpectedValue(format!(
+                    "The host of file URL \"{request}\" must be \"localhost\" or empty"
+                )));
+            }
+            // `/C:/a` on windows.
+            let path = if cfg!(windows) {
+                path.strip_prefix('/').unwrap_or(path)
+            } else {
+                path
+            };
+            return Ok(Cow::Owned(percent_decode(request, path)?.into_owned()));
+        }
+        if matches!(
+            Resolver::get_target_kind(request),
+            PathKind::Relative | PathKind::AbsolutePosix | PathKind::AbsoluteWin
+        ) {
+            percent_decode(request, request)
+        } else {
+            Ok(Cow::Borrowed(request))
         }
     }
 
@@ -142,27 +191,88 @@ impl Request {
     }
 }
 
+/// The invalid escapes are kept as they are.
+fn percent_decode<'a>(request: &str, input: &'a str) -> RResult<Cow<'a, str>> {
+    if !input.contains('%') {
+        return Ok(Cow::Borrowed(input));
+    }
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| input.get(index + 1..index + 3))
+            .flatten()
+            .filter(|hex| hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(b'/' | b'\\') => {
+                return Err(Error::UnexpectedValue(format!(
+                    "Invalid module request \"{request}\", it must not include encoded \"/\" or \"\\\" characters"
+                )))
+            }
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map(Cow::Owned).map_err(|_| {
+        Error::UnexpectedValue(format!(
+            "Invalid module request \"{request}\", it is not valid UTF-8 after being decoded"
+        ))
+    })
+}
+
+/// The targets which are created again and again, such as `with_target("")`
+/// after a file is found, share one allocation.
+fn shared(target: &str) -> Arc<str> {
+    static EMPTY: Lazy<Arc<str>> = Lazy::new(|| Arc::from(""));
+    static DOT: Lazy<Arc<str>> = Lazy::new(|| Arc::from("."));
+    match target {
+        "" => EMPTY.clone(),
+        "." => DOT.clone(),
+        _ => Arc::from(target),
+    }
+}
+
 impl Resolver {
     #[must_use]
     pub(crate) fn parse(request: &str) -> Request {
         Request::from_request(request)
     }
-}
 
-enum ParseStats {
-    Request,
-    Query,
-    Fragment,
-    Start,
+    /// Rejects `""`, and resolves `.` and `..` as `./` and `../` with
+    /// `DotRequests::Node`, see `Options::dot_requests`.
+    pub(crate) fn apply_dot_requests(&self, request: Request) -> RResult<Request> {
+        if self.options.dot_requests == DotRequests::Lenient {
+            return Ok(request);
+        }
+        match request.target() {
+            "" if !request.is_directory() => Err(Error::UnexpectedValue(format!(
+                "Invalid request \"{request}\", the specifier must not be empty"
+            ))),
+            "." | ".." if !request.is_directory() => Ok(Request::from_parts(
+                &format!("{}/", request.target()),
+                request.query(),
+                request.fragment(),
+            )),
+            _ => Ok(request),
+        }
+    }
 }
 
 #[test]
 fn parse_identifier_test() {
     fn should_parsed(input: &str, t: &str, q: &str, f: &str) {
         let (target, query, fragment) = Request::parse_identifier(input);
-        assert_eq!(&*target, t);
-        assert_eq!(query.as_ref().map_or("", |q| q.as_ref()), q);
-        assert_eq!(fragment.as_ref().map_or("", |f| f.as_ref()), f);
+        assert_eq!(target, t);
+        assert_eq!(query.unwrap_or_default(), q);
+        assert_eq!(fragment.unwrap_or_default(), f);
     }
 
     should_parsed("path/abc", "path/abc", "", "");
@@ -180,6 +290,10 @@ fn parse_identifier_test() {
         "#/not/a/hash?not-a-query",
     );
     should_parsed("#a?b#c?d", "#a", "?b", "#c?d");
+    should_parsed("a?b?c", "a?b", "?c", "");
+    should_parsed("?a#b", "", "?a", "#b");
+    should_parsed("#", "#", "", "");
+    should_parsed("", "", "", "");
 
     // windows like
     should_parsed("path\\#", "path\\", "", "#");
@@ -194,3 +308,26 @@ fn parse_identifier_test() {
         "#/not/a/hash?not-a-query",
     );
 }
+
+#[test]
+fn decode_url_test() {
+    fn should_decoded(input: &str, expected: &str) {
+        assert_eq!(Request::decode_url(input).unwrap(), expected);
+    }
+
+    should_decoded("./a%20b.js", "./a b.js");
+    should_decoded("./a%2.js", "./a%2.js");
+    should_decoded("pkg%20name", "pkg%20name");
+    should_decoded("/%E4%B8%AD.js", "/中.js");
+    if cfg!(windows) {
+        should_decoded("file:///C:/a%20b.js", "C:/a b.js");
+    } else {
+        should_decoded("file:///a%20b.js?q#f", "/a b.js?q#f");
+        should_decoded("file://localhost/a.js", "/a.js");
+    }
+    assert!(Request::decode_url("./a%2Fb.js").is_err());
+    assert!(Request::decode_url("./a%5cb.js").is_err());
+    assert!(Request::decode_url("./%FF.js").is_err());
+    assert!(Request::decode_url("file://host/a.js").is_err());
+    assert!(Request::decode_url("file:a.js").is_err());
+}
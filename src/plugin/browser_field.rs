@@ -1,6 +1,6 @@
 use crate::{
     context::Context, description::DescriptionData, log::color, log::depth, AliasMap, Info,
-    PathKind, Plugin, ResolveResult, Resolver, State,
+    PathKind, Plugin, ResolveResult, Resolver, Stage, State,
 };
 use path_absolutize::Absolutize;
 use std::path::{Path, PathBuf};
@@ -47,6 +47,33 @@ impl<'a> BrowserFieldPlugin<'a> {
     }
 }
 
+impl<'a> BrowserFieldPlugin<'a> {
+    /// Whether the resolved `path` is mapped to `false`, the key may omit
+    /// the extension of the file, such as `"./lib/util": false` for the
+    /// target `./lib/util.js` of `exports`.
+    pub fn is_ignored_file(&self, path: &Path, extensions: &[String]) -> bool {
+        let Some(path) = path.to_str() else {
+            return false;
+        };
+        let stems = extensions
+            .iter()
+            .filter_map(|ext| path.strip_suffix(ext.as_str()));
+        let candidates: Vec<&str> = std::iter::once(path).chain(stems).collect();
+        self.pkg_info
+            .data()
+            .alias_fields()
+            .iter()
+            .filter(|(_, target)| matches!(target, AliasMap::Ignored))
+            .any(|(key, _)| {
+                let key = self.pkg_info.dir().as_ref().join(key);
+                let key = key.absolutize_from(Path::new("")).unwrap();
+                candidates
+                    .iter()
+                    .any(|candidate| key.as_ref() == Path::new(candidate))
+            })
+    }
+}
+
 impl<'a> Plugin for BrowserFieldPlugin<'a> {
     fn apply(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State {
         if !resolver.options.browser_field {
@@ -97,6 +124,7 @@ impl<'a> Plugin for BrowserFieldPlugin<'a> {
                     if fully_specified {
                         context.fully_specified.set(true);
                     }
+                    context.record_stage(&state, Stage::BrowserField, alias_key);
                     if state.is_finished() {
                         return state;
                     }
@@ -2,12 +2,12 @@ use crate::{
     description::DescriptionData,
     log::color,
     log::depth,
-    map::{ExportsField, Field},
+    map::{matched_key, ExportsField, Field},
     resolve::get_path_from_request,
-    Context, Error, Info, Resolver, State,
+    Algorithm, Context, Error, Info, ResolveResult, Resolver, Stage, State,
 };
 
-use super::Plugin;
+use super::{BrowserFieldPlugin, Plugin};
 
 pub struct ExportsFieldPlugin<'a> {
     pkg_info: &'a DescriptionData,
@@ -45,7 +45,7 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
                 Some(target) => format!(".{target}"),
                 None => {
                     let path = info.normalized_path().as_ref().join(target);
-                    if resolver.load_entry(&path).exists()
+                    if resolver.load_entry(&path).probe_exists()
                         || self
                             .pkg_info
                             .data()
@@ -70,7 +70,8 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
                 normalized_target
             };
 
-            let list = match ExportsField::field_process(
+            let list = match self.pkg_info.exports_targets(
+                field,
                 root,
                 &remaining_target,
                 &resolver.options.condition_names,
@@ -80,13 +81,31 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
             };
 
             if list.is_empty() {
-                return State::Error(Error::UnexpectedValue(format!(
-                    "Package path {target} is not exported in {}/package.json",
-                    self.pkg_info.dir().as_ref().display()
-                )));
+                let is_manifest =
+                    remaining_target == format!("./{}", resolver.options.description_file);
+                if resolver.options.allow_manifest_access && is_manifest {
+                    tracing::debug!(
+                        "'{}' is not exported, fall through as `allow_manifest_access` is enabled({})",
+                        color::blue(&target),
+                        depth(&context.depth)
+                    );
+                    return State::Resolving(info);
+                }
+                if !resolver.options.strict_exports {
+                    tracing::debug!(
+                        "'{}' is not exported, fall through as `strict_exports` is disabled({})",
+                        color::blue(&target),
+                        depth(&context.depth)
+                    );
+                    return State::Resolving(info);
+                }
+                return State::Error(Error::NotExported {
+                    request: target.to_string(),
+                    package: self.pkg_info.dir().as_ref().into(),
+                });
             }
 
-            for item in list {
+            for item in list.iter() {
                 tracing::debug!(
                     "ExportsField in '{}' works, trigger by '{}', mapped to '{}'({})",
                     color::blue(&format!(
@@ -103,16 +122,42 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
                         self.pkg_info.dir().as_ref().display()
                     )));
                 }
-                let request = Resolver::parse(&item);
+                let request = Resolver::parse(item);
                 let info = Info::from(self.pkg_info.dir().clone()).with_request(request);
                 if let Err(msg) = ExportsField::check_target(info.request().target()) {
-                    let msg = format!("{msg} in {:?}/package.json", &self.pkg_info.dir());
+                    let msg = format!(
+                        "Invalid package target \"{item}\" for \"{remaining_target}\": {msg} in {}/package.json",
+                        self.pkg_info.dir().as_ref().display()
+                    );
                     return State::Error(Error::UnexpectedValue(msg));
                 }
+                // Node.js never appends the extensions to the targets.
+                let fully_specified = context.fully_specified.get();
+                if resolver.options.algorithm != Algorithm::Enhanced {
+                    context.fully_specified.set(true);
+                }
                 let state = resolver._resolve(info, context);
-                if state.is_finished() {
-                    return state;
+                context.fully_specified.set(fully_specified);
+                if !state.is_finished() {
+                    continue;
+                }
+                if let Some(key) = matched_key::<ExportsField>(root, &remaining_target) {
+                    context.record_stage(&state, Stage::ExportsField, key);
                 }
+                // map the result back through the `browser` field, which may
+                // not match the target of `exports` literally.
+                return match state {
+                    State::Success(ResolveResult::Resource(info))
+                        if resolver.options.browser_field
+                            && BrowserFieldPlugin::new(self.pkg_info, false).is_ignored_file(
+                                info.normalized_path().as_ref(),
+                                &resolver.options.extensions,
+                            ) =>
+                    {
+                        State::Success(ResolveResult::Ignored)
+                    }
+                    _ => state,
+                };
             }
 
             return State::Failed(info);
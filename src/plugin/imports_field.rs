@@ -4,8 +4,8 @@ use crate::{
     description::DescriptionData,
     log::color,
     log::depth,
-    map::{Field, ImportsField},
-    Error, Info, PathKind, Resolver, State,
+    map::{matched_key, Field, ImportsField},
+    Error, Info, PathKind, Resolver, Stage, State,
 };
 
 pub struct ImportsFieldPlugin<'a> {
@@ -17,11 +17,15 @@ impl<'a> ImportsFieldPlugin<'a> {
         Self { pkg_info }
     }
 
-    fn check_target(&self, resolver: &Resolver, info: Info) -> State {
+    fn check_target(&self, resolver: &Resolver, info: Info, key: &str) -> State {
         let path = info.to_resolved_path();
         if resolver.load_entry(&path).is_file() {
             if let Err(msg) = ImportsField::check_target(info.request().target()) {
-                let msg = format!("{msg} in {:?}/package.json", &self.pkg_info.dir().as_ref());
+                let msg = format!(
+                    "Invalid package target \"{}\" for \"{key}\": {msg} in {}/package.json",
+                    info.request().target(),
+                    self.pkg_info.dir().as_ref().display()
+                );
                 State::Error(Error::UnexpectedValue(msg))
             } else {
                 State::Resolving(info)
@@ -64,20 +68,28 @@ impl<'a> Plugin for ImportsFieldPlugin<'a> {
                 color::blue(&item),
                 depth(&context.depth)
             );
+            let key = info.request().target();
             let request = Resolver::parse(item);
             let is_relative = !matches!(request.kind(), PathKind::Normal | PathKind::Internal);
-            let info = Info::from(self.pkg_info.dir().clone()).with_request(request);
+            let next = Info::from(self.pkg_info.dir().clone()).with_request(request);
+            let matched = matched_key::<ImportsField>(root, key).unwrap_or(key);
             if is_relative {
-                self.check_target(resolver, info)
+                let state = self.check_target(resolver, next, key);
+                // the target is a file, which is resolved as it is afterwards.
+                if matches!(state, State::Resolving(_)) && context.stage.is_none() {
+                    context.stage = Some((Stage::ImportsField, matched.to_string()));
+                }
+                state
             } else {
                 let fully_specified = context.fully_specified.get();
                 if fully_specified {
                     context.fully_specified.set(false);
                 }
-                let state = resolver._resolve(info, context);
+                let state = resolver._resolve(next, context);
                 if fully_specified {
                     context.fully_specified.set(true);
                 }
+                context.record_stage(&state, Stage::ImportsField, matched);
                 state
             }
         } else {
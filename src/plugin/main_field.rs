@@ -1,13 +1,28 @@
 use super::Plugin;
-use crate::{description::DescriptionData, log::color, log::depth, Context, Info, Resolver, State};
+use crate::{
+    description::DescriptionData, log::color, log::depth, Context, Info, Resolver, ResultKind,
+    Stage, State,
+};
 
 pub struct MainFieldPlugin<'a> {
     pkg_info: &'a DescriptionData,
+    /// `None` means `Options::main_fields`.
+    fields: Option<&'a [String]>,
 }
 
 impl<'a> MainFieldPlugin<'a> {
     pub fn new(pkg_info: &'a DescriptionData) -> Self {
-        Self { pkg_info }
+        Self {
+            pkg_info,
+            fields: None,
+        }
+    }
+
+    pub fn with_fields(self, fields: &'a [String]) -> Self {
+        Self {
+            fields: Some(fields),
+            ..self
+        }
     }
 }
 
@@ -17,9 +32,14 @@ impl<'a> Plugin for MainFieldPlugin<'a> {
         if !self.pkg_info.dir().as_ref().eq(&*resolved) {
             return State::Resolving(info);
         }
+        // the directory of the package is the context anyway.
+        if context.resolve_to_context.get() && context.result_kind == ResultKind::Existence {
+            return State::Resolving(info);
+        }
         let main_field_info = info.clone().with_path(resolved).with_target(".");
 
-        for user_main_field in &resolver.options.main_fields {
+        let fields = self.fields.unwrap_or(&resolver.options.main_fields);
+        for user_main_field in fields {
             if let Some(main_field) = self
                 .pkg_info
                 .data()
@@ -54,7 +74,11 @@ impl<'a> Plugin for MainFieldPlugin<'a> {
                 if fully_specified {
                     context.fully_specified.set(true);
                 }
+                context.record_stage(&state, Stage::MainField, user_main_field);
                 if state.is_finished() {
+                    if matches!(state, State::Success(_)) {
+                        context.main_field = Some(user_main_field.clone());
+                    }
                     return state;
                 }
                 tracing::debug!("Leaving MainField({})", depth(&context.depth));
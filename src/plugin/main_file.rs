@@ -1,5 +1,6 @@
 use super::Plugin;
-use crate::{log::color, log::depth, Context, Info, Resolver, State};
+use crate::{log::color, log::depth, Algorithm, Context, Info, Resolver, Stage, State};
+use std::path::Path;
 
 pub struct MainFilePlugin;
 
@@ -12,11 +13,16 @@ impl Plugin for MainFilePlugin {
                 color::blue(main_file),
                 depth(&context.depth)
             );
-            let main_file_info = info
-                .clone()
-                .with_path(&path)
-                .with_target(&format!("./{main_file}"));
-            let state = resolver._resolve(main_file_info, context);
+            let state = if resolver.options.algorithm == Algorithm::Enhanced {
+                let main_file_info = info
+                    .clone()
+                    .with_path(&path)
+                    .with_target(&format!("./{main_file}"));
+                resolver._resolve(main_file_info, context)
+            } else {
+                Self::resolve_with_extensions(resolver, &info, &path, main_file, context)
+            };
+            context.record_stage(&state, Stage::MainFile, main_file);
             if state.is_finished() {
                 return state;
             }
@@ -25,3 +31,31 @@ impl Plugin for MainFilePlugin {
         State::Resolving(info)
     }
 }
+
+impl MainFilePlugin {
+    /// Node.js only tries the main file with the extensions, such as
+    /// `index.js`, but never `index` itself.
+    fn resolve_with_extensions(
+        resolver: &Resolver,
+        info: &Info,
+        path: &Path,
+        main_file: &str,
+        context: &mut Context,
+    ) -> State {
+        let fully_specified = context.fully_specified.get();
+        context.fully_specified.set(true);
+        let mut state = State::Resolving(info.clone());
+        for extension in &resolver.options.extensions {
+            let main_file_info = info
+                .clone()
+                .with_path(path)
+                .with_target(&format!("./{main_file}{extension}"));
+            state = resolver._resolve(main_file_info, context);
+            if state.is_finished() {
+                break;
+            }
+        }
+        context.fully_specified.set(fully_specified);
+        state
+    }
+}
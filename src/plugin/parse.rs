@@ -8,7 +8,7 @@ impl ParsePlugin {
         let had_hash = !request.fragment().is_empty();
         let no_query = request.query().is_empty();
         let had_request = !info.request().target().is_empty();
-        if no_query && had_hash && had_request {
+        if no_query && had_hash && had_request && !context.split_by_host {
             tracing::debug!("ParsePlugin works({})", depth(&context.depth));
             let target = format!(
                 "{}{}{}",
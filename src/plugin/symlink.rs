@@ -1,4 +1,4 @@
-use crate::{log::depth, Context, Info, ResolveResult, Resolver, State};
+use crate::{log::depth, Context, Error, Info, ResolveResult, Resolver, ResultKind, State};
 use std::path::PathBuf;
 
 #[derive(Default)]
@@ -8,6 +8,11 @@ impl SymlinkPlugin {
     pub fn apply(resolver: &Resolver, info: Info, context: &mut Context) -> State {
         debug_assert!(info.request().target().is_empty());
 
+        if context.result_kind == ResultKind::FullMetadata {
+            context.symlink_chain = resolver
+                .load_entry(info.normalized_path().as_ref())
+                .symlink_chain();
+        }
         if !resolver.options.symlinks {
             return State::Success(ResolveResult::Resource(info));
         }
@@ -60,6 +65,22 @@ fn resolve_symlink(resolver: &Resolver, info: Info, _context: &mut Context) -> S
             path.push(c);
         }
         head.init_real(path.clone().into_boxed_path());
+        if let Some(root) = resolver.options.workspace_root.as_ref() {
+            // keep the path in the workspace, the real path differs between machines.
+            if entry_path.starts_with(root) && !path.starts_with(root) {
+                return State::Success(ResolveResult::Resource(info));
+            }
+        }
+        if !resolver.options.allow_symlink_escape
+            && path.as_path() != entry_path
+            && !resolver.is_in_roots(&path)
+        {
+            return State::Error(Error::UnexpectedValue(format!(
+                "{} is a symlink to {} which is outside of the roots",
+                entry_path.display(),
+                path.display()
+            )));
+        }
         path
     } else {
         stack
@@ -0,0 +1,72 @@
+use crate::{Context, RResult, ResolveResult, Resolver, Resource};
+use std::{path::Path, sync::Mutex};
+
+/// Shares a resolver between threads, each thread takes a `ResolverHandle`
+/// which owns the scratch context of resolving, so the context is not
+/// allocated for every call.
+#[derive(Debug)]
+pub struct ResolverPool {
+    resolver: Resolver,
+    /// The contexts of the dropped handles.
+    idle: Mutex<Vec<Context>>,
+}
+
+/// A handle of `ResolverPool` for one thread, the cache is shared with
+/// the other handles, its context is returned to the pool when it is dropped.
+#[derive(Debug)]
+pub struct ResolverHandle<'a> {
+    pool: &'a ResolverPool,
+    context: Option<Context>,
+}
+
+impl ResolverPool {
+    #[must_use]
+    pub fn new(resolver: Resolver) -> Self {
+        Self {
+            resolver,
+            idle: Default::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn resolver(&self) -> &Resolver {
+        &self.resolver
+    }
+
+    #[must_use]
+    pub fn handle(&self) -> ResolverHandle<'_> {
+        let context = self
+            .idle
+            .lock()
+            .ok()
+            .and_then(|mut idle| idle.pop())
+            .unwrap_or_else(|| Context::new(false, false));
+        ResolverHandle {
+            pool: self,
+            context: Some(context),
+        }
+    }
+
+    /// The number of the contexts which are waiting for reuse.
+    #[must_use]
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().map_or(0, |idle| idle.len())
+    }
+}
+
+impl<'a> ResolverHandle<'a> {
+    /// The same as `Resolver::resolve`.
+    pub fn resolve(&mut self, path: &Path, request: &str) -> RResult<ResolveResult<Resource>> {
+        self.pool
+            .resolver
+            .resolve_in(path, request, None, self.context.as_mut(), None)
+    }
+}
+
+impl<'a> Drop for ResolverHandle<'a> {
+    fn drop(&mut self) {
+        if let (Some(context), Ok(mut idle)) = (self.context.take(), self.pool.idle.lock()) {
+            idle.push(context);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{parse::Request, Info, RResult, ResolveResult, Resolver, State};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// What a `ProtocolHandler` resolved the request to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolResolution {
+    /// The file of the request, it is returned as is, so it can be a
+    /// virtual module which does not exist.
+    Resolved(PathBuf),
+    /// The request is resolved to nothing, such as the built-in modules.
+    Ignored,
+    /// Resolve this request instead in the same directory, such as `pkg`
+    /// for `npm:pkg`.
+    Delegate(String),
+    /// Resolve the request in the directory instead, such as `pkg` in the
+    /// store of packages.
+    DelegateIn(PathBuf, String),
+}
+
+/// Resolves the requests with a protocol, such as `virtual:entry`,
+/// see `Options::protocols`.
+pub trait ProtocolHandler: std::fmt::Debug + Send + Sync {
+    /// `specifier` is the request without the protocol, such as `entry` of
+    /// `virtual:entry`, and `path` is the directory which it is issued from.
+    fn resolve(&self, path: &Path, specifier: &str) -> RResult<ProtocolResolution>;
+}
+
+/// The built-in modules of Node.js, such as `node:fs`, are ignored.
+#[derive(Debug, Default)]
+pub struct NodeProtocol;
+
+impl ProtocolHandler for NodeProtocol {
+    fn resolve(&self, _path: &Path, _specifier: &str) -> RResult<ProtocolResolution> {
+        Ok(ProtocolResolution::Ignored)
+    }
+}
+
+/// The `file:` URLs are resolved as the absolute paths they point to.
+#[derive(Debug, Default)]
+pub struct FileProtocol;
+
+impl ProtocolHandler for FileProtocol {
+    fn resolve(&self, _path: &Path, specifier: &str) -> RResult<ProtocolResolution> {
+        let url = format!("file:{specifier}");
+        let path = Request::decode_url(&url)?;
+        Ok(ProtocolResolution::Delegate(path.into_owned()))
+    }
+}
+
+/// The handlers of `node:` and `file:`.
+pub(crate) fn builtin() -> Vec<(String, Arc<dyn ProtocolHandler>)> {
+    vec![
+        (String::from("node:"), Arc::new(NodeProtocol)),
+        (String::from("file:"), Arc::new(FileProtocol)),
+    ]
+}
+
+/// Splits `request` into the protocol, such as `virtual:`, and the rest.
+/// The protocol has at least two characters, so `C:` of Windows is not one.
+pub fn split_protocol(request: &str) -> Option<(&str, &str)> {
+    let index = memchr::memchr(b':', request.as_bytes())?;
+    let scheme = &request[..index];
+    let mut chars = scheme.chars();
+    let valid = scheme.len() >= 2
+        && chars.next().map_or(false, |c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then(|| request.split_at(index + 1))
+}
+
+/// The result of a handler, it is resolving if the request is delegated.
+pub(crate) enum Handled {
+    Finished(State),
+    Delegated(Option<PathBuf>, String),
+}
+
+impl Resolver {
+    /// Runs the handler registered for the protocol of `request`, if any,
+    /// the protocol is returned along with the result.
+    pub(crate) fn handle_protocol<'r>(
+        &self,
+        path: &Path,
+        request: &'r str,
+    ) -> Option<(&'r str, Handled)> {
+        let (protocol, specifier) = split_protocol(request)?;
+        let (_, handler) = self
+            .options
+            .protocols
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(protocol))?;
+        tracing::debug!("'{protocol}' is handled by {handler:?}");
+        let handled = match handler.resolve(path, specifier) {
+            Ok(ProtocolResolution::Resolved(resolved)) => Handled::Finished(State::Success(
+                ResolveResult::Resource(Info::new(resolved, Request::default())),
+            )),
+            Ok(ProtocolResolution::Ignored) => {
+                Handled::Finished(State::Success(ResolveResult::Ignored))
+            }
+            Ok(ProtocolResolution::Delegate(request)) => Handled::Delegated(None, request),
+            Ok(ProtocolResolution::DelegateIn(path, request)) => {
+                Handled::Delegated(Some(path), request)
+            }
+            Err(error) => Handled::Finished(State::Error(error)),
+        };
+        Some((protocol, handled))
+    }
+}
+
+#[test]
+fn split_protocol_test() {
+    assert_eq!(split_protocol("node:fs"), Some(("node:", "fs")));
+    assert_eq!(split_protocol("npm:pkg@1/a:b"), Some(("npm:", "pkg@1/a:b")));
+    assert_eq!(
+        split_protocol("https://a.com/b.js"),
+        Some(("https:", "//a.com/b.js"))
+    );
+    assert_eq!(split_protocol("C:/a.js"), None);
+    assert_eq!(split_protocol("./a:b"), None);
+    assert_eq!(split_protocol("@scope/a:b"), None);
+    assert_eq!(split_protocol("1a:b"), None);
+}
@@ -0,0 +1,73 @@
+use crate::{Context, ResolveResult, State};
+
+/// The stage of the pipeline which mapped the request, see `Provenance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Stage {
+    /// `Options::alias`.
+    Alias,
+    /// `Options::fallback`.
+    Fallback,
+    /// The `browser` field.
+    BrowserField,
+    /// The fields in `Options::exports_field`.
+    ExportsField,
+    /// The `imports` field.
+    ImportsField,
+    /// The fields in `Options::main_fields`.
+    MainField,
+    /// The files in `Options::main_files`.
+    MainFile,
+    /// `compilerOptions.paths` of tsconfig.
+    TsconfigPaths,
+    /// A handler in `Options::protocols`.
+    Protocol,
+}
+
+/// How the file was found at last.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Lookup {
+    /// The path is the file itself.
+    #[default]
+    Exact,
+    /// The extension in `Options::extensions` was appended.
+    Extension(String),
+}
+
+/// Where the path of a result came from, for the diagnostics and the hosts
+/// which treat the aliased results differently.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Provenance {
+    /// The innermost stage which mapped the request, `None` if the request
+    /// was resolved as it is.
+    pub stage: Option<Stage>,
+    /// The key matched by `stage`, such as `"./feature/*"` of `exports`,
+    /// `"main"` of the main fields, or `"npm:"` of the protocols.
+    pub key: Option<String>,
+    /// Whether an extension was appended to the path of `stage`.
+    pub lookup: Lookup,
+}
+
+impl Context {
+    /// Records `stage` if `state` is a resolved file, the stages are recorded
+    /// from the innermost, so only the first one is kept.
+    pub(crate) fn record_stage(&mut self, state: &State, stage: Stage, key: &str) {
+        if self.stage.is_none() && matches!(state, State::Success(ResolveResult::Resource(_))) {
+            self.stage = Some((stage, key.to_string()));
+        }
+    }
+
+    pub(crate) fn take_provenance(&mut self) -> Provenance {
+        let (stage, key) = self.stage.take().unzip();
+        Provenance {
+            stage,
+            key,
+            lookup: self
+                .extension
+                .take()
+                .map_or(Lookup::Exact, Lookup::Extension),
+        }
+    }
+}
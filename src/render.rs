@@ -0,0 +1,164 @@
+use crate::{log::color, Error, Resolver};
+use std::path::{Path, PathBuf};
+
+/// The lines of a file around the offending position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Excerpt {
+    pub path: PathBuf,
+    /// In bytes from the start of the file.
+    pub offset: usize,
+    /// 1-based.
+    pub line: usize,
+    /// 1-based, counted in characters.
+    pub column: usize,
+    /// The numbers and the contents of the lines around `line`.
+    pub lines: Vec<(usize, String)>,
+}
+
+/// The failed resolution passed to `ErrorRenderer`.
+#[derive(Debug)]
+pub struct ErrorReport<'a> {
+    /// The directory which the request was issued from.
+    pub path: &'a Path,
+    pub request: &'a str,
+    pub error: &'a Error,
+    /// The part of the file which caused the error, such as a malformed
+    /// description file.
+    pub excerpt: Option<Excerpt>,
+}
+
+/// Formats the errors for the diagnostics of the host, such as in
+/// another language or with its own markup.
+pub trait ErrorRenderer: std::fmt::Debug + Send + Sync {
+    fn render(&self, report: &ErrorReport) -> String;
+}
+
+/// Renders the error with the colors of terminals, and the excerpt
+/// with the offending position highlighted.
+#[derive(Debug, Default)]
+pub struct PrettyRenderer;
+
+impl ErrorRenderer for PrettyRenderer {
+    fn render(&self, report: &ErrorReport) -> String {
+        let mut output = format!(
+            "{}: Can't resolve '{}' in '{}'\n  {}\n",
+            color::red(&color::bold(&"error")),
+            color::cyan(&report.request),
+            color::cyan(&report.path.display()),
+            report.error
+        );
+        if let Error::StoppedAt { boundary, .. } = report.error {
+            output.push_str(&format!(
+                "{} the modules above '{}' are not looked up, see `stop_at`\n",
+                color::blue(&"note:"),
+                boundary.display()
+            ));
+        }
+        if let Error::NotFound { paths, .. } = report.error {
+            for item in paths {
+                let missing = if item.exists { "" } else { " (missing)" };
+                output.push_str(&format!(
+                    "{} {}{missing}
+",
+                    color::blue(&"looked up in"),
+                    item.path.display()
+                ));
+            }
+        }
+        let Some(excerpt) = report.excerpt.as_ref() else {
+            return output;
+        };
+        let width = excerpt
+            .lines
+            .last()
+            .map_or(1, |(number, _)| number.to_string().len());
+        output.push_str(&format!(
+            "{:width$}{} {}:{}:{}\n",
+            "",
+            color::blue(&"-->"),
+            excerpt.path.display(),
+            excerpt.line,
+            excerpt.column
+        ));
+        for (number, content) in &excerpt.lines {
+            output.push_str(&format!(
+                "{} {content}\n",
+                color::blue(&format!("{number:>width$} |"))
+            ));
+            if *number == excerpt.line {
+                output.push_str(&format!(
+                    "{} {}{}\n",
+                    color::blue(&format!("{:width$} |", "")),
+                    " ".repeat(excerpt.column - 1),
+                    color::red(&"^")
+                ));
+            }
+        }
+        output
+    }
+}
+
+#[cfg(feature = "excerpt")]
+impl Excerpt {
+    /// `line` and `column` are 1-based, the lines next to `line` are included.
+    pub(crate) fn new(path: &Path, content: &str, line: usize, column: usize) -> Self {
+        let offset = content
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum::<usize>()
+            + content
+                .lines()
+                .nth(line.saturating_sub(1))
+                .map_or(0, |line| {
+                    line.chars()
+                        .take(column.saturating_sub(1))
+                        .map(char::len_utf8)
+                        .sum()
+                });
+        let lines = content
+            .lines()
+            .enumerate()
+            .map(|(index, content)| (index + 1, content.to_string()))
+            .skip(line.saturating_sub(2))
+            .take(if line > 1 { 3 } else { 2 })
+            .collect();
+        Self {
+            path: path.to_path_buf(),
+            offset,
+            line,
+            column: column.max(1),
+            lines,
+        }
+    }
+
+    /// `offset` is in bytes, it should be at a char boundary.
+    pub(crate) fn at_offset(path: &Path, content: &str, offset: usize) -> Self {
+        let before = &content[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        let column = before[line_start..].chars().count() + 1;
+        Self::new(path, content, line, column)
+    }
+}
+
+impl Resolver {
+    /// Formats `error` returned by resolving `request` in `path` with
+    /// `options.error_renderer`, or `PrettyRenderer` if it is not set.
+    #[must_use]
+    pub fn render_error(&self, path: &Path, request: &str, error: &Error) -> String {
+        let report = ErrorReport {
+            path,
+            request,
+            error,
+            #[cfg(feature = "excerpt")]
+            excerpt: self.error_excerpt(path, request, error),
+            #[cfg(not(feature = "excerpt"))]
+            excerpt: None,
+        };
+        match self.options.error_renderer.as_ref() {
+            Some(renderer) => renderer.render(&report),
+            None => PrettyRenderer.render(&report),
+        }
+    }
+}
@@ -1,29 +1,61 @@
 use crate::{
     description::DescriptionData,
+    entry::Entry,
     info::NormalizedPath,
     kind::PathKind,
     log::color,
+    options::Probe,
     plugin::{
         BrowserFieldPlugin, ExportsFieldPlugin, ExtensionAliasPlugin, ImportsFieldPlugin,
         MainFieldPlugin, MainFilePlugin, Plugin,
     },
-    Context, EnforceExtension, Info, ResolveResult, Resolver, State,
+    Context, EnforceExtension, Error, ExtensionConflictPolicy, Info, IoErrorClass,
+    NestedMainFields, ProbeOrder, RResult, ResolveResult, Resolver, State,
 };
 use std::{
     borrow::Cow,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 impl Resolver {
-    fn resolve_file_with_ext(&self, mut path: PathBuf, info: Info) -> State {
-        let v = unsafe { &mut *(&mut path as *mut PathBuf as *mut Vec<u8>) };
+    fn load_candidate(&self, path: &Path) -> Arc<Entry> {
+        if let Some(observer) = self.options.observer.as_ref() {
+            observer.on_candidate(path);
+        }
+        self.load_entry(path)
+    }
+
+    /// `Error::PermissionDenied` if the stat of the candidate was denied,
+    /// see `Options::ignore_permission_denied`.
+    fn denied(&self, candidate: &Entry, path: &Path) -> Option<Error> {
+        let denied = candidate.cached_stat().error() == Some(IoErrorClass::PermissionDenied);
+        (denied && !self.options.ignore_permission_denied)
+            .then(|| Error::PermissionDenied(path.into()))
+    }
+
+    fn resolve_file_with_ext(
+        &self,
+        path: &mut PathBuf,
+        info: Info,
+        context: &mut Context,
+    ) -> State {
+        let v = unsafe { &mut *(path as *mut PathBuf as *mut Vec<u8>) };
         for ext in &self.options.extensions {
+            if let Some(error) = context.interrupted(path) {
+                return State::Error(error);
+            }
             v.extend_from_slice(ext.as_bytes());
-            if self.load_entry(path.as_ref()).is_file() {
+            let candidate = self.load_candidate(path.as_ref());
+            if candidate.is_file() {
+                context.extension.get_or_insert_with(|| ext.clone());
                 return State::Success(ResolveResult::Resource(
-                    info.with_path(path).with_target(""),
+                    info.with_path(&*path).with_target(""),
                 ));
             }
+            if let Some(error) = self.denied(&candidate, path) {
+                return State::Error(error);
+            }
             unsafe {
                 v.set_len(v.len() - ext.len());
             }
@@ -45,7 +77,7 @@ impl Resolver {
             "Attempting to load '{}' as a context",
             color::blue(&path.display())
         );
-        if self.load_entry(&path).is_dir() {
+        if self.load_candidate(&path).is_dir() {
             State::Success(ResolveResult::Resource(Info::new(path, Default::default())))
         } else {
             State::Failed(info)
@@ -60,11 +92,14 @@ impl Resolver {
         let path = info.to_resolved_path();
         let request = info.request();
         let target = request.target();
-        if self.load_entry(&path).is_file() {
+        let candidate = self.load_candidate(&path);
+        if candidate.is_file() {
             let path = path.to_path_buf();
             State::Success(ResolveResult::Resource(
                 info.with_path(path).with_target(""),
             ))
+        } else if let Some(error) = self.denied(&candidate, &path) {
+            State::Error(error)
         } else if matches!(
             request.kind(),
             PathKind::AbsolutePosix | PathKind::AbsoluteWin | PathKind::Relative
@@ -86,33 +121,149 @@ impl Resolver {
     }
 
     pub(crate) fn resolve_as_file(&self, info: Info, context: &mut Context) -> State {
-        if info.request().is_directory() {
+        if info.request().is_directory() && self.options.strict_directory_requests {
             return State::Resolving(info);
         }
 
         self.options
             .extension_alias
             .iter()
-            .fold(State::Resolving(info), |state, (extension, alias_list)| {
-                state.then(|info| {
-                    ExtensionAliasPlugin::new(extension, alias_list).apply(self, info, context)
-                })
-            })
+            .fold(
+                self.resolve_as_source(info, context),
+                |state, (extension, alias_list)| {
+                    state.then(|info| {
+                        ExtensionAliasPlugin::new(extension, alias_list).apply(self, info, context)
+                    })
+                },
+            )
             .then(|info| {
-                let path = info.to_resolved_path().to_path_buf();
-                tracing::debug!(
-                    "Attempting to load '{}' as a file",
-                    color::blue(&path.display())
-                );
-                if matches!(self.options.enforce_extension, EnforceExtension::Enabled) {
-                    self.resolve_file_with_ext(path, info)
-                } else if self.load_entry(&path).is_file() {
-                    State::Success(ResolveResult::Resource(
-                        info.with_path(path).with_target(""),
-                    ))
-                } else {
-                    self.resolve_file_with_ext(path, info)
+                let extra = self.options.extensions.iter().map(String::len).max();
+                let (mut path, allocated) = context
+                    .paths
+                    .alloc(&info.to_resolved_path(), extra.unwrap_or_default());
+                if allocated {
+                    self.metrics.record_path_allocation();
                 }
+                let state = self.resolve_as_file_at(&mut path, info, context);
+                context.paths.free(path);
+                state
+            })
+    }
+
+    /// `path` is the buffer of the candidates, which is given back to
+    /// `Context::paths` after the probes.
+    fn resolve_as_file_at(&self, path: &mut PathBuf, info: Info, context: &mut Context) -> State {
+        tracing::debug!(
+            "Attempting to load '{}' as a file",
+            color::blue(&path.display())
+        );
+        let unknown_extension = self.has_unknown_extension(path);
+        if !unknown_extension && matches!(self.options.enforce_extension, EnforceExtension::Enabled)
+        {
+            return self.resolve_file_with_ext(path, info, context);
+        }
+        if unknown_extension {
+            return self.resolve_explicit_file(path, info);
+        }
+        match self.options.extension_conflict_policy {
+            ExtensionConflictPolicy::PreferExplicit => self
+                .resolve_explicit_file(path, info)
+                .then(|info| self.resolve_file_with_ext(path, info, context)),
+            ExtensionConflictPolicy::PreferAppended => self
+                .resolve_file_with_ext(path, info, context)
+                .then(|info| self.resolve_explicit_file(path, info)),
+        }
+    }
+
+    /// Resolves the request as it is, without the extensions.
+    fn resolve_explicit_file(&self, path: &Path, info: Info) -> State {
+        let candidate = self.load_candidate(path);
+        if candidate.is_file() {
+            State::Success(ResolveResult::Resource(
+                info.with_path(path).with_target(""),
+            ))
+        } else if let Some(error) = self.denied(&candidate, path) {
+            State::Error(error)
+        } else {
+            State::Resolving(info)
+        }
+    }
+
+    /// Whether the file name of `path` ends with an extension which is not
+    /// in `extensions`, see `Options::resolve_unknown_extensions`.
+    fn has_unknown_extension(&self, path: &Path) -> bool {
+        if !self.options.resolve_unknown_extensions || path.extension().is_none() {
+            return false;
+        }
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        !self
+            .options
+            .extensions
+            .iter()
+            .any(|extension| !extension.is_empty() && name.ends_with(extension.as_str()))
+    }
+
+    /// The outputs of the referenced projects are mapped to the sources in
+    /// their `rootDir`, such as `dist/index.js` to `src/index.ts`.
+    fn resolve_as_source(&self, info: Info, context: &mut Context) -> State {
+        if context.source_dirs.is_empty() {
+            return State::Resolving(info);
+        }
+        let path = info.to_resolved_path();
+        for index in 0..context.source_dirs.len() {
+            let (out_dir, root_dir) = &context.source_dirs[index];
+            let Ok(relative) = path.strip_prefix(out_dir) else {
+                continue;
+            };
+            let mut source = strip_output_extension(&root_dir.join(relative));
+            tracing::debug!(
+                "Attempting to load '{}' as the source of '{}'",
+                color::blue(&source.display()),
+                color::blue(&path.display())
+            );
+            let state = self.resolve_file_with_ext(&mut source, info.clone(), context);
+            if state.is_finished() {
+                return state;
+            }
+        }
+        State::Resolving(info)
+    }
+
+    pub(crate) fn resolve_as_file_or_dir(
+        &self,
+        info: Info,
+        order: ProbeOrder,
+        context: &mut Context,
+    ) -> State {
+        // the main fields and main files of a directory are resolved as usual.
+        let order = if context.probing_dir.get() {
+            ProbeOrder::FileFirst
+        } else {
+            order
+        };
+        let probes = order.probes();
+        probes
+            .iter()
+            .enumerate()
+            .fold(State::Resolving(info), |state, (index, probe)| {
+                state.then(|info| match probe {
+                    Probe::File => self.resolve_as_file(info, context),
+                    Probe::Dir => {
+                        let prev_probing_dir = context.probing_dir.get();
+                        context.probing_dir.set(true);
+                        let state = self.resolve_as_dir(info, context);
+                        context.probing_dir.set(prev_probing_dir);
+                        match state {
+                            // the file is still tried if it is not a directory.
+                            State::Failed(info) if index + 1 < probes.len() => {
+                                State::Resolving(info)
+                            }
+                            state => state,
+                        }
+                    }
+                })
             })
     }
 
@@ -126,42 +277,153 @@ impl Resolver {
             Ok(pkg_info) => pkg_info,
             Err(err) => return State::Error(err),
         };
-        if let Some(pkg_info) = pkg_info {
-            MainFieldPlugin::new(pkg_info).apply(self, info, context)
-        } else {
-            State::Resolving(info)
+        match pkg_info {
+            Some(pkg_info) => match self.nested_main_fields(&entry, pkg_info) {
+                Ok(NestedMainFields::All) => {
+                    MainFieldPlugin::new(pkg_info).apply(self, info, context)
+                }
+                Ok(NestedMainFields::Main) => {
+                    let main = [String::from("main")];
+                    MainFieldPlugin::new(pkg_info)
+                        .with_fields(&main)
+                        .apply(self, info, context)
+                }
+                Ok(NestedMainFields::Ignore) => State::Resolving(info),
+                Err(err) => State::Error(err),
+            },
+            None => State::Resolving(info),
         }
         .then(|info| MainFilePlugin.apply(self, info, context))
     }
 
+    /// `Options::nested_main_fields` if `pkg_info` is a nested description
+    /// file of a package, otherwise all the main fields are read.
+    fn nested_main_fields(
+        &self,
+        entry: &Entry,
+        pkg_info: &DescriptionData,
+    ) -> RResult<NestedMainFields> {
+        if self.options.nested_main_fields == NestedMainFields::All {
+            return Ok(NestedMainFields::All);
+        }
+        let nested = entry
+            .package_root_info(self)?
+            .as_ref()
+            .map_or(false, |root| root.dir() != pkg_info.dir());
+        Ok(if nested {
+            self.options.nested_main_fields
+        } else {
+            NestedMainFields::All
+        })
+    }
+
+    /// Whether the ancestors of `dir` are out of `Options::stop_at`.
+    pub(crate) fn is_stop_at(&self, dir: &Path) -> bool {
+        self.options.stop_at.as_deref() == Some(dir)
+    }
+
+    /// Whether the description files in the ancestors of `dir` are out of
+    /// `Options::description_search_roots`.
+    pub(crate) fn is_description_search_root(&self, dir: &Path) -> bool {
+        self.options
+            .description_search_roots
+            .iter()
+            .any(|root| root == dir)
+    }
+
+    /// The nearest root of `Options::description_search_roots` which `path`
+    /// is inside.
+    pub(crate) fn description_search_root(&self, path: &Path) -> Option<&Path> {
+        self.options
+            .description_search_roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(PathBuf::as_path)
+    }
+
     pub(crate) fn resolve_as_modules(&self, info: Info, context: &mut Context) -> State {
+        let target = info.request().target();
+        if let Some(prefix) = self
+            .options
+            .module_alias_prefixes
+            .iter()
+            .find(|prefix| target.starts_with(prefix.as_str()))
+        {
+            tracing::debug!(
+                "'{}' starts with the alias prefix '{}', it is not looked up in modules",
+                color::red(&target),
+                color::red(prefix)
+            );
+            return State::Failed(info);
+        }
+        if target.starts_with('@') && !target.contains('/') {
+            return State::Error(Error::UnexpectedValue(format!(
+                "Invalid module request \"{target}\", the name of a scoped package should be in the form of \"@scope/name\""
+            )));
+        }
+
+        let info = match self
+            .apply_overrides(info, context)
+            .then(|info| self.apply_isolated_mode(info, context))
+        {
+            State::Resolving(info) => info,
+            state => return state,
+        };
         let original_dir = info.normalized_path();
+        let request_module_name = get_module_name_from_request(info.request().target());
         for module in &self.options.modules {
-            let node_modules_path = Path::new(module);
-            let (node_modules_path, need_find_up) = if node_modules_path.is_absolute() {
-                (Cow::Borrowed(node_modules_path), false)
-            } else {
-                (Cow::Owned(original_dir.as_ref().join(module)), true)
+            let strategy = ModulesDirStrategy::new(module);
+            if !strategy.is_in_scope(self, request_module_name) {
+                continue;
+            }
+            let state = match strategy {
+                ModulesDirStrategy::Hierarchical(name) => {
+                    let node_modules_path = original_dir.as_ref().join(name);
+                    self._resolve_as_modules(
+                        info.clone(),
+                        original_dir,
+                        &node_modules_path,
+                        context,
+                    )
+                    .then(|info| match original_dir.as_ref().parent() {
+                        Some(_) if self.is_stop_at(original_dir.as_ref()) => {
+                            context.stopped_at_boundary = true;
+                            State::Resolving(info)
+                        }
+                        Some(parent_dir) => {
+                            let prev_finding_up = context.finding_up_modules.get();
+                            context.finding_up_modules.set(true);
+                            let state = self._resolve(info.with_path(parent_dir), context);
+                            context.finding_up_modules.set(prev_finding_up);
+                            state
+                        }
+                        None => State::Resolving(info),
+                    })
+                }
+                // the stores had been looked up by the outermost call.
+                ModulesDirStrategy::Absolute(_) if context.finding_up_modules.get() => continue,
+                ModulesDirStrategy::Absolute(store) => {
+                    self._resolve_as_modules(info.clone(), original_dir, store, context)
+                }
             };
-            let state = self
-                ._resolve_as_modules(info.clone(), original_dir, &node_modules_path, context)
-                .then(|info| {
-                    if !need_find_up {
-                        State::Resolving(info)
-                    } else if let Some(parent_dir) = original_dir.as_ref().parent() {
-                        self._resolve(info.with_path(parent_dir), context)
-                    } else {
-                        State::Resolving(info)
-                    }
-                });
             if state.is_finished() {
                 return state;
             }
         }
+        // the global folders are the last resort, after all the ancestors are visited.
+        if !context.finding_up_modules.get() {
+            for folder in &self.global_folders {
+                let state = self._resolve_as_modules(info.clone(), original_dir, folder, context);
+                if state.is_finished() {
+                    return state;
+                }
+            }
+        }
         State::Failed(info)
     }
 
-    fn _resolve_as_modules(
+    pub(crate) fn _resolve_as_modules(
         &self,
         info: Info,
         original_dir: &NormalizedPath,
@@ -169,6 +431,7 @@ impl Resolver {
         context: &mut Context,
     ) -> State {
         let entry = self.load_entry(node_modules_path);
+        context.modules_dirs.push(entry.clone());
         let pkg_info = match entry.pkg_info(self) {
             Ok(pkg_info) => pkg_info.as_ref(),
             Err(err) => return State::Error(err),
@@ -242,8 +505,9 @@ impl Resolver {
             }
             .then(|info| self.resolve_as_context(info, context))
             .then(|info| self.resolve_as_fully_specified(info, context))
-            .then(|info| self.resolve_as_file(info, context))
-            .then(|info| self.resolve_as_dir(info, context));
+            .then(|info| {
+                self.resolve_as_file_or_dir(info, self.options.modules_probe_order, context)
+            });
 
             match state {
                 State::Failed(info) => State::Resolving(info),
@@ -253,6 +517,63 @@ impl Resolver {
     }
 }
 
+/// How an item of `modules` is looked up.
+pub(crate) enum ModulesDirStrategy<'a> {
+    /// A folder name, such as `node_modules`, which is looked up in the
+    /// directory of the request and all its ancestors.
+    Hierarchical(&'a str),
+    /// A shared store, which is looked up once and before the ancestors of
+    /// the request are visited.
+    Absolute(&'a Path),
+}
+
+impl<'a> ModulesDirStrategy<'a> {
+    pub(crate) fn new(module: &'a str) -> Self {
+        let path = Path::new(module);
+        if path.is_absolute() {
+            Self::Absolute(path)
+        } else {
+            Self::Hierarchical(module)
+        }
+    }
+
+    fn key(&self) -> &str {
+        match self {
+            Self::Hierarchical(name) => name,
+            Self::Absolute(path) => path.to_str().unwrap_or_default(),
+        }
+    }
+
+    /// Whether `modules_scope` allows the module to be looked up in this directory.
+    pub(crate) fn is_in_scope(&self, resolver: &Resolver, module_name: &str) -> bool {
+        let key = self.key();
+        let Some((_, patterns)) = resolver
+            .options
+            .modules_scope
+            .iter()
+            .find(|(module, _)| module == key)
+        else {
+            return true;
+        };
+        patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => module_name.starts_with(prefix),
+                None => module_name == pattern,
+            })
+    }
+}
+
+fn strip_output_extension(path: &Path) -> PathBuf {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return path.to_path_buf();
+    };
+    [".d.ts", ".js", ".jsx", ".mjs", ".cjs"]
+        .iter()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .map_or_else(|| path.to_path_buf(), |stem| path.with_file_name(stem))
+}
+
 fn is_resolve_self(pkg_info: &DescriptionData, request_module_name: &str) -> bool {
     pkg_info
         .data()
@@ -261,32 +582,44 @@ fn is_resolve_self(pkg_info: &DescriptionData, request_module_name: &str) -> boo
         .map_or(false, |ans| ans)
 }
 
-/// split the index from `[module-name]/[path]`
+/// split the index from `[module-name]/[path]`, the index is the byte offset.
 pub(crate) fn split_slash_from_request(target: &str) -> Option<usize> {
-    let has_namespace_scope = target.starts_with('@');
-    let chars = target.chars().enumerate();
-    let slash_index_list: Vec<usize> = chars
-        .filter_map(|(index, char)| if '/' == char { Some(index) } else { None })
-        .collect();
+    // `@/` is not a scope, it is used as the path alias by some frameworks.
+    let has_namespace_scope = target.starts_with('@') && !target.starts_with("@/");
+    let mut slash_index_list = memchr::memchr_iter(b'/', target.as_bytes());
     if has_namespace_scope {
-        slash_index_list.get(1)
+        slash_index_list.nth(1)
     } else {
-        slash_index_list.first()
+        slash_index_list.next()
     }
-    .copied()
 }
 
-fn get_module_name_from_request(target: &str) -> &str {
-    split_slash_from_request(target).map_or(target, |index| &target[0..index])
+/// split `[module-name]/[path]` into `([module-name], Some("/[path]"))`,
+/// it splits at byte offset so that multibyte characters are kept as they are.
+fn split_request(target: &str) -> (&str, Option<&str>) {
+    match split_slash_from_request(target) {
+        Some(index) => {
+            let (module_name, path) = target.split_at(index);
+            (module_name, Some(path))
+        }
+        None => (target, None),
+    }
+}
+
+pub(crate) fn get_module_name_from_request(target: &str) -> &str {
+    split_request(target).0
 }
 
 pub(crate) fn get_path_from_request(target: &str) -> Option<Cow<str>> {
-    split_slash_from_request(target).map(|index| Cow::Borrowed(&target[index..]))
+    split_request(target).1.map(Cow::Borrowed)
 }
 
 #[cfg(test)]
 mod test {
-    use super::{get_module_name_from_request, get_path_from_request, split_slash_from_request};
+    use super::{
+        get_module_name_from_request, get_path_from_request, split_request,
+        split_slash_from_request,
+    };
 
     #[test]
     fn test_split_slash_from_request() {
@@ -295,6 +628,26 @@ mod test {
         assert_eq!(split_slash_from_request("@a"), None);
         assert_eq!(split_slash_from_request("@a/b"), None);
         assert_eq!(split_slash_from_request("@a/b/c"), Some(4));
+        // found by `cargo fuzz run resolve_request`
+        assert_eq!(split_slash_from_request("中/a"), Some(3));
+        assert_eq!(split_slash_from_request("中文"), None);
+        assert_eq!(split_slash_from_request("中文/a"), Some(6));
+        assert_eq!(split_slash_from_request("@中/文"), None);
+        assert_eq!(split_slash_from_request("@中/文/b"), Some(8));
+        assert_eq!(split_slash_from_request("😀/a"), Some(4));
+        assert_eq!(split_slash_from_request("@😀/😀/😀"), Some(10));
+    }
+
+    #[test]
+    fn test_split_request() {
+        assert_eq!(split_request("a"), ("a", None));
+        assert_eq!(split_request("a/b"), ("a", Some("/b")));
+        assert_eq!(split_request("中文/文件.js"), ("中文", Some("/文件.js")));
+        assert_eq!(split_request("@スコープ/😀"), ("@スコープ/😀", None));
+        assert_eq!(
+            split_request("@スコープ/😀/lib/文件"),
+            ("@スコープ/😀", Some("/lib/文件"))
+        );
     }
 
     #[test]
@@ -304,6 +657,9 @@ mod test {
         assert_eq!(get_module_name_from_request("@a"), "@a");
         assert_eq!(get_module_name_from_request("@a/b"), "@a/b");
         assert_eq!(get_module_name_from_request("@a/b/c"), "@a/b");
+        assert_eq!(get_module_name_from_request("@/a/b"), "@");
+        assert_eq!(get_module_name_from_request("中文/a"), "中文");
+        assert_eq!(get_module_name_from_request("@中/😀/a"), "@中/😀");
     }
 
     #[test]
@@ -313,5 +669,7 @@ mod test {
         assert_eq!(get_path_from_request("@a"), None);
         assert_eq!(get_path_from_request("@a/b"), None);
         assert_eq!(get_path_from_request("@a/b/c"), Some("/c".into()));
+        assert_eq!(get_path_from_request("中文/😀"), Some("/😀".into()));
+        assert_eq!(get_path_from_request("@中/😀/文"), Some("/文".into()));
     }
 }
@@ -0,0 +1,141 @@
+use crate::{Algorithm, Cache, Options, Resolver};
+use dashmap::DashMap;
+use rustc_hash::FxHasher;
+use std::{hash::BuildHasherDefault, sync::Arc};
+
+/// The options which differ from the base options of `ResolverFactory`,
+/// such as the conditions of `import` and `require()`.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct OptionsDelta {
+    /// Added to `condition_names`.
+    pub add_conditions: Vec<String>,
+    /// Removed from `condition_names`, before `add_conditions` is added.
+    pub remove_conditions: Vec<String>,
+    /// Replaces `extensions`.
+    pub extensions: Option<Vec<String>>,
+    /// Replaces `main_fields`.
+    pub main_fields: Option<Vec<String>>,
+    /// Replaces `fully_specified`.
+    pub fully_specified: Option<bool>,
+    /// Replaces `prefer_relative`.
+    pub prefer_relative: Option<bool>,
+    /// Replaces `algorithm`.
+    pub algorithm: Option<Algorithm>,
+}
+
+impl OptionsDelta {
+    fn apply(&self, mut options: Options) -> Options {
+        options
+            .condition_names
+            .retain(|condition| !self.remove_conditions.contains(condition));
+        options
+            .condition_names
+            .extend(self.add_conditions.iter().cloned());
+        if let Some(extensions) = &self.extensions {
+            options.extensions = extensions.clone();
+        }
+        if let Some(main_fields) = &self.main_fields {
+            options.main_fields = main_fields.clone();
+        }
+        if let Some(fully_specified) = self.fully_specified {
+            options.fully_specified = fully_specified;
+        }
+        if let Some(prefer_relative) = self.prefer_relative {
+            options.prefer_relative = prefer_relative;
+        }
+        if let Some(algorithm) = self.algorithm {
+            options.algorithm = algorithm;
+        }
+        options
+    }
+}
+
+/// Called when `ResolverFactory` creates a child resolver, such as applying
+/// the plugins of the hosts to it.
+pub trait ResolverCreated: std::fmt::Debug + Send + Sync {
+    fn resolver_created(&self, category: &str, delta: &OptionsDelta, resolver: &Resolver);
+}
+
+/// Creates the resolvers of the categories of the dependencies, such as
+/// `esm`, `commonjs`, `url` and `worker`, as `ResolverFactory` of webpack
+/// does. The resolvers are created once for each category and delta, and
+/// they share the cache with each other.
+#[derive(Debug)]
+pub struct ResolverFactory {
+    options: Options,
+    cache: Arc<Cache>,
+    resolvers: DashMap<(String, OptionsDelta), Arc<Resolver>, BuildHasherDefault<FxHasher>>,
+    on_created: Option<Arc<dyn ResolverCreated>>,
+}
+
+impl ResolverFactory {
+    /// `options` is the base of the children, its `external_cache` is shared
+    /// by them if there is one.
+    #[must_use]
+    pub fn new(options: Options) -> Self {
+        let cache = options.external_cache.clone().unwrap_or_default();
+        Self {
+            options,
+            cache,
+            resolvers: Default::default(),
+            on_created: None,
+        }
+    }
+
+    #[must_use]
+    pub fn on_created(self, hook: Arc<dyn ResolverCreated>) -> Self {
+        Self {
+            on_created: Some(hook),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn cache(&self) -> &Arc<Cache> {
+        &self.cache
+    }
+
+    /// Returns the resolver of `category` with the base options changed by
+    /// `delta`, it is created if it was not.
+    pub fn get(&self, category: &str, delta: &OptionsDelta) -> Arc<Resolver> {
+        let key = (category.to_string(), delta.clone());
+        if let Some(resolver) = self.resolvers.get(&key) {
+            return resolver.clone();
+        }
+        let mut created = false;
+        let resolver = self
+            .resolvers
+            .entry(key)
+            .or_insert_with(|| {
+                created = true;
+                let options = Options {
+                    external_cache: Some(self.cache.clone()),
+                    ..delta.apply(self.options.clone())
+                };
+                Arc::new(Resolver::new(options))
+            })
+            .clone();
+        // the hook is called after the shard is unlocked, so it can get the
+        // other resolvers.
+        if let (true, Some(hook)) = (created, &self.on_created) {
+            hook.resolver_created(category, delta, &resolver);
+        }
+        resolver
+    }
+
+    /// The number of the resolvers which were created.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+
+    /// Drops the resolvers, the cache is kept.
+    pub fn clear(&self) {
+        self.resolvers.clear();
+    }
+}
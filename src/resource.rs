@@ -1,30 +1,108 @@
-use crate::{description::DescriptionData, info::Info, Resolver};
+use crate::{description::DescriptionData, info::Info, ModuleType, Provenance, Resolver, Symlink};
 use std::{path::PathBuf, sync::Arc};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Resource {
     pub path: PathBuf,
     pub query: Option<String>,
     pub fragment: Option<String>,
     pub description: Option<Arc<DescriptionData>>,
+    /// The field in `main_fields` which was used when the request pointed
+    /// to a package directory, such as `"main"` or `"module"`.
+    pub main_field: Option<String>,
+    /// Inferred from the extension by `Options::module_types`.
+    pub module_type: ModuleType,
+    /// The stage which produced `path`, and how the file was found.
+    pub provenance: Provenance,
+    /// The root of `Options::description_search_roots` which stopped the
+    /// lookup of `description`, the description files above it were not
+    /// read, `None` if the lookup didn't reach a root.
+    pub description_boundary: Option<PathBuf>,
+    /// The segments of the path before the symlinks were resolved which
+    /// are symlinks, from the outermost, so the hosts can dedupe the
+    /// packages by their own policy and watch the real directories. It is
+    /// recorded even if `Options::symlinks` is disabled.
+    pub symlink_chain: Vec<Symlink>,
+    /// The path before the symlinks were resolved, only recorded if
+    /// `Options::dedupe_by_realpath` is enabled.
+    pub logical_path: Option<PathBuf>,
+    /// The path after the symlinks were resolved, which is the same for the
+    /// results reached through the different symlinks, only recorded if
+    /// `Options::dedupe_by_realpath` is enabled.
+    pub real_path: Option<PathBuf>,
 }
 
+/// What the caller of `Resolver::resolve_with_kind` needs from the result,
+/// the steps which only produce the other parts of the result are skipped.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ResultKind {
+    /// Only the path, the query and the fragment of `Resource`, the
+    /// description and the provenance of the result are not looked up.
+    Path,
+    /// Only whether the request is resolved, such as for the hosts which
+    /// check the requests of `require.context`. Besides what `Path` skips,
+    /// the symlinks of the result are not resolved unless
+    /// `Options::allow_symlink_escape` is disabled, and the main fields
+    /// are not followed with `Options::resolve_to_context`, as the
+    /// directory of the package exists anyway. So the path of the result
+    /// only witnesses the existence.
+    Existence,
+    /// The whole `Resource`, as `Resolver::resolve` returns.
+    FullMetadata,
+}
+
+/// The main fields which predate `exports`, and are only understood by bundlers.
+pub const LEGACY_MAIN_FIELDS: [&str; 3] = ["module", "jsnext:main", "es2015"];
+
 impl Resource {
-    pub(crate) fn new(info: Info, resolver: &Resolver) -> Self {
+    /// The description is only looked up for `ResultKind::FullMetadata`.
+    pub(crate) fn new(info: Info, resolver: &Resolver, kind: ResultKind) -> Self {
         let path = info.normalized_path().as_ref().to_path_buf();
         let query = info.request().query();
         let fragment = info.request().fragment();
-        let description = resolver
-            .load_entry(&path)
-            .pkg_info(resolver)
-            .unwrap()
-            .clone();
+        let description = match kind {
+            ResultKind::FullMetadata => resolver
+                .load_entry(&path)
+                .pkg_info(resolver)
+                .unwrap()
+                .clone(),
+            ResultKind::Path | ResultKind::Existence => None,
+        };
+        let description_boundary = match description {
+            Some(_) => None,
+            None if kind != ResultKind::FullMetadata => None,
+            None => resolver.description_search_root(&path).map(PathBuf::from),
+        };
+        let module_type = ModuleType::infer(&path, &resolver.options.module_types);
+        let path = resolver.output_path(path);
         Resource {
             path,
             query: (!query.is_empty()).then(|| query.into()),
             fragment: (!fragment.is_empty()).then(|| fragment.into()),
             description,
+            main_field: None,
+            module_type,
+            provenance: Provenance::default(),
+            description_boundary,
+            symlink_chain: vec![],
+            logical_path: None,
+            real_path: None,
+        }
+    }
+
+    /// Returns the main field if it is one of `LEGACY_MAIN_FIELDS` and the
+    /// package provides neither `main` nor `exports`, which means the legacy
+    /// field is the only entry of the package.
+    #[must_use]
+    pub fn legacy_main_field(&self) -> Option<&str> {
+        let main_field = self.main_field.as_deref()?;
+        if !LEGACY_MAIN_FIELDS.contains(&main_field) {
+            return None;
         }
+        let raw = self.description.as_ref()?.data().raw();
+        (raw.get("main").is_none() && raw.get("exports").is_none()).then_some(main_field)
     }
 
     pub fn join(&self) -> PathBuf {
@@ -0,0 +1,39 @@
+use crate::{
+    context::Context, BrowserFieldPlugin, ImportsFieldPlugin, Info, Plugin, Resolver, State,
+};
+
+impl Resolver {
+    /// Applies the `imports` and the `browser` fields of `options.root_package`
+    /// to the request issued inside the application, if the nearest package
+    /// of the request is not the application itself.
+    pub(crate) fn apply_root_package(&self, info: Info, context: &mut Context) -> State {
+        let Some(root) = self.options.root_package.as_deref() else {
+            return State::Resolving(info);
+        };
+        let Ok(inside) = info.normalized_path().as_ref().strip_prefix(root) else {
+            return State::Resolving(info);
+        };
+        if inside
+            .components()
+            .any(|component| component.as_os_str() == "node_modules")
+        {
+            return State::Resolving(info);
+        }
+        let root_info = match self.load_entry(root).pkg_info(self) {
+            Ok(Some(pkg_info)) if pkg_info.dir().as_ref() == root => pkg_info.clone(),
+            Ok(_) => return State::Resolving(info),
+            Err(error) => return State::Error(error),
+        };
+        let nearest = match self.load_entry(&info.to_resolved_path()).pkg_info(self) {
+            Ok(pkg_info) => pkg_info.clone(),
+            Err(error) => return State::Error(error),
+        };
+        if nearest.map_or(false, |nearest| nearest.dir().as_ref() == root) {
+            // it is applied as the nearest package.
+            return State::Resolving(info);
+        }
+        ImportsFieldPlugin::new(&root_info)
+            .apply(self, info, context)
+            .then(|info| BrowserFieldPlugin::new(&root_info, false).apply(self, info, context))
+    }
+}
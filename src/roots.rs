@@ -0,0 +1,70 @@
+use crate::{info::NormalizedPath, FileSystem, Resolver};
+use std::path::{Component, Path, PathBuf};
+
+/// Joins `path` to the current working directory and normalizes it.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    NormalizedPath::new(cwd.join(path)).as_ref().to_path_buf()
+}
+
+/// Makes `roots` absolute and appends their real paths if they are
+/// symlinks, an empty `roots` means the current working directory.
+pub(crate) fn normalize(roots: &[PathBuf], fs: &dyn FileSystem) -> Vec<PathBuf> {
+    let roots = if roots.is_empty() {
+        vec![PathBuf::new()]
+    } else {
+        roots.to_vec()
+    };
+    let mut normalized = vec![];
+    for root in roots {
+        let root = absolute(&root);
+        if let Ok(real) = fs.canonicalize(&root) {
+            if real != root {
+                normalized.push(real);
+            }
+        }
+        normalized.push(root);
+    }
+    normalized
+}
+
+/// The path from `base` to `path`, both are absolute and normalized.
+/// `path` is returned as is if they are on different drives of Windows.
+fn relative(path: &Path, base: &Path) -> PathBuf {
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+    if matches!(
+        path_components.peek(),
+        Some(Component::Prefix(_) | Component::RootDir)
+    ) {
+        return path.to_path_buf();
+    }
+    let mut relative: PathBuf = base_components.map(|_| Component::ParentDir).collect();
+    relative.extend(path_components);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    relative
+}
+
+impl Resolver {
+    pub(crate) fn is_in_roots(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+
+    /// Applies `workspace_root` and `output_path_style` to a returned path.
+    pub(crate) fn output_path(&self, path: PathBuf) -> PathBuf {
+        let path = match self.options.workspace_root.as_ref() {
+            Some(root) if path.is_absolute() => relative(&path, root),
+            _ => path,
+        };
+        self.options.output_path_style.apply(path)
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{RResult, ResolveResult, Resolver, Resource};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
+
+thread_local! {
+    /// The blocked probes of the `resolve_sandboxed` running on this thread.
+    static BLOCKED: RefCell<Option<Vec<PathBuf>>> = RefCell::new(None);
+}
+
+/// The result of `Resolver::resolve_sandboxed`.
+#[derive(Debug)]
+pub struct SandboxedResult {
+    pub result: RResult<ResolveResult<Resource>>,
+    /// The paths outside of `roots` which were probed, in the order they
+    /// were probed. The probes served by the cache are not listed again.
+    pub blocked: Vec<PathBuf>,
+}
+
+pub(crate) fn record(path: &Path) {
+    BLOCKED.with(|blocked| {
+        if let Some(blocked) = blocked.borrow_mut().as_mut() {
+            if !blocked.iter().any(|blocked| blocked == path) {
+                blocked.push(path.to_path_buf());
+            }
+        }
+    });
+}
+
+impl Resolver {
+    /// Same as `resolve`, but also returns the probes which were blocked
+    /// because `sandbox` is enabled, so they can be audited.
+    pub fn resolve_sandboxed(&self, path: &Path, request: &str) -> SandboxedResult {
+        let outer = BLOCKED.with(|blocked| blocked.replace(Some(vec![])));
+        let result = self.resolve(path, request);
+        let blocked = BLOCKED
+            .with(|blocked| blocked.replace(outer))
+            .unwrap_or_default();
+        SandboxedResult { result, blocked }
+    }
+
+    pub(crate) fn is_blocked(&self, path: &Path) -> bool {
+        self.options.sandbox && !self.is_in_roots(path)
+    }
+}
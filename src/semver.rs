@@ -0,0 +1,354 @@
+//! The subset of https://github.com/npm/node-semver which is used to select
+//! the installed versions, the build metadata is ignored.
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Identifier {
+    Numeric(u64),
+    Alphanumeric(String),
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Numeric(a), Self::Numeric(b)) => a.cmp(b),
+            (Self::Alphanumeric(a), Self::Alphanumeric(b)) => a.cmp(b),
+            (Self::Numeric(_), Self::Alphanumeric(_)) => Ordering::Less,
+            (Self::Alphanumeric(_), Self::Numeric(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: Vec<Identifier>,
+}
+
+impl Version {
+    pub(crate) fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches(['v', '=']);
+        let version = version
+            .split_once('+')
+            .map_or(version, |(version, _)| version);
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+        let mut numbers = core.split('.').map(|number| number.parse::<u64>().ok());
+        let (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) = (
+            numbers.next(),
+            numbers.next(),
+            numbers.next(),
+            numbers.next(),
+        ) else {
+            return None;
+        };
+        let pre = match pre {
+            Some(pre) => pre
+                .split('.')
+                .map(|identifier| match identifier.parse::<u64>() {
+                    Ok(number) => Some(Identifier::Numeric(number)),
+                    Err(_) if identifier.is_empty() => None,
+                    Err(_) => Some(Identifier::Alphanumeric(identifier.to_string())),
+                })
+                .collect::<Option<_>>()?,
+            None => vec![],
+        };
+        Some(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: vec![],
+        }
+    }
+
+    /// The lowest prerelease of the version, such as `2.0.0-0`, which is
+    /// used as the exclusive upper bound.
+    fn lowest(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            pre: vec![Identifier::Numeric(0)],
+            ..Self::new(major, minor, patch)
+        }
+    }
+
+    fn same_core(&self, other: &Self) -> bool {
+        (self.major, self.minor, self.patch) == (other.major, other.minor, other.patch)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+}
+
+type Comparator = (Op, Version);
+
+/// A version with the omitted or wildcard parts, such as `1.x`.
+struct Partial {
+    major: Option<u64>,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: Vec<Identifier>,
+}
+
+impl Partial {
+    fn parse(version: &str) -> Option<Self> {
+        let version = version.trim_start_matches(['v', '=']);
+        if version.is_empty() {
+            return Some(Self {
+                major: None,
+                minor: None,
+                patch: None,
+                pre: vec![],
+            });
+        }
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        let mut parts = core.split('.').map(|part| match part {
+            "x" | "X" | "*" => Ok(None),
+            _ => part.parse::<u64>().map(Some).map_err(|_| ()),
+        });
+        let mut next = || parts.next().unwrap_or(Ok(None));
+        let (major, minor, patch) = (next().ok()?, next().ok()?, next().ok()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        let pre = match (major, minor, patch) {
+            (Some(_), Some(_), Some(_)) if version.contains('-') => Version::parse(version)?.pre,
+            _ => vec![],
+        };
+        // `1.x.3` is treated as `1.x`.
+        let patch = minor.and(patch);
+        let minor = major.and(minor);
+        Some(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    fn floor(&self) -> Version {
+        Version {
+            pre: self.pre.clone(),
+            ..Version::new(
+                self.major.unwrap_or(0),
+                self.minor.unwrap_or(0),
+                self.patch.unwrap_or(0),
+            )
+        }
+    }
+
+    /// The exclusive upper bound of the omitted parts, `None` for `*`.
+    fn ceil(&self) -> Option<Version> {
+        match (self.major, self.minor, self.patch) {
+            (None, _, _) => None,
+            (Some(major), None, _) => Some(Version::lowest(major + 1, 0, 0)),
+            (Some(major), Some(minor), None) => Some(Version::lowest(major, minor + 1, 0)),
+            (Some(_), Some(_), Some(_)) => None,
+        }
+    }
+}
+
+fn comparators(token: &str, op: &str) -> Option<Vec<Comparator>> {
+    let partial = Partial::parse(token)?;
+    let (major, minor, patch) = (partial.major, partial.minor, partial.patch);
+    let floor = partial.floor();
+    let exact = patch.is_some();
+    Some(match op {
+        "^" => {
+            let ceil = match (major, minor, patch) {
+                (None, _, _) => return Some(vec![]),
+                (Some(major), _, _) if major > 0 => Version::lowest(major + 1, 0, 0),
+                (Some(_), None, _) => Version::lowest(1, 0, 0),
+                (Some(_), Some(minor), _) if minor > 0 => Version::lowest(0, minor + 1, 0),
+                (Some(_), Some(_), None) => Version::lowest(0, 1, 0),
+                (Some(_), Some(_), Some(patch)) => Version::lowest(0, 0, patch + 1),
+            };
+            vec![(Op::Ge, floor), (Op::Lt, ceil)]
+        }
+        "~" => match (major, minor) {
+            (None, _) => vec![],
+            (Some(major), None) => {
+                vec![(Op::Ge, floor), (Op::Lt, Version::lowest(major + 1, 0, 0))]
+            }
+            (Some(major), Some(minor)) => {
+                vec![
+                    (Op::Ge, floor),
+                    (Op::Lt, Version::lowest(major, minor + 1, 0)),
+                ]
+            }
+        },
+        ">" if exact => vec![(Op::Gt, floor)],
+        ">" => match partial.ceil() {
+            Some(ceil) => vec![(
+                Op::Ge,
+                Version {
+                    pre: vec![],
+                    ..ceil
+                },
+            )],
+            // nothing is greater than `*`.
+            None => vec![(Op::Lt, Version::lowest(0, 0, 0))],
+        },
+        ">=" => vec![(Op::Ge, floor)],
+        "<" if exact => vec![(Op::Lt, floor)],
+        "<" => vec![(
+            Op::Lt,
+            Version::lowest(floor.major, floor.minor, floor.patch),
+        )],
+        "<=" if exact => vec![(Op::Le, floor)],
+        "<=" => match partial.ceil() {
+            Some(ceil) => vec![(Op::Lt, ceil)],
+            None => vec![],
+        },
+        "" if exact => vec![(Op::Eq, floor)],
+        "" => match partial.ceil() {
+            Some(ceil) => vec![(Op::Ge, floor), (Op::Lt, ceil)],
+            None => vec![],
+        },
+        _ => return None,
+    })
+}
+
+/// The comparators of a range without `||`, such as `>=1.2 <3`.
+fn comparator_set(range: &str) -> Option<Vec<Comparator>> {
+    if let Some((from, to)) = range.split_once(" - ") {
+        let mut set = comparators(from.trim(), ">=")?;
+        set.extend(comparators(to.trim(), "<=")?);
+        return Some(set);
+    }
+    let mut set = vec![];
+    let mut tokens = range.split_whitespace().peekable();
+    while let Some(token) = tokens.next() {
+        let index = token
+            .find(|c: char| !matches!(c, '<' | '>' | '=' | '^' | '~'))
+            .unwrap_or(token.len());
+        let (op, version) = token.split_at(index);
+        // `>= 1.2` is the same as `>=1.2`.
+        let version = match (version.is_empty(), tokens.peek()) {
+            (true, Some(_)) => tokens.next().unwrap_or_default(),
+            _ => version,
+        };
+        let op = match op {
+            "=" => "",
+            "~>" => "~",
+            op => op,
+        };
+        set.extend(comparators(version, op)?);
+    }
+    Some(set)
+}
+
+fn test_set(set: &[Comparator], version: &Version) -> bool {
+    let passed = set.iter().all(|(op, bound)| match op {
+        Op::Gt => version > bound,
+        Op::Ge => version >= bound,
+        Op::Lt => version < bound,
+        Op::Le => version <= bound,
+        Op::Eq => version == bound,
+    });
+    // the prereleases are only matched by a comparator of the same version, such as
+    // `1.2.3-beta.2` by `>=1.2.3-beta.1`.
+    passed
+        && (version.pre.is_empty()
+            || set
+                .iter()
+                .any(|(_, bound)| !bound.pre.is_empty() && bound.same_core(version)))
+}
+
+/// Whether `version` satisfies `range`, `None` if the range is invalid.
+pub(crate) fn satisfies(version: &Version, range: &str) -> Option<bool> {
+    let mut satisfied = false;
+    for range in range.split("||") {
+        satisfied |= test_set(&comparator_set(range.trim())?, version);
+    }
+    Some(satisfied)
+}
+
+#[test]
+fn satisfies_test() {
+    let check =
+        |version: &str, range: &str| satisfies(&Version::parse(version).unwrap(), range).unwrap();
+    assert!(check("1.2.3", "1.2.3"));
+    assert!(check("1.2.3", "=1.2.3"));
+    assert!(check("1.2.3", ""));
+    assert!(check("1.2.3", "*"));
+    assert!(check("1.2.3", "1.x"));
+    assert!(check("1.2.3", "1"));
+    assert!(!check("2.0.0", "1"));
+    assert!(check("1.9.0", "^1.2.3"));
+    assert!(!check("2.0.0", "^1.2.3"));
+    assert!(!check("1.2.2", "^1.2.3"));
+    assert!(check("0.2.9", "^0.2.3"));
+    assert!(!check("0.3.0", "^0.2.3"));
+    assert!(!check("0.0.4", "^0.0.3"));
+    assert!(check("1.2.9", "~1.2.3"));
+    assert!(!check("1.3.0", "~1.2.3"));
+    assert!(check("1.9.0", "~1"));
+    assert!(check("2.0.0", ">1.2"));
+    assert!(!check("1.2.9", ">1.2"));
+    assert!(check("1.2.4", ">1.2.3"));
+    assert!(check("1.2.0", ">= 1.2"));
+    assert!(check("1.2.9", "<=1.2"));
+    assert!(!check("1.3.0", "<=1.2"));
+    assert!(!check("1.2.0", "<1.2"));
+    assert!(check("1.5.0", ">=1.2 <2"));
+    assert!(!check("2.1.0", ">=1.2 <2"));
+    assert!(check("3.1.0", "^1 || ^3"));
+    assert!(check("2.3.9", "1.2 - 2.3"));
+    assert!(!check("2.4.0", "1.2 - 2.3"));
+    // prereleases
+    assert!(!check("2.0.0-beta.1", "^1.2.3"));
+    assert!(!check("1.5.0-beta.1", "^1.2.3"));
+    assert!(check("1.2.3-beta.2", "^1.2.3-beta.1"));
+    assert!(!check("1.2.3-alpha", "^1.2.3-beta.1"));
+    assert!(check("1.2.3", "^1.2.3-beta.1"));
+    assert!(Version::parse("1.2.3-beta.2") > Version::parse("1.2.3-beta.1"));
+    assert!(Version::parse("1.2.3-beta") > Version::parse("1.2.3-1"));
+    assert!(Version::parse("1.2.3") > Version::parse("1.2.3-rc"));
+    assert!(Version::parse("1.2").is_none());
+    assert!(satisfies(&Version::parse("1.2.3").unwrap(), "^a").is_none());
+    assert!(satisfies(&Version::parse("1.2.3").unwrap(), "=>1").is_none());
+}
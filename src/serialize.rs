@@ -0,0 +1,87 @@
+use crate::{description::DescriptionData, Error};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::{collections::HashSet, path::Path};
+
+/// `HashSet` has no stable order, so it is serialized as a sorted sequence.
+pub(crate) fn sorted<S: Serializer>(
+    set: &HashSet<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut items: Vec<&String> = set.iter().collect();
+    items.sort();
+    serializer.collect_seq(items)
+}
+
+impl Serialize for DescriptionData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DescriptionData", 2)?;
+        state.serialize_field("dir", self.dir().as_ref())?;
+        state.serialize_field("data", self.data().raw().as_ref())?;
+        state.end()
+    }
+}
+
+/// Serialized as `{ "kind": "...", "message": "...", "path": "..." | null }`,
+/// `NotFound` also has `"paths"`, the modules directories which were looked up.
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (kind, message, path): (&str, String, Option<&Path>) = match self {
+            Error::Io(error) => ("Io", error.to_string(), None),
+            Error::UnexpectedJson((path, error)) => {
+                ("UnexpectedJson", error.to_string(), Some(path))
+            }
+            Error::UnexpectedValue(message) => ("UnexpectedValue", message.clone(), None),
+            Error::ResolveFailedTag => ("ResolveFailedTag", String::from("Resolve failed"), None),
+            Error::Overflow => (
+                "Overflow",
+                String::from("Too many nested resolutions"),
+                None,
+            ),
+            Error::CantFindTsConfig(path) => (
+                "CantFindTsConfig",
+                String::from("Can't find tsconfig"),
+                Some(path),
+            ),
+            Error::Cancelled => ("Cancelled", String::from("Resolution was cancelled"), None),
+            Error::Timeout {
+                elapsed,
+                last_probe,
+            } => (
+                "Timeout",
+                format!("Resolution timed out after {elapsed:?}"),
+                Some(last_probe),
+            ),
+            Error::PermissionDenied(path) => (
+                "PermissionDenied",
+                String::from("Permission denied"),
+                Some(path),
+            ),
+            Error::NotExported { request, package } => (
+                "NotExported",
+                format!("Package path {request} is not exported"),
+                Some(package),
+            ),
+            Error::StoppedAt { request, boundary } => (
+                "StoppedAt",
+                format!("Can't resolve '{request}' above the boundary"),
+                Some(boundary),
+            ),
+            Error::NotFound { request, .. } => {
+                ("NotFound", format!("Can't find module '{request}'"), None)
+            }
+        };
+        let paths = match self {
+            Error::NotFound { paths, .. } => Some(paths),
+            _ => None,
+        };
+        let len = 3 + usize::from(paths.is_some());
+        let mut state = serializer.serialize_struct("Error", len)?;
+        state.serialize_field("kind", kind)?;
+        state.serialize_field("message", &message)?;
+        state.serialize_field("path", &path)?;
+        if let Some(paths) = paths {
+            state.serialize_field("paths", paths)?;
+        }
+        state.end()
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{Error, RResult, Resolver, Resource};
+use std::path::{Component, Path};
+
+/// What to do with a result matched by `SuspiciousResult`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SuspiciousAction {
+    /// Keep the result, and report it by `Observer::on_suspicious_result`.
+    Warn,
+    /// Fail to resolve the request.
+    Error,
+}
+
+/// A check of the results which are rarely the modules, and are usually
+/// reached through a misconfigured `exports` or `main`, such as the scripts
+/// in `node_modules/.bin`, see `Options::suspicious_results`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SuspiciousResult {
+    /// The name of the directory which the result is inside, such as
+    /// `.bin`. Only the directories inside the package of the result are
+    /// matched, so the project itself can live in any directory.
+    pub dir: String,
+    pub action: SuspiciousAction,
+}
+
+impl SuspiciousResult {
+    #[must_use]
+    pub fn new(dir: &str, action: SuspiciousAction) -> Self {
+        Self {
+            dir: dir.to_string(),
+            action,
+        }
+    }
+
+    /// Fails the results inside `.bin`, and warns the ones inside
+    /// `dist-types`, which only holds the declarations of TypeScript.
+    #[must_use]
+    pub fn recommended() -> Vec<Self> {
+        vec![
+            Self::new(".bin", SuspiciousAction::Error),
+            Self::new("dist-types", SuspiciousAction::Warn),
+        ]
+    }
+}
+
+impl Resolver {
+    /// Applies `Options::suspicious_results` to `resource`, which is found at
+    /// `real`, the absolute path before `Options::workspace_root` is applied.
+    pub(crate) fn check_suspicious(
+        &self,
+        path: &Path,
+        request: &str,
+        real: &Path,
+        resource: &Resource,
+    ) -> RResult<()> {
+        if self.options.suspicious_results.is_empty() {
+            return Ok(());
+        }
+        // the description is not looked up for the results of the other kinds.
+        let description = match resource.description.as_ref() {
+            Some(description) => Some(description.clone()),
+            None => self.load_entry(real).pkg_info(self)?.clone(),
+        };
+        let inside = description
+            .as_ref()
+            .and_then(|description| real.strip_prefix(description.dir().as_ref()).ok())
+            .unwrap_or(real);
+        let dirs = inside.parent().map_or(vec![], |dirs| {
+            dirs.components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => name.to_str(),
+                    _ => None,
+                })
+                .collect()
+        });
+        for suspicious in &self.options.suspicious_results {
+            if !dirs.contains(&suspicious.dir.as_str()) {
+                continue;
+            }
+            match suspicious.action {
+                SuspiciousAction::Warn => {
+                    if let Some(observer) = self.options.observer.as_ref() {
+                        observer.on_suspicious_result(path, request, resource, &suspicious.dir);
+                    }
+                }
+                SuspiciousAction::Error => {
+                    return Err(Error::UnexpectedValue(format!(
+                        "'{request}' in '{}' is resolved to '{}' inside '{}', which is likely a misconfiguration of the package",
+                        path.display(),
+                        real.display(),
+                        suspicious.dir
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
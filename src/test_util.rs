@@ -0,0 +1,182 @@
+//! Declares the trees of packages in memory, so the resolutions can be
+//! tested without the fixtures on the disk.
+//!
+//! ```rust
+//! use nodejs_resolver::test_util::VirtualTree;
+//! use serde_json::json;
+//!
+//! let tree = VirtualTree::new("/project")
+//!     .file("src/index.js", "")
+//!     .dir("node_modules/a")
+//!     .file("package.json", json!({ "main": "lib.js" }))
+//!     .file("lib.js", "");
+//! let resolver = tree.resolver(Default::default());
+//! assert!(resolver.resolve(&tree.path("src"), "a").is_ok());
+//! ```
+
+use crate::{Cache, FileKind, FileMetadata, FileSystem, Options, Resolver};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A `FileSystem` which serves the files and the directories declared by
+/// `VirtualTree`, there are no symlinks and no modified times.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileSystem {
+    files: BTreeMap<PathBuf, Arc<str>>,
+    dirs: BTreeSet<PathBuf>,
+}
+
+impl MemoryFileSystem {
+    fn add_dir(&mut self, path: &Path) {
+        for dir in path.ancestors() {
+            if !self.dirs.insert(dir.to_path_buf()) {
+                break;
+            }
+        }
+    }
+
+    fn add_file(&mut self, path: PathBuf, content: Arc<str>) {
+        if let Some(parent) = path.parent() {
+            self.add_dir(parent);
+        }
+        self.files.insert(path, content);
+    }
+
+    fn not_found() -> io::Error {
+        io::Error::from(io::ErrorKind::NotFound)
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.read_to_string(path).map(String::into_bytes)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(path)
+            .map(|content| content.to_string())
+            .ok_or_else(Self::not_found)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMetadata> {
+        let kind = if self.files.contains_key(path) {
+            FileKind::File
+        } else if self.dirs.contains(path) {
+            FileKind::Dir
+        } else {
+            return Err(Self::not_found());
+        };
+        Ok(FileMetadata {
+            kind,
+            modified: None,
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.metadata(path)?;
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a symbolic link",
+        ))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.metadata(path).map(|_| path.to_path_buf())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.dirs.contains(path) {
+            return Err(Self::not_found());
+        }
+        let children = self.dirs.iter().chain(self.files.keys());
+        Ok(children
+            .filter(|child| child.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Builds a `MemoryFileSystem`, the paths are relative to the root, and
+/// the files declared after `dir` are in that directory.
+#[derive(Debug, Clone)]
+pub struct VirtualTree {
+    root: PathBuf,
+    /// The directory of the following `file`s.
+    current: PathBuf,
+    fs: MemoryFileSystem,
+}
+
+impl VirtualTree {
+    /// `root` should be absolute, such as `/project`.
+    #[must_use]
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        let root = root.as_ref().to_path_buf();
+        let mut fs = MemoryFileSystem::default();
+        fs.add_dir(&root);
+        Self {
+            current: root.clone(),
+            root,
+            fs,
+        }
+    }
+
+    /// Creates the directory at `path` and its ancestors.
+    #[must_use]
+    pub fn dir<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.current = self.root.join(path);
+        self.fs.add_dir(&self.current);
+        self
+    }
+
+    /// Creates the file at `path` of the current directory, `content` can
+    /// be a string or a `serde_json::Value`.
+    #[must_use]
+    pub fn file<P: AsRef<Path>, C: ToString>(mut self, path: P, content: C) -> Self {
+        let path = self.current.join(path);
+        self.fs.add_file(path, content.to_string().into());
+        self
+    }
+
+    /// The absolute path of `path` in the tree.
+    #[must_use]
+    pub fn path<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.root.join(path)
+    }
+
+    #[must_use]
+    pub fn file_system(&self) -> MemoryFileSystem {
+        self.fs.clone()
+    }
+
+    /// Writes the directories and the files into `dir` on the disk, such as
+    /// comparing the results with Node.js.
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        let local = |path: &Path| dir.join(path.strip_prefix(&self.root).unwrap_or(path));
+        for path in &self.fs.dirs {
+            if path.starts_with(&self.root) {
+                std::fs::create_dir_all(local(path))?;
+            }
+        }
+        for (path, content) in &self.fs.files {
+            std::fs::write(local(path), content.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Creates a resolver which reads the tree, `external_cache` of `options`
+    /// is replaced.
+    #[must_use]
+    pub fn resolver(&self, options: Options) -> Resolver {
+        let cache = Cache::with_file_system(Arc::new(self.file_system()));
+        Resolver::new(Options {
+            external_cache: Some(Arc::new(cache)),
+            ..options
+        })
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{RResult, ResolveResult, Resolver, Resource};
+use std::{
+    fmt::Write as _,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tracing_subscriber::prelude::*;
+
+/// Writes each event to `writer` as soon as it is emitted, one line per event.
+struct StreamLayer<W> {
+    writer: Arc<Mutex<W>>,
+}
+
+impl<S, W> tracing_subscriber::Layer<S> for StreamLayer<W>
+where
+    S: tracing::Subscriber,
+    W: Write + Send + 'static,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _: tracing_subscriber::layer::Context<'_, S>) {
+        let mut line = Line::default();
+        event.record(&mut line);
+        line.0.push('\n');
+        if let Ok(mut writer) = self.writer.lock() {
+            // the trace is best effort, it never fails the resolution.
+            let _ = writer.write_all(line.0.as_bytes());
+        }
+    }
+}
+
+#[derive(Default)]
+struct Line(String);
+
+impl tracing::field::Visit for Line {
+    fn record_debug(&mut self, _field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        if !self.0.is_empty() {
+            self.0.push(' ');
+        }
+        let _ = write!(self.0, "{value:?}");
+    }
+}
+
+impl Resolver {
+    /// Same as `resolve`, but the trace of this call, which is printed by
+    /// `RESOLVER_TRACE=DEBUG` otherwise, is streamed to `writer` while
+    /// resolving, so nothing is buffered for the deep resolutions.
+    ///
+    /// `writer` is returned after the call, the write errors are ignored.
+    pub fn resolve_traced<W>(
+        &self,
+        path: &Path,
+        request: &str,
+        writer: W,
+    ) -> (RResult<ResolveResult<Resource>>, W)
+    where
+        W: Write + Send + 'static,
+    {
+        let writer = Arc::new(Mutex::new(writer));
+        let subscriber = tracing_subscriber::Registry::default().with(StreamLayer {
+            writer: writer.clone(),
+        });
+        let result = tracing::subscriber::with_default(subscriber, || self.resolve(path, request));
+        let writer = match Arc::try_unwrap(writer) {
+            Ok(writer) => writer,
+            Err(_) => unreachable!("the subscriber is dropped after the call"),
+        };
+        let writer = writer
+            .into_inner()
+            .unwrap_or_else(|error| error.into_inner());
+        (result, writer)
+    }
+}
@@ -1,6 +1,7 @@
 // copy from https://github.com/drivasperez/tsconfig
 
 use crate::context::Context;
+use crate::description::parse_jsonc;
 use crate::{Error, Info, RResult, ResolveResult, Resolver, State};
 use rustc_hash::FxHashMap;
 use std::{path::Path, sync::Arc};
@@ -9,21 +10,25 @@ use std::{path::Path, sync::Arc};
 pub struct TsConfig {
     pub extends: Option<String>,
     pub compiler_options: Option<CompilerOptions>,
+    /// The `path` of each item in `references`.
+    pub references: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct CompilerOptions {
     pub base_url: Option<String>,
     pub paths: Option<FxHashMap<String, Vec<String>>>,
+    pub out_dir: Option<String>,
+    pub root_dir: Option<String>,
 }
 
 impl TsConfig {
     pub fn parse(json_str: &str, location: &Path) -> RResult<serde_json::Value> {
-        let serde_value = jsonc_parser::parse_to_serde_value(json_str, &Default::default())
+        let serde_value = parse_jsonc(json_str)
             .map_err(|err| {
                 Error::UnexpectedValue(format!("Parse {} failed. Error: {err}", location.display()))
             })?
-            .unwrap_or_else(|| panic!("Transfer {} to serde value failed", location.display()));
+            .ok_or_else(|| Error::UnexpectedValue(format!("{} is empty", location.display())))?;
         Ok(serde_value)
     }
 }
@@ -55,12 +60,37 @@ impl Resolver {
                 }
                 map
             });
-            CompilerOptions { base_url, paths }
+            let out_dir = options
+                .get("outDir")
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            let root_dir = options
+                .get("rootDir")
+                .and_then(|v| v.as_str())
+                .map(String::from);
+            CompilerOptions {
+                base_url,
+                paths,
+                out_dir,
+                root_dir,
+            }
         });
         let extends: Option<String> = json.get("extends").map(|v| v.to_string());
+        let references = json
+            .get("references")
+            .and_then(|v| v.as_array())
+            .map(|references| {
+                references
+                    .iter()
+                    .filter_map(|reference| reference.get("path")?.as_str())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
         Ok(TsConfig {
             extends,
             compiler_options,
+            references,
         })
     }
 
@@ -97,7 +127,7 @@ impl Resolver {
                     ResolveResult::Resource(info) => {
                         self.parse_file_to_value(&info.to_resolved_path(), context)
                     }
-                    ResolveResult::Ignored => {
+                    ResolveResult::Ignored | ResolveResult::OptionalMissing => {
                         return Err(Error::UnexpectedValue(format!(
                             "{s} had been ignored in {}",
                             location.display()
@@ -1,6 +1,6 @@
 // Copy from https://github.com/dividab/tsconfig-paths
 
-use crate::{context::Context, Info, RResult, Resolver, State};
+use crate::{context::Context, Info, RResult, Resolver, Stage, State, TsconfigPathsOrder};
 use rustc_hash::FxHashMap;
 use std::path::{Path, PathBuf};
 
@@ -8,6 +8,17 @@ use std::path::{Path, PathBuf};
 pub struct TsConfigInfo {
     pub paths: Option<FxHashMap<String, Vec<String>>>,
     pub base_url: Option<String>,
+    pub out_dir: Option<String>,
+    pub root_dir: Option<String>,
+    /// The projects in `references`, their own `references` are not followed.
+    pub references: Vec<ProjectReference>,
+}
+
+#[derive(Debug)]
+pub struct ProjectReference {
+    /// The directory where the referenced tsconfig located.
+    pub dir: PathBuf,
+    pub info: TsConfigInfo,
 }
 
 #[derive(Debug, PartialEq)]
@@ -36,13 +47,38 @@ impl Resolver {
     }
 
     fn parse_tsconfig(&self, location: &Path, context: &mut Context) -> RResult<TsConfigInfo> {
+        let (mut tsconfig, references) = self.parse_project(location, context)?;
+        let location_dir = location.parent().unwrap();
+        for reference in references {
+            // `path` can point to a directory containing `tsconfig.json`, or to the config file itself.
+            let mut reference_location = location_dir.join(reference);
+            if self.load_entry(&reference_location).is_dir() {
+                reference_location.push("tsconfig.json");
+            }
+            let (info, _) = self.parse_project(&reference_location, context)?;
+            let dir = reference_location.parent().unwrap().to_path_buf();
+            tsconfig.references.push(ProjectReference { dir, info });
+        }
+        Ok(tsconfig)
+    }
+
+    fn parse_project(
+        &self,
+        location: &Path,
+        context: &mut Context,
+    ) -> RResult<(TsConfigInfo, Vec<String>)> {
         let tsconfig = self.parse_ts_file(location, context)?;
-        let base_url = tsconfig
-            .compiler_options
-            .as_ref()
-            .and_then(|options| options.base_url.clone());
-        let paths = tsconfig.compiler_options.and_then(|options| options.paths);
-        Ok(TsConfigInfo { paths, base_url })
+        let info = match tsconfig.compiler_options {
+            Some(options) => TsConfigInfo {
+                paths: options.paths,
+                base_url: options.base_url,
+                out_dir: options.out_dir,
+                root_dir: options.root_dir,
+                references: vec![],
+            },
+            None => TsConfigInfo::default(),
+        };
+        Ok((info, tsconfig.references))
     }
 
     fn match_star<'a>(pattern: &'a str, search: &'a str) -> Option<&'a str> {
@@ -88,6 +124,42 @@ impl Resolver {
             .unwrap_or_default()
     }
 
+    fn resolve_with_paths(
+        &self,
+        info: &Info,
+        absolute_base_url: &Path,
+        paths: &Option<FxHashMap<String, Vec<String>>>,
+        context: &mut Context,
+    ) -> Option<State> {
+        let absolute_path_mappings = Resolver::create_match_list(absolute_base_url, paths);
+        let mut matched = false;
+        for entry in absolute_path_mappings {
+            let star_match = if entry.pattern == info.request().target() {
+                ""
+            } else if let Some(s) = Self::match_star(&entry.pattern, info.request().target()) {
+                s
+            } else {
+                continue;
+            };
+            matched = true;
+
+            for physical_path_pattern in &entry.paths {
+                let physical_path = &physical_path_pattern
+                    .display()
+                    .to_string()
+                    .replace('*', star_match);
+                let info = info.clone().with_path(physical_path).with_target("");
+                let result = self._resolve(info, context);
+                context.record_stage(&result, Stage::TsconfigPaths, &entry.pattern);
+                if result.is_finished() {
+                    return Some(result);
+                }
+            }
+        }
+        // `State::Failed` means the request matched, but none of the candidates exist.
+        matched.then(|| State::Failed(info.clone()))
+    }
+
     pub(super) fn _resolve_with_tsconfig(
         &self,
         info: Info,
@@ -99,6 +171,32 @@ impl Resolver {
             Err(error) => return State::Error(error),
         };
         let location_dir = location.parent().unwrap();
+        context.source_dirs = tsconfig
+            .references
+            .iter()
+            .filter_map(|reference| {
+                let out_dir = reference.dir.join(reference.info.out_dir.as_ref()?);
+                let root_dir = match reference.info.root_dir.as_ref() {
+                    Some(root_dir) => reference.dir.join(root_dir),
+                    None => reference.dir.clone(),
+                };
+                Some((out_dir, root_dir))
+            })
+            .collect();
+        let modules_first = matches!(
+            self.options.tsconfig_paths_order,
+            TsconfigPathsOrder::AfterModules
+        );
+        let modules_result = if modules_first {
+            let result = self._resolve(info.clone(), context);
+            if result.is_finished() {
+                return result;
+            }
+            Some(result)
+        } else {
+            None
+        };
+
         let absolute_base_url = if let Some(base_url) = tsconfig.base_url.as_ref() {
             location_dir.join(base_url)
         } else {
@@ -115,31 +213,32 @@ impl Resolver {
             }
         }
 
-        let absolute_path_mappings =
-            Resolver::create_match_list(&absolute_base_url, &tsconfig.paths);
-
-        for entry in absolute_path_mappings {
-            let star_match = if entry.pattern == info.request().target() {
-                ""
-            } else if let Some(s) = Self::match_star(&entry.pattern, info.request().target()) {
-                s
-            } else {
-                continue;
+        // the path mappings of the referenced projects are relative to their own tsconfig.
+        let references = tsconfig.references.iter().map(|reference| {
+            let absolute_base_url = match reference.info.base_url.as_ref() {
+                Some(base_url) => reference.dir.join(base_url),
+                None => reference.dir.clone(),
             };
-
-            for physical_path_pattern in &entry.paths {
-                let physical_path = &physical_path_pattern
-                    .display()
-                    .to_string()
-                    .replace('*', star_match);
-                let info = info.clone().with_path(physical_path).with_target("");
-                let result = self._resolve(info, context);
-                if result.is_finished() {
-                    return result;
-                }
+            (absolute_base_url, &reference.info.paths)
+        });
+        let mut matched = false;
+        for (absolute_base_url, paths) in
+            std::iter::once((absolute_base_url.clone(), &tsconfig.paths)).chain(references)
+        {
+            match self.resolve_with_paths(&info, &absolute_base_url, paths, context) {
+                Some(result) if result.is_finished() => return result,
+                Some(_) => matched = true,
+                None => {}
             }
         }
-        self._resolve(info, context)
+
+        if matched && !self.options.tsconfig_paths_fallthrough {
+            return State::Failed(info);
+        }
+        match modules_result {
+            Some(result) => result,
+            None => self._resolve(info, context),
+        }
     }
 }
 
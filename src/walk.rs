@@ -0,0 +1,92 @@
+use crate::FileSystem;
+use std::path::{Path, PathBuf};
+
+/// The files and the directories which are skipped when the directories are
+/// walked, such as by `Resolver::resolve_context` and the expansion of the
+/// patterns in `Resolver::package_entries`, see `Options::walk_exclude`.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WalkExclude {
+    /// Whether the names starting with `.` are skipped, such as `.git` and
+    /// `.DS_Store`.
+    pub dotfiles: bool,
+    /// The names of the files and the directories, such as `__tests__`.
+    pub names: Vec<String>,
+    /// The patterns matched against the paths relative to the walked
+    /// directory, such as `**/*.test.js`. `*` matches a part of a name,
+    /// `**` matches any directories and `?` matches a character.
+    pub globs: Vec<String>,
+}
+
+impl WalkExclude {
+    /// Skips the dotfiles and `__tests__`, which are rarely the modules.
+    #[must_use]
+    pub fn recommended() -> Self {
+        Self {
+            dotfiles: true,
+            names: vec![String::from("__tests__")],
+            globs: vec![],
+        }
+    }
+
+    /// `relative` is the path relative to the walked directory, such as
+    /// `dir/a.js`, with `/` as the separator.
+    pub(crate) fn is_excluded(&self, relative: &str) -> bool {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        (self.dotfiles && name.starts_with('.'))
+            || self.names.iter().any(|excluded| excluded == name)
+            || self.globs.iter().any(|glob| glob_match(glob, relative))
+    }
+}
+
+/// The real path of `dir` which is about to be walked, `None` if it is one
+/// of `ancestors`, the real paths of the directories being walked, as `dir`
+/// is a symlink to one of them then, and walking it would not end.
+pub(crate) fn enter(fs: &dyn FileSystem, ancestors: &[PathBuf], dir: &Path) -> Option<PathBuf> {
+    let real = fs.canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    (!ancestors.contains(&real)).then_some(real)
+}
+
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let patterns: Vec<&str> = pattern.split('/').collect();
+    let segments: Vec<&str> = path.split('/').collect();
+    match_segments(&patterns, &segments)
+}
+
+fn match_segments(patterns: &[&str], segments: &[&str]) -> bool {
+    match patterns.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => {
+            (0..=segments.len()).any(|skipped| match_segments(rest, &segments[skipped..]))
+        }
+        Some((pattern, rest)) => segments.split_first().map_or(false, |(segment, others)| {
+            match_name(pattern.as_bytes(), segment.as_bytes()) && match_segments(rest, others)
+        }),
+    }
+}
+
+fn match_name(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skipped| match_name(rest, &name[skipped..])),
+        Some((b'?', rest)) => !name.is_empty() && match_name(rest, &name[1..]),
+        Some((byte, rest)) => name.first() == Some(byte) && match_name(rest, &name[1..]),
+    }
+}
+
+#[test]
+fn walk_exclude_test() {
+    let exclude = WalkExclude {
+        globs: vec![String::from("**/*.test.js"), String::from("fixtures/*")],
+        ..WalkExclude::recommended()
+    };
+    assert!(exclude.is_excluded(".git"));
+    assert!(exclude.is_excluded("src/.DS_Store"));
+    assert!(exclude.is_excluded("src/__tests__"));
+    assert!(exclude.is_excluded("a.test.js"));
+    assert!(exclude.is_excluded("src/lib/a.test.js"));
+    assert!(exclude.is_excluded("fixtures/a"));
+    assert!(!exclude.is_excluded("fixtures/a/b.js"));
+    assert!(!exclude.is_excluded("src/a.js"));
+    assert!(!WalkExclude::default().is_excluded(".git"));
+}
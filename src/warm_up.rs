@@ -0,0 +1,97 @@
+use crate::{info::NormalizedPath, RResult, Resolver};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Reported by `Resolver::warm_up` after each package is parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmUpProgress {
+    pub parsed: usize,
+    pub total: usize,
+}
+
+pub type WarmUpCallback<'a> = &'a (dyn Fn(WarmUpProgress) + Sync);
+
+impl Resolver {
+    /// Parses the description files of the packages in `dirs` and the
+    /// absolute paths in `modules` on all cores ahead of resolving, which
+    /// cuts the cold start of the builds with huge dependency trees.
+    /// It is expected to be called right after the resolver is created.
+    ///
+    /// Returns the number of the packages which were parsed, the malformed
+    /// description files are skipped here and reported when they are used.
+    pub fn warm_up(&self, dirs: &[PathBuf], on_progress: Option<WarmUpCallback>) -> RResult<usize> {
+        let stores = self
+            .options
+            .modules
+            .iter()
+            .map(Path::new)
+            .filter(|path| path.is_absolute());
+        let mut packages = vec![];
+        for dir in stores.chain(dirs.iter().map(PathBuf::as_path)) {
+            self.collect_packages(NormalizedPath::new(dir).as_ref(), &mut packages)?;
+        }
+
+        let total = packages.len();
+        let parsed = AtomicUsize::new(0);
+        let succeeded = AtomicUsize::new(0);
+        let threads = std::thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(total.max(1));
+        let chunk_size = total.div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            for chunk in packages.chunks(chunk_size) {
+                let (parsed, succeeded) = (&parsed, &succeeded);
+                scope.spawn(move || {
+                    for package in chunk {
+                        let pkg_info = self.load_entry(package).pkg_info(self).ok().cloned();
+                        // the description file may be found in the ancestors.
+                        let is_package = pkg_info
+                            .flatten()
+                            .map_or(false, |pkg_info| pkg_info.dir().as_ref() == package);
+                        if is_package {
+                            succeeded.fetch_add(1, Ordering::Relaxed);
+                        }
+                        let parsed = parsed.fetch_add(1, Ordering::Relaxed) + 1;
+                        if let Some(on_progress) = on_progress {
+                            on_progress(WarmUpProgress { parsed, total });
+                        }
+                    }
+                });
+            }
+        });
+        Ok(succeeded.into_inner())
+    }
+
+    /// The packages are the children of `dir`, and the children of the scopes.
+    fn collect_packages(&self, dir: &Path, packages: &mut Vec<PathBuf>) -> RResult<()> {
+        if self.is_blocked(dir) {
+            return Ok(());
+        }
+        let children = match self.cache.fs.file_system().read_dir(dir) {
+            Ok(children) => children,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+        // the kinds of the children are loaded in a batch.
+        let stats = self.cache.fs.file_system().metadata_batch(&children);
+        for (child, stat) in children.into_iter().zip(stats) {
+            let entry = self.load_entry(&child);
+            entry.init_stat(&stat);
+            if !entry.is_dir() {
+                continue;
+            }
+            let is_scope = child
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.starts_with('@'));
+            if is_scope {
+                self.collect_packages(&child, packages)?;
+            } else {
+                packages.push(child);
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,14 @@
 use nodejs_resolver::test_helper::{p, vec_to_set};
 use nodejs_resolver::{
-    AliasMap, Cache, EnforceExtension, Error, Options, ResolveResult, Resolver, Resource,
+    AfterResolve, AfterResolveAction, Algorithm, AliasMap, BeforeResolve, Cache, CancelToken,
+    ConditionSensitivity, ContextOptions, DescriptionRetention, DirSnapshotFileSystem, DotRequests,
+    EnforceExtension, Error, ExportsField, ExtensionConflictPolicy, Field, FileSystem,
+    ImportsField, IoErrorClass, JsFormat, JsrProtocol, LoaderPrefix, LoaderSyntax, Lookup,
+    MalformedDescription, MappingEntry, ModuleType, ModulesPath, NestedMainFields, NpmProtocol,
+    Observer, Options, OptionsDelta, OsFileSystem, PackageInstance, PathStyle, PkgJSON, ProbeOrder,
+    ProtocolHandler, ProtocolResolution, Provenance, RawRequest, ResolveResult, Resolver,
+    ResolverCreated, ResolverFactory, ResolverPool, Resource, ResultKind, Stage, StructuredRequest,
+    SuspiciousAction, SuspiciousResult, Symlink, WalkExclude, WarmUpProgress, ZipFileSystem,
 };
 use path_absolutize::Absolutize;
 use std::path::{Path, PathBuf};
@@ -15,6 +23,7 @@ fn should_equal(resolver: &Resolver, path: &Path, request: &str, expected: PathB
             assert_eq!(actual, expected);
         }
         Ok(ResolveResult::Ignored) => panic!("should not ignored"),
+        Ok(ResolveResult::OptionalMissing) => panic!("should not be missing"),
         Err(error) => panic!("{error:?}"),
     }
 }
@@ -28,7 +37,10 @@ fn should_ignored(resolver: &Resolver, path: &Path, request: &str) {
 
 fn should_failed(resolver: &Resolver, path: &Path, request: &str) {
     let result = resolver.resolve(path, request);
-    if !matches!(result, Err(Error::ResolveFailedTag)) {
+    if !matches!(
+        result,
+        Err(Error::ResolveFailedTag | Error::NotFound { .. })
+    ) {
         println!("{result:?}");
         panic!("should failed");
     }
@@ -73,7 +85,8 @@ fn should_unexpected_value_error(
 ) {
     match resolver.resolve(path, request) {
         Err(err) => match err {
-            Error::UnexpectedValue(err) => {
+            Error::UnexpectedValue(_) | Error::NotExported { .. } => {
+                let err = err.to_string();
                 if err.contains(&expected_err_msg) {
                 } else {
                     assert_eq!(err, expected_err_msg);
@@ -2253,6 +2266,12 @@ fn exports_field_test() {
         "Trying to access out of package scope. Requesting ./a/../b/../../pack1/index.js"
             .to_string(),
     );
+    should_unexpected_value_error(
+        &resolver,
+        &p(vec!["exports-field-error"]),
+        "exports-field",
+        "Invalid package target \"./a/../b/../../pack1/index.js\" for \".\"".to_string(),
+    );
 
     let resolver = Resolver::new(Options {
         extensions: vec![String::from(".js")],
@@ -2770,7 +2789,7 @@ fn exports_filed_test_5() {
         &resolver,
         &export_cases_path5,
         "pkgexports/sub/./../asdf.js",
-        "Trying to access out of package scope. Requesting ././../asd".to_string(),
+        "Target should not contain empty or \".\" segments. Requesting ././../asdf.js".to_string(),
     );
     // FIXME:
     // should_unexpected_value_error(
@@ -3074,6 +3093,49 @@ fn main_fields_test() {
 }
 
 #[test]
+fn legacy_main_fields_test() {
+    let case_path = p(vec!["legacy-main-fields"]);
+    let resolver = Resolver::new(Options {
+        main_fields: vec![
+            "module".to_string(),
+            "jsnext:main".to_string(),
+            "es2015".to_string(),
+            "main".to_string(),
+        ],
+        ..Default::default()
+    });
+    let resolve = |request: &str| match resolver.resolve(&case_path, request) {
+        Ok(ResolveResult::Resource(resource)) => resource,
+        other => panic!("{other:?}"),
+    };
+
+    let resource = resolve("./jsnext");
+    assert_eq!(
+        resource.path,
+        p(vec!["legacy-main-fields", "jsnext", "es.js"])
+    );
+    assert_eq!(resource.main_field.as_deref(), Some("jsnext:main"));
+    assert_eq!(resource.legacy_main_field(), Some("jsnext:main"));
+
+    // `main` exists, so it is not the only entry.
+    let resource = resolve("./es2015");
+    assert_eq!(
+        resource.path,
+        p(vec!["legacy-main-fields", "es2015", "es2015.js"])
+    );
+    assert_eq!(resource.main_field.as_deref(), Some("es2015"));
+    assert_eq!(resource.legacy_main_field(), None);
+
+    let resource = resolve("./plain");
+    assert_eq!(resource.main_field.as_deref(), Some("main"));
+    assert_eq!(resource.legacy_main_field(), None);
+
+    let resource = resolve("./plain/main.js");
+    assert_eq!(resource.main_field, None);
+}
+
+#[test]
+#[cfg(feature = "tsconfig")]
 fn tsconfig_paths_test() {
     let tsconfig_path = p(vec!["tsconfig-paths"]);
     let resolver = Resolver::new(Options {
@@ -3236,6 +3298,7 @@ fn tsconfig_paths_test() {
 }
 
 #[test]
+#[cfg(feature = "tsconfig")]
 fn tsconfig_paths_nested() {
     let tsconfig_path = p(vec!["tsconfig-paths-nested"]);
     let resolver = Resolver::new(Options {
@@ -3311,6 +3374,7 @@ fn tsconfig_paths_nested() {
 }
 
 #[test]
+#[cfg(feature = "tsconfig")]
 fn tsconfig_paths_without_base_url_test() {
     let case_path = p(vec!["tsconfig-paths-without-baseURL"]);
     let resolver = Resolver::new(Options {
@@ -3329,6 +3393,7 @@ fn tsconfig_paths_without_base_url_test() {
 }
 
 #[test]
+#[cfg(feature = "tsconfig")]
 fn tsconfig_paths_overridden_base_url() {
     let case_path = p(vec!["tsconfig-paths-override-baseURL"]);
     let resolver = Resolver::new(Options {
@@ -3345,6 +3410,7 @@ fn tsconfig_paths_overridden_base_url() {
 }
 
 #[test]
+#[cfg(feature = "tsconfig")]
 fn tsconfig_paths_missing_base_url() {
     let case_path = p(vec!["tsconfig-paths-missing-baseURL"]);
     let resolver = Resolver::new(Options {
@@ -3356,6 +3422,7 @@ fn tsconfig_paths_missing_base_url() {
 }
 
 #[test]
+#[cfg(feature = "tsconfig")]
 fn tsconfig_paths_extends_from_node_modules() {
     let case_path = p(vec!["tsconfig-paths-extends-from-module"]);
     let resolver = Resolver::new(Options {
@@ -3384,6 +3451,143 @@ fn tsconfig_paths_extends_from_node_modules() {
 }
 
 #[test]
+#[cfg(feature = "tsconfig")]
+fn tsconfig_references_test() {
+    let case_path = p(vec!["tsconfig-references"]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![".ts".to_string(), ".js".to_string()],
+        tsconfig: Some(case_path.join("tsconfig.json")),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "@a/util",
+        p(vec![
+            "tsconfig-references",
+            "packages",
+            "a",
+            "src",
+            "util.ts",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "@b",
+        p(vec![
+            "tsconfig-references",
+            "packages",
+            "b",
+            "src",
+            "main.ts",
+        ]),
+    );
+    // `outDir` is mapped to `rootDir`, even if it had not been built.
+    should_equal(
+        &resolver,
+        &case_path,
+        "./packages/a",
+        p(vec![
+            "tsconfig-references",
+            "packages",
+            "a",
+            "src",
+            "index.ts",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path.join("packages").join("b"),
+        "../a/dist/util.js",
+        p(vec![
+            "tsconfig-references",
+            "packages",
+            "a",
+            "src",
+            "util.ts",
+        ]),
+    );
+    should_failed(&resolver, &case_path, "./packages/a/dist/inexist.js");
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![".ts".to_string(), ".js".to_string()],
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "@a/util");
+    should_failed(&resolver, &case_path, "./packages/a");
+}
+
+#[test]
+#[cfg(feature = "tsconfig")]
+fn tsconfig_paths_order_test() {
+    use nodejs_resolver::TsconfigPathsOrder;
+
+    let case_path = p(vec!["tsconfig-paths-order"]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![".ts".to_string(), ".js".to_string()],
+        tsconfig: Some(case_path.join("tsconfig.json")),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "foo",
+        p(vec!["tsconfig-paths-order", "src", "foo.ts"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "missing",
+        p(vec![
+            "tsconfig-paths-order",
+            "node_modules",
+            "missing",
+            "index.js",
+        ]),
+    );
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![".ts".to_string(), ".js".to_string()],
+        tsconfig: Some(case_path.join("tsconfig.json")),
+        tsconfig_paths_order: TsconfigPathsOrder::AfterModules,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "foo",
+        p(vec![
+            "tsconfig-paths-order",
+            "node_modules",
+            "foo",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "bar",
+        p(vec!["tsconfig-paths-order", "src", "bar.ts"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![".ts".to_string(), ".js".to_string()],
+        tsconfig: Some(case_path.join("tsconfig.json")),
+        tsconfig_paths_fallthrough: false,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "foo",
+        p(vec!["tsconfig-paths-order", "src", "foo.ts"]),
+    );
+    should_failed(&resolver, &case_path, "missing");
+}
+
+#[test]
+#[cfg(feature = "tsconfig")]
 fn tsconfig_inexist() {
     let resolver = Resolver::new(Options {
         extensions: vec![".ts".to_string()],
@@ -3729,6 +3933,65 @@ fn resolve_modules_test() {
     should_failed(&resolver, &p(vec![]), "recursive-module");
 }
 
+#[test]
+fn resolve_modules_store_test() {
+    let store = p(vec!["modules-store", "store"]).display().to_string();
+    let issuer = p(vec!["modules-store", "project", "src"]);
+    let resolver = Resolver::new(Options {
+        modules: vec!["node_modules".to_string(), store.clone()],
+        modules_scope: vec![(store.clone(), vec!["@corp/*".to_string()])],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &issuer,
+        "@corp/ui",
+        p(vec!["modules-store", "store", "@corp", "ui", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &issuer,
+        "lodash",
+        p(vec![
+            "modules-store",
+            "project",
+            "node_modules",
+            "lodash",
+            "index.js",
+        ]),
+    );
+
+    // the order of `modules` is the precedence.
+    let resolver = Resolver::new(Options {
+        modules: vec![store.clone(), "node_modules".to_string()],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &issuer,
+        "lodash",
+        p(vec!["modules-store", "store", "lodash", "index.js"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        modules: vec![store.clone(), "node_modules".to_string()],
+        modules_scope: vec![(store, vec!["@corp/*".to_string()])],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &issuer,
+        "lodash",
+        p(vec![
+            "modules-store",
+            "project",
+            "node_modules",
+            "lodash",
+            "index.js",
+        ]),
+    );
+}
+
 #[test]
 fn extension_alias() {
     let resolver = Resolver::new(Options {
@@ -3861,3 +4124,4208 @@ fn extension_alias2() {
         p(vec!["extension-alias", "dir2", "index.js"]),
     );
 }
+
+#[test]
+fn duplicate_report_test() {
+    let fixture = p(vec!["duplicate-packages"]);
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &fixture,
+        "lib",
+        p(vec![
+            "duplicate-packages",
+            "node_modules",
+            "lib",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &fixture,
+        "consumer",
+        p(vec![
+            "duplicate-packages",
+            "node_modules",
+            "consumer",
+            "index.js",
+        ]),
+    );
+    assert!(resolver.duplicate_report().is_empty());
+
+    let consumer_dir = p(vec!["duplicate-packages", "node_modules", "consumer"]);
+    should_equal(
+        &resolver,
+        &consumer_dir,
+        "lib",
+        p(vec![
+            "duplicate-packages",
+            "node_modules",
+            "consumer",
+            "node_modules",
+            "lib",
+            "index.js",
+        ]),
+    );
+    let report = resolver.duplicate_report();
+    assert_eq!(report.len(), 1);
+    assert_eq!(report[0].name, "lib");
+    assert_eq!(
+        report[0].instances,
+        vec![
+            PackageInstance {
+                version: Some("1.0.0".to_string()),
+                path: p(vec![
+                    "duplicate-packages",
+                    "node_modules",
+                    "consumer",
+                    "node_modules",
+                    "lib"
+                ]),
+            },
+            PackageInstance {
+                version: Some("2.0.0".to_string()),
+                path: p(vec!["duplicate-packages", "node_modules", "lib"]),
+            },
+        ]
+    );
+
+    resolver.clear_duplicate_report();
+    assert!(resolver.duplicate_report().is_empty());
+}
+
+#[test]
+fn observer_test() {
+    #[derive(Debug, Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+
+    impl Observer for Recorder {
+        fn on_resolve_start(&self, _path: &Path, request: &str) {
+            self.0.lock().unwrap().push(format!("start {request}"));
+        }
+
+        fn on_candidate(&self, path: &Path) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("candidate {}", path.display()));
+        }
+
+        fn on_success(&self, _path: &Path, request: &str, _result: &ResolveResult<Resource>) {
+            self.0.lock().unwrap().push(format!("success {request}"));
+        }
+
+        fn on_failure(&self, _path: &Path, request: &str, _error: &Error) {
+            self.0.lock().unwrap().push(format!("failure {request}"));
+        }
+    }
+
+    let recorder = Arc::new(Recorder::default());
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        observer: Some(recorder.clone()),
+        ..Default::default()
+    });
+    let fixture = p(vec!["full", "a"]);
+    should_equal(&resolver, &fixture, "./abc", p(vec!["full", "a", "abc.js"]));
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        vec![
+            "start ./abc".to_string(),
+            format!("candidate {}", p(vec!["full", "a", "abc"]).display()),
+            format!("candidate {}", p(vec!["full", "a", "abc.js"]).display()),
+            "success ./abc".to_string(),
+        ]
+    );
+
+    recorder.0.lock().unwrap().clear();
+    should_failed(&resolver, &fixture, "./not-exist");
+    let events = recorder.0.lock().unwrap();
+    assert_eq!(events.first().unwrap(), "start ./not-exist");
+    assert_eq!(events.last().unwrap(), "failure ./not-exist");
+}
+
+#[test]
+fn malformed_description_test() {
+    let case_path = p(vec!["incorrect-package", "pack1"]);
+    let resolver = Resolver::new(Options {
+        on_malformed_description: MalformedDescription::Ignore,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./a.js",
+        p(vec!["incorrect-package", "pack1", "a.js"]),
+    );
+
+    let warnings = Arc::new(std::sync::Mutex::new(vec![]));
+    let collected = warnings.clone();
+    let resolver = Resolver::new(Options {
+        on_malformed_description: MalformedDescription::Warn(Arc::new(move |path, error| {
+            assert!(matches!(error, Error::UnexpectedJson(_)));
+            collected.lock().unwrap().push(path.to_path_buf());
+        })),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./a.js",
+        p(vec!["incorrect-package", "pack1", "a.js"]),
+    );
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        vec![p(vec!["incorrect-package", "pack1", "package.json"])]
+    );
+
+    let resolver = Resolver::new(Options::default());
+    should_unexpected_json_error(
+        &resolver,
+        &case_path,
+        "./a.js",
+        p(vec!["incorrect-package", "pack1", "package.json"]),
+    );
+}
+
+#[test]
+fn jsonc_description_test() {
+    let case_path = p(vec!["jsonc-description"]);
+    let resolver = Resolver::new(Options::default());
+    #[cfg(feature = "jsonc-description")]
+    should_equal(
+        &resolver,
+        &case_path,
+        ".",
+        p(vec!["jsonc-description", "lib.js"]),
+    );
+    #[cfg(not(feature = "jsonc-description"))]
+    should_unexpected_json_error(
+        &resolver,
+        &case_path,
+        ".",
+        p(vec!["jsonc-description", "package.json"]),
+    );
+}
+
+#[test]
+fn unicode_request_test() {
+    let case_path = p(vec!["unicode"]);
+    let resolver = Resolver::new(Options::default());
+    should_equal(&resolver, &case_path, "./😀", p(vec!["unicode", "😀.js"]));
+    should_equal(
+        &resolver,
+        &case_path,
+        "中文包",
+        p(vec!["unicode", "node_modules", "中文包", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "中文包/子目录/文件",
+        p(vec![
+            "unicode",
+            "node_modules",
+            "中文包",
+            "子目录",
+            "文件.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "@スコープ/😀",
+        p(vec![
+            "unicode",
+            "node_modules",
+            "@スコープ",
+            "😀",
+            "lib",
+            "文件.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "@スコープ/😀/lib/文件",
+        p(vec![
+            "unicode",
+            "node_modules",
+            "@スコープ",
+            "😀",
+            "lib",
+            "文件.js",
+        ]),
+    );
+    should_failed(&resolver, &case_path, "@スコープ/😀/文件");
+}
+
+#[test]
+fn open_document_test() {
+    let case_path = p(vec!["open-document"]);
+    let resolver = Resolver::new(Options::default());
+    should_equal(&resolver, &case_path, ".", p(vec!["open-document", "a.js"]));
+
+    let pkg_path = case_path.join("package.json");
+    resolver.open_document(&pkg_path, r#"{ "main": "./b.js" }"#);
+    should_equal(&resolver, &case_path, ".", p(vec!["open-document", "b.js"]));
+
+    // the unsaved file only exists in editor.
+    should_failed(&resolver, &case_path, "./c");
+    resolver.open_document(case_path.join("c.js"), "");
+    should_equal(
+        &resolver,
+        &case_path,
+        "./c",
+        p(vec!["open-document", "c.js"]),
+    );
+
+    assert!(resolver.close_document(case_path.join("c.js")));
+    should_failed(&resolver, &case_path, "./c");
+    assert!(resolver.close_document(&pkg_path));
+    assert!(!resolver.close_document(&pkg_path));
+    should_equal(&resolver, &case_path, ".", p(vec!["open-document", "a.js"]));
+}
+
+#[test]
+fn issuer_browser_field_test() {
+    let issuer = p(vec!["issuer-browser", "node_modules", "issuer"]);
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        ..Default::default()
+    });
+    // `issuer/lodash/package.json` should not hide the `browser` field of issuer.
+    should_equal(
+        &resolver,
+        &issuer,
+        "lodash",
+        p(vec![
+            "issuer-browser",
+            "node_modules",
+            "lodash-es",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &issuer.join("lib"),
+        "lodash",
+        p(vec![
+            "issuer-browser",
+            "node_modules",
+            "lodash-es",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &p(vec!["issuer-browser"]),
+        "lodash",
+        p(vec!["issuer-browser", "node_modules", "lodash", "index.js"]),
+    );
+
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &issuer,
+        "lodash",
+        p(vec!["issuer-browser", "node_modules", "lodash", "index.js"]),
+    );
+}
+
+#[test]
+fn entry_test() {
+    let resolver = Resolver::new(Options::default());
+    let entry = resolver.entry(p(vec!["full", "a", "node_modules", "package2", "a.js"]));
+    assert!(entry.is_file());
+    assert!(!entry.is_dir());
+    assert!(entry.exists());
+    assert!(entry.symlink_target().is_none());
+    assert_eq!(
+        entry.pkg_info().unwrap().unwrap().dir().as_ref(),
+        p(vec!["full", "a", "node_modules", "package2"])
+    );
+    let parent = entry.parent().unwrap();
+    assert!(parent.is_dir());
+    assert_eq!(
+        parent.path(),
+        p(vec!["full", "a", "node_modules", "package2"])
+    );
+
+    let entry = resolver.entry(p(vec!["full", "a", "dir", "..", "not-exist.js"]));
+    assert_eq!(entry.path(), p(vec!["full", "a", "not-exist.js"]));
+    assert!(!entry.exists());
+    assert!(entry.modified().is_none());
+
+    let entry = resolver.entry(p(vec!["symlink", "linked", "index.js"]));
+    assert_eq!(
+        entry.symlink_target(),
+        Some(p(vec!["symlink", "lib", "index.js"]).as_path())
+    );
+
+    let entry = resolver.entry(p(vec!["incorrect-package", "pack1", "a.js"]));
+    assert!(matches!(entry.pkg_info(), Err(Error::UnexpectedJson(_))));
+}
+
+#[test]
+fn package_entries_test() {
+    let case_path = p(vec!["package-entries"]);
+    let resolver = Resolver::new(Default::default());
+    let entries = |dir: &str, conditions: Vec<&str>| -> Vec<(String, PathBuf)> {
+        let conditions = conditions.into_iter().map(String::from).collect();
+        resolver
+            .package_entries(&case_path.join(dir), &conditions)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.subpath, entry.path))
+            .collect()
+    };
+    let dist = |names: Vec<&str>| {
+        let mut paths = vec!["package-entries", "with-exports", "dist"];
+        paths.extend(names);
+        p(paths)
+    };
+
+    assert_eq!(
+        entries("with-exports", vec![]),
+        vec![
+            (".".to_string(), dist(vec!["index.js"])),
+            ("./features/a".to_string(), dist(vec!["features", "a.js"])),
+            (
+                "./features/nested/b".to_string(),
+                dist(vec!["features", "nested", "b.js"])
+            ),
+            (
+                "./package.json".to_string(),
+                p(vec!["package-entries", "with-exports", "package.json"])
+            ),
+        ]
+    );
+    assert_eq!(
+        entries("with-exports", vec!["import", "node"]),
+        vec![
+            (".".to_string(), dist(vec!["index.mjs"])),
+            ("./features/a".to_string(), dist(vec!["features", "a.js"])),
+            (
+                "./features/nested/b".to_string(),
+                dist(vec!["features", "nested", "b.js"])
+            ),
+            (
+                "./package.json".to_string(),
+                p(vec!["package-entries", "with-exports", "package.json"])
+            ),
+            ("./utils/x.js".to_string(), dist(vec!["utils", "x.js"])),
+        ]
+    );
+    assert_eq!(
+        entries("sugar", vec![]),
+        vec![(
+            ".".to_string(),
+            p(vec!["package-entries", "sugar", "main.js"])
+        )]
+    );
+    assert_eq!(
+        entries("no-exports", vec![]),
+        vec![(
+            ".".to_string(),
+            p(vec!["package-entries", "no-exports", "lib.js"])
+        )]
+    );
+    // `nested/loop` and `nested/again` link to the directories being walked.
+    let features = |names: Vec<&str>| {
+        let mut paths = vec!["package-entries", "with-loop", "dist", "features"];
+        paths.extend(names);
+        p(paths)
+    };
+    assert_eq!(
+        entries("with-loop", vec![]),
+        vec![
+            ("./features/a".to_string(), features(vec!["a.js"])),
+            (
+                "./features/nested/b".to_string(),
+                features(vec!["nested", "b.js"])
+            ),
+        ]
+    );
+    assert!(matches!(
+        resolver.package_entries(&case_path, &Default::default()),
+        Err(Error::UnexpectedValue(_))
+    ));
+}
+
+#[test]
+fn reverse_resolve_test() {
+    let case_path = p(vec!["package-entries"]);
+    let resolver = Resolver::new(Default::default());
+    let pkg_dir = case_path.join("with-exports");
+    let reverse = |file: PathBuf, conditions: Vec<&str>| {
+        let conditions = conditions.into_iter().map(String::from).collect();
+        resolver
+            .reverse_resolve(&pkg_dir, &file, &conditions)
+            .unwrap()
+    };
+
+    assert_eq!(
+        reverse(pkg_dir.join("dist").join("index.js"), vec![]),
+        vec!["with-exports"]
+    );
+    assert_eq!(
+        reverse(pkg_dir.join("dist").join("index.js"), vec!["import"]),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        reverse(
+            pkg_dir
+                .join("dist")
+                .join("features")
+                .join("nested")
+                .join("b.js"),
+            vec![]
+        ),
+        vec!["with-exports/features/nested/b"]
+    );
+    // excluded by `null`.
+    assert_eq!(
+        reverse(
+            pkg_dir
+                .join("dist")
+                .join("features")
+                .join("internal")
+                .join("c.js"),
+            vec![]
+        ),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        reverse(case_path.join("sugar").join("main.js"), vec![]),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        resolver
+            .reverse_resolve(
+                &case_path.join("sugar"),
+                &case_path.join("sugar").join("main.js"),
+                &Default::default()
+            )
+            .unwrap(),
+        vec!["sugar"]
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_test() {
+    let fixture_path = p(vec![]);
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_set(vec!["require", "node", "import"]),
+        ..Default::default()
+    });
+    let result = resolver
+        .resolve(&fixture_path, "./legacy-main-fields/plain")
+        .unwrap();
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(
+        json["Resource"]["path"],
+        serde_json::json!(p(vec!["legacy-main-fields", "plain", "main.js"]))
+    );
+    assert_eq!(json["Resource"]["query"], serde_json::Value::Null);
+    assert_eq!(
+        json["Resource"]["description"]["dir"],
+        serde_json::json!(p(vec!["legacy-main-fields", "plain"]))
+    );
+    assert_eq!(json["Resource"]["description"]["data"]["name"], "plain");
+    assert_eq!(json["Resource"]["main_field"], "main");
+
+    let error = resolver.resolve(&fixture_path, "./inexist").unwrap_err();
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        serde_json::json!({
+            "kind": "ResolveFailedTag",
+            "message": "Resolve failed",
+            "path": null,
+        })
+    );
+    let error = resolver.resolve(&fixture_path, "inexist").unwrap_err();
+    let json = serde_json::to_value(&error).unwrap();
+    assert_eq!(json["kind"], "NotFound");
+    assert_eq!(
+        json["paths"][0],
+        serde_json::json!({ "path": p(vec!["node_modules"]), "exists": true })
+    );
+    let error = Error::StoppedAt {
+        request: String::from("a"),
+        boundary: fixture_path.clone().into(),
+    };
+    let json = serde_json::to_value(&error).unwrap();
+    assert_eq!(json["kind"], "StoppedAt");
+    assert_eq!(json["path"], serde_json::json!(fixture_path));
+
+    let options = serde_json::to_value(&resolver.options).unwrap();
+    assert_eq!(
+        options["condition_names"],
+        serde_json::json!(["import", "node", "require"])
+    );
+    assert_eq!(options["enforce_extension"], serde_json::json!("Disabled"));
+    assert!(options.get("observer").is_none());
+}
+
+#[test]
+fn metrics_test() {
+    let fixture_path = p(vec![]);
+    let resolver = Resolver::new(Default::default());
+    let metrics = resolver.metrics();
+    assert_eq!(metrics.snapshot().resolutions, 0);
+    assert_eq!(metrics.cache_hit_rate(), 0.0);
+    assert_eq!(metrics.average_duration(), std::time::Duration::ZERO);
+
+    should_equal(&resolver, &fixture_path, "./a", p(vec!["a.js"]));
+    should_equal(&resolver, &fixture_path, "./a", p(vec!["a.js"]));
+    should_failed(&resolver, &fixture_path, "./inexist");
+
+    let snapshot = std::thread::spawn(move || metrics.snapshot())
+        .join()
+        .unwrap();
+    assert_eq!(snapshot.resolutions, 3);
+    assert_eq!(snapshot.failures, 1);
+    assert!(snapshot.cache_hits > 0);
+    assert!(snapshot.cache_misses > 0);
+    let metrics = resolver.metrics();
+    assert!(metrics.cache_hit_rate() > 0.0 && metrics.cache_hit_rate() < 1.0);
+    assert!(metrics.average_duration() > std::time::Duration::ZERO);
+
+    metrics.reset();
+    assert_eq!(resolver.metrics().snapshot().resolutions, 0);
+}
+
+#[test]
+fn zip_archive_test() {
+    let archive = p(vec!["zip-archive", "cache", "pkg.zip"]);
+    let file_system = Arc::new(ZipFileSystem::new(Arc::new(OsFileSystem)));
+    let resolver = Resolver::new(Options {
+        external_cache: Some(Arc::new(Cache::with_file_system(file_system.clone()))),
+        ..Default::default()
+    });
+    let pkg_dir = archive.join("node_modules").join("pkg");
+    should_equal(
+        &resolver,
+        &pkg_dir,
+        ".",
+        pkg_dir.join("lib").join("index.js"),
+    );
+    should_equal(
+        &resolver,
+        &pkg_dir.join("lib"),
+        "pkg",
+        pkg_dir.join("lib").join("index.js"),
+    );
+    should_equal(
+        &resolver,
+        &pkg_dir.join("lib"),
+        "./util",
+        pkg_dir.join("lib").join("util.js"),
+    );
+    should_failed(&resolver, &pkg_dir, "./inexist");
+
+    assert_eq!(
+        file_system
+            .read_to_string(&pkg_dir.join("lib").join("index.js"))
+            .unwrap(),
+        "module.exports = \"pkg\";\n"
+    );
+    assert_eq!(
+        file_system.read_dir(&pkg_dir).unwrap(),
+        vec![
+            pkg_dir.join("deflated.json"),
+            pkg_dir.join("lib"),
+            pkg_dir.join("package.json")
+        ]
+    );
+    let error = file_system
+        .read(&pkg_dir.join("deflated.json"))
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn before_resolve_test() {
+    #[derive(Debug)]
+    struct StripPrefix;
+
+    impl BeforeResolve for StripPrefix {
+        fn before_resolve(&self, raw: &mut RawRequest) {
+            if let Some(index) = raw.request.rfind('!') {
+                raw.request = raw.request[index + 1..].to_string();
+            }
+            if let Some(request) = raw.request.strip_prefix("virtual:") {
+                raw.request = request.to_string();
+                raw.path = raw.path.join("alias");
+            }
+        }
+    }
+
+    let fixture_path = p(vec![]);
+    let resolver = Resolver::new(Options {
+        before_resolve: Some(Arc::new(StripPrefix)),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "-!babel-loader!./a.js?q",
+        p(vec!["a.js?q"]),
+    );
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "virtual:./a",
+        p(vec!["alias", "a", "index"]),
+    );
+    should_failed(
+        &Resolver::new(Default::default()),
+        &fixture_path,
+        "virtual:./a",
+    );
+}
+
+#[test]
+fn after_resolve_test() {
+    #[derive(Debug)]
+    struct PreferCjs;
+
+    impl AfterResolve for PreferCjs {
+        fn after_resolve(&self, _request: &RawRequest, resolved: &Path) -> AfterResolveAction {
+            let path = resolved.display().to_string();
+            let esm = format!("dist{}esm", std::path::MAIN_SEPARATOR);
+            if path.contains(&esm) {
+                let cjs = format!("dist{}cjs", std::path::MAIN_SEPARATOR);
+                AfterResolveAction::Rewrite(path.replace(&esm, &cjs).into())
+            } else if resolved == p(vec!["after-resolve", "rejected.js"]) {
+                AfterResolveAction::Reject
+            } else {
+                AfterResolveAction::Accept
+            }
+        }
+    }
+
+    let case_path = p(vec!["after-resolve"]);
+    let resolver = Resolver::new(Options {
+        after_resolve: Some(Arc::new(PreferCjs)),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./dist/esm/index.js?q",
+        p(vec!["after-resolve", "dist", "cjs", "index.js?q"]),
+    );
+    should_failed(&resolver, &case_path, "./rejected");
+
+    let resolver = Resolver::new(Options {
+        after_resolve: Some(Arc::new(PreferCjs)),
+        fallback: vec![(
+            "./rejected".to_string(),
+            vec![AliasMap::Target("./legacy/rejected".to_string())],
+        )],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./rejected",
+        p(vec!["after-resolve", "legacy", "rejected.js"]),
+    );
+}
+
+#[test]
+fn resolve_context_test() {
+    let case_path = p(vec!["context-module"]);
+    let resolver = Resolver::new(Default::default());
+    let locale = |names: Vec<&str>| {
+        let mut paths = vec!["context-module", "locale"];
+        paths.extend(names);
+        p(paths)
+    };
+    let is_js = |path: &str| path.ends_with(".js");
+    let is_tests = |path: &str| path.starts_with("./__tests__");
+
+    let mut options = ContextOptions::new(&is_js);
+    assert_eq!(
+        resolver
+            .resolve_context(&case_path, "./locale", &options)
+            .unwrap(),
+        vec![
+            locale(vec!["__tests__", "en.test.js"]),
+            locale(vec!["en.js"]),
+            locale(vec!["nested", "fr.js"]),
+            locale(vec!["zh.js"]),
+        ]
+    );
+
+    options.exclude = Some(&is_tests);
+    options.recursive = false;
+    assert_eq!(
+        resolver
+            .resolve_context(&case_path, "./locale", &options)
+            .unwrap(),
+        vec![locale(vec!["en.js"]), locale(vec!["zh.js"])]
+    );
+
+    assert!(matches!(
+        resolver.resolve_context(&case_path, "./inexist", &options),
+        Err(Error::ResolveFailedTag)
+    ));
+}
+
+#[test]
+fn resolve_context_symlink_loop_test() {
+    let case_path = p(vec!["context-loop"]);
+    let resolver = Resolver::new(Default::default());
+    let is_js = |path: &str| path.ends_with(".js");
+    // `sub/loop` and `sub/again` link to the directories being walked.
+    assert_eq!(
+        resolver
+            .resolve_context(&case_path, "./dir", &ContextOptions::new(&is_js))
+            .unwrap(),
+        vec![
+            p(vec!["context-loop", "dir", "a.js"]),
+            p(vec!["context-loop", "dir", "linked", "c.js"]),
+            p(vec!["context-loop", "dir", "sub", "b.js"]),
+        ]
+    );
+}
+
+#[test]
+fn description_search_roots_test() {
+    let case_path = p(vec!["description-roots", "project", "src"]);
+    let resource = |resolver: &Resolver| match resolver.resolve(&case_path, "./a").unwrap() {
+        ResolveResult::Resource(resource) => resource,
+        _ => unreachable!(),
+    };
+
+    let resolver = Resolver::new(Default::default());
+    let a = resource(&resolver);
+    assert_eq!(a.description.unwrap().data().name(), Some("outer"));
+    assert_eq!(a.description_boundary, None);
+
+    let project = p(vec!["description-roots", "project"]);
+    let resolver = Resolver::new(Options {
+        description_search_roots: vec![project.clone()],
+        ..Default::default()
+    });
+    let a = resource(&resolver);
+    assert!(a.description.is_none());
+    assert_eq!(a.description_boundary, Some(project));
+
+    let resolver = Resolver::new(Options {
+        description_search_roots: vec![p(vec!["description-roots"])],
+        ..Default::default()
+    });
+    let a = resource(&resolver);
+    assert_eq!(a.description.unwrap().data().name(), Some("outer"));
+    assert_eq!(a.description_boundary, None);
+}
+
+#[test]
+fn walk_exclude_test() {
+    let case_path = p(vec!["walk-exclude"]);
+    let file = |names: Vec<&str>| {
+        let mut paths = vec!["walk-exclude"];
+        paths.extend(names);
+        p(paths)
+    };
+    let is_js = |path: &str| path.ends_with(".js");
+    let options = ContextOptions::new(&is_js);
+
+    let resolver = Resolver::new(Default::default());
+    assert_eq!(
+        resolver.resolve_context(&case_path, ".", &options).unwrap(),
+        vec![
+            file(vec![".cache", "a.js"]),
+            file(vec![".hidden.js"]),
+            file(vec!["__tests__", "a.test.js"]),
+            file(vec!["a.js"]),
+            file(vec!["lib", "b.js"]),
+            file(vec!["lib", "b.test.js"]),
+        ]
+    );
+
+    let resolver = Resolver::new(Options {
+        walk_exclude: WalkExclude {
+            globs: vec![String::from("**/*.test.js")],
+            ..WalkExclude::recommended()
+        },
+        ..Default::default()
+    });
+    assert_eq!(
+        resolver.resolve_context(&case_path, ".", &options).unwrap(),
+        vec![file(vec!["a.js"]), file(vec!["lib", "b.js"])]
+    );
+}
+
+#[test]
+fn exports_null_test() {
+    let case_path = p(vec!["exports-null"]);
+    let resolver = Resolver::new(Default::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "blocked/util",
+        p(vec![
+            "exports-null",
+            "node_modules",
+            "blocked",
+            "lib",
+            "util.js",
+        ]),
+    );
+    // blocked by `null` even if `./*` matches.
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "blocked/internal/secret.js",
+        "Package path blocked/internal/secret.js is not exported".to_string(),
+    );
+
+    let resolver = Resolver::new(Options {
+        strict_exports: false,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "blocked/internal/secret.js",
+        p(vec![
+            "exports-null",
+            "node_modules",
+            "blocked",
+            "internal",
+            "secret.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "blocked/util",
+        p(vec![
+            "exports-null",
+            "node_modules",
+            "blocked",
+            "lib",
+            "util.js",
+        ]),
+    );
+}
+
+#[test]
+fn scope_edge_test() {
+    let case_path = p(vec!["scope-edge"]);
+    let resolver = Resolver::new(Default::default());
+    should_failed(&resolver, &case_path, "@/components");
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "@scope",
+        "Invalid module request \"@scope\"".to_string(),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "@scope/pkg",
+        p(vec![
+            "scope-edge",
+            "node_modules",
+            "@scope",
+            "pkg",
+            "index.js",
+        ]),
+    );
+
+    let resolver = Resolver::new(Options {
+        fallback: vec![("@".to_string(), vec![AliasMap::Target("./src".to_string())])],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "@/components",
+        p(vec!["scope-edge", "src", "components.js"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        module_alias_prefixes: vec![],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "@/components",
+        p(vec![
+            "scope-edge",
+            "node_modules",
+            "@",
+            "components",
+            "index.js",
+        ]),
+    );
+}
+
+#[test]
+fn directory_request_test() {
+    let fixture_path = p(vec![]);
+    let resolver = Resolver::new(Default::default());
+    should_failed(&resolver, &fixture_path, "./a/");
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "./dirOrFile/",
+        p(vec!["dirOrFile", "index.js"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        strict_directory_requests: false,
+        ..Default::default()
+    });
+    should_equal(&resolver, &fixture_path, "./a/", p(vec!["a.js"]));
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "./dirOrFile/",
+        p(vec!["dirOrFile.js"]),
+    );
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "./dirOrFile/?q",
+        p(vec!["dirOrFile.js?q"]),
+    );
+}
+
+#[test]
+fn dir_resolver_test() {
+    let lib_path = p(vec!["browser-module", "lib"]);
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        ..Default::default()
+    });
+    let dir_resolver = resolver.dir_resolver(&lib_path);
+    assert_eq!(dir_resolver.dir(), lib_path);
+    let requests = [
+        "./ignore",
+        "./toString",
+        "./replaced",
+        "module-a",
+        "module-b",
+        "./redirect2",
+        "./inexist",
+    ];
+    for request in requests {
+        let expected = resolver.resolve(&lib_path, request);
+        let result = dir_resolver.resolve(request);
+        match (expected, result) {
+            (Ok(ResolveResult::Resource(expected)), Ok(ResolveResult::Resource(result))) => {
+                assert_eq!(expected.join(), result.join(), "{request}")
+            }
+            (Ok(ResolveResult::Ignored), Ok(ResolveResult::Ignored)) => (),
+            (Err(Error::ResolveFailedTag), Err(Error::ResolveFailedTag)) => (),
+            (
+                Err(Error::NotFound {
+                    paths: expected, ..
+                }),
+                Err(Error::NotFound { paths, .. }),
+            ) => {
+                assert_eq!(expected, paths, "{request}")
+            }
+            (expected, result) => panic!("{request}: {expected:?} != {result:?}"),
+        }
+    }
+}
+
+#[test]
+fn modules_paths_test() {
+    let store = p(vec!["modules-store", "store"]);
+    let issuer = p(vec!["modules-store", "project", "src"]);
+    let resolver = Resolver::new(Options {
+        modules: vec!["node_modules".to_string(), store.display().to_string()],
+        modules_scope: vec![(store.display().to_string(), vec!["@corp/*".to_string()])],
+        ..Default::default()
+    });
+    let paths = resolver.modules_paths(&issuer, "@corp/missing").unwrap();
+    // the failure carries the directories which the resolution looked up.
+    let error = resolver.resolve(&issuer, "@corp/missing").unwrap_err();
+    let Error::NotFound {
+        request,
+        paths: walked,
+    } = &error
+    else {
+        panic!("{error:?}")
+    };
+    assert_eq!(request, "@corp/missing");
+    assert_eq!(walked, &paths);
+    let rendered = resolver.render_error(&issuer, "@corp/missing", &error);
+    assert!(rendered.contains(&format!("{} (missing)", paths[0].path.display())));
+    assert_eq!(
+        paths[..3],
+        [
+            ModulesPath {
+                path: p(vec!["modules-store", "project", "src", "node_modules"]),
+                exists: false,
+            },
+            ModulesPath {
+                path: p(vec!["modules-store", "project", "node_modules"]),
+                exists: true,
+            },
+            ModulesPath {
+                path: p(vec!["modules-store", "node_modules"]),
+                exists: false,
+            },
+        ]
+    );
+    assert_eq!(
+        paths.last(),
+        Some(&ModulesPath {
+            path: store.clone(),
+            exists: true,
+        })
+    );
+
+    // the store is out of the scope of `lodash`.
+    let paths = resolver.modules_paths(&issuer, "lodash/fp").unwrap();
+    assert!(paths.iter().all(|item| item.path != store));
+    assert!(paths.iter().all(|item| item.path.ends_with("node_modules")));
+
+    assert_eq!(resolver.modules_paths(&issuer, "./lodash"), None);
+}
+
+#[test]
+fn node_esm_algorithm_test() {
+    let case_path = p(vec!["node-algorithm", "src"]);
+    let resolver = Resolver::new(Options {
+        algorithm: Algorithm::NodeEsm,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./a.js",
+        p(vec!["node-algorithm", "src", "a.js"]),
+    );
+    should_failed(&resolver, &case_path, "./a");
+    should_failed(&resolver, &case_path, "./dir");
+    should_failed(&resolver, &case_path, "./dir/");
+    should_equal(
+        &resolver,
+        &case_path,
+        "./dir/index.js",
+        p(vec!["node-algorithm", "src", "dir", "index.js"]),
+    );
+
+    // the requests are URLs.
+    should_equal(
+        &resolver,
+        &case_path,
+        "./a%20b.js",
+        p(vec!["node-algorithm", "src", "a b.js"]),
+    );
+    let url = format!(
+        "file://{}{}",
+        if cfg!(windows) { "/" } else { "" },
+        case_path
+            .join("a.js")
+            .display()
+            .to_string()
+            .replace('\\', "/")
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        &url,
+        p(vec!["node-algorithm", "src", "a.js"]),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "./a%2Fb.js",
+        "Invalid module request \"./a%2Fb.js\", it must not include encoded \"/\" or \"\\\" characters".to_string(),
+    );
+
+    // `exports` is enforced with the conditions of `import`.
+    should_equal(
+        &resolver,
+        &case_path,
+        "esm-pkg",
+        p(vec![
+            "node-algorithm",
+            "node_modules",
+            "esm-pkg",
+            "index.mjs",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "esm-pkg/feature",
+        p(vec![
+            "node-algorithm",
+            "node_modules",
+            "esm-pkg",
+            "feature.js",
+        ]),
+    );
+    assert!(resolver.resolve(&case_path, "esm-pkg/internal.js").is_err());
+
+    // the packages without `exports` are resolved by `main`.
+    should_equal(
+        &resolver,
+        &case_path,
+        "legacy",
+        p(vec![
+            "node-algorithm",
+            "node_modules",
+            "legacy",
+            "lib",
+            "main.js",
+        ]),
+    );
+    should_failed(&resolver, &case_path, "legacy/util");
+    should_equal(
+        &resolver,
+        &case_path,
+        "legacy/util.js",
+        p(vec!["node-algorithm", "node_modules", "legacy", "util.js"]),
+    );
+
+    // the options which conflict with the algorithm are overridden.
+    let resolver = Resolver::new(Options {
+        algorithm: Algorithm::NodeEsm,
+        extensions: vec![".ts".to_string()],
+        fully_specified: false,
+        strict_exports: false,
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "./a");
+    assert!(resolver.resolve(&case_path, "esm-pkg/internal.js").is_err());
+}
+
+#[test]
+fn node_cjs_algorithm_test() {
+    let case_path = p(vec!["node-algorithm", "src"]);
+    let resolver = Resolver::new(Options {
+        algorithm: Algorithm::NodeCjs,
+        extensions: vec![".ts".to_string()],
+        strict_exports: false,
+        ..Default::default()
+    });
+    // the same as `require.resolve` of Node.js.
+    let cases = [
+        ("./a", vec!["src", "a.js"]),
+        ("./a.js", vec!["src", "a.js"]),
+        ("./a b.js", vec!["src", "a b.js"]),
+        ("./dir", vec!["src", "dir", "index.js"]),
+        ("./dir/", vec!["src", "dir", "index.js"]),
+        ("./c", vec!["src", "c.json"]),
+        ("esm-pkg", vec!["node_modules", "esm-pkg", "index.cjs"]),
+        (
+            "esm-pkg/feature",
+            vec!["node_modules", "esm-pkg", "feature.js"],
+        ),
+        ("legacy", vec!["node_modules", "legacy", "lib", "main.js"]),
+        ("legacy/util", vec!["node_modules", "legacy", "util.js"]),
+        // the main file is only tried with the extensions.
+        ("./bare", vec!["src", "bare", "index.json"]),
+    ];
+    for (request, expected) in cases {
+        let expected = p([vec!["node-algorithm"], expected].concat());
+        should_equal(&resolver, &case_path, request, expected);
+    }
+    should_failed(&resolver, &case_path, "./a%20b.js");
+    // the targets of `exports` are not appended the extensions.
+    should_failed(&resolver, &case_path, "exact-exports");
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "esm-pkg/internal.js",
+        format!(
+            "Package path esm-pkg/internal.js is not exported in {}",
+            p(vec![
+                "node-algorithm",
+                "node_modules",
+                "esm-pkg",
+                "package.json"
+            ])
+            .display()
+        ),
+    );
+
+    // select the algorithm per call.
+    let esm_resolver = resolver.with_algorithm(Algorithm::NodeEsm);
+    should_failed(&esm_resolver, &case_path, "./a");
+    should_equal(
+        &esm_resolver,
+        &case_path,
+        "esm-pkg",
+        p(vec![
+            "node-algorithm",
+            "node_modules",
+            "esm-pkg",
+            "index.mjs",
+        ]),
+    );
+    // the options overridden by the algorithm are restored.
+    let enhanced_resolver = esm_resolver.with_algorithm(Algorithm::Enhanced);
+    should_failed(&enhanced_resolver, &case_path, "./a");
+    should_equal(
+        &enhanced_resolver,
+        &case_path,
+        "esm-pkg/internal.js",
+        p(vec![
+            "node-algorithm",
+            "node_modules",
+            "esm-pkg",
+            "internal.js",
+        ]),
+    );
+}
+
+#[test]
+fn module_type_test() {
+    fn should_typed(
+        resolver: &Resolver,
+        path: &Path,
+        request: &str,
+        module_type: ModuleType,
+        js_format: Option<JsFormat>,
+    ) {
+        match resolver.resolve(path, request) {
+            Ok(ResolveResult::Resource(resource)) => {
+                assert_eq!(resource.module_type, module_type, "{request}");
+                assert_eq!(resource.js_format(), js_format, "{request}");
+            }
+            result => panic!("{request}: {result:?}"),
+        }
+    }
+
+    let case_path = p(vec!["module-type"]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![],
+        ..Default::default()
+    });
+    let esm = Some(JsFormat::Module);
+    let cjs = Some(JsFormat::CommonJs);
+    should_typed(&resolver, &case_path, "./esm/a.js", ModuleType::Js, esm);
+    should_typed(&resolver, &case_path, "./esm/b.cjs", ModuleType::Js, cjs);
+    should_typed(&resolver, &case_path, "./cjs/a.js", ModuleType::Js, cjs);
+    should_typed(&resolver, &case_path, "./cjs/b.mjs", ModuleType::Js, esm);
+    let cases = [
+        ("./cjs/data.json", ModuleType::Json),
+        ("./cjs/style.css", ModuleType::Css),
+        ("./cjs/addon.node", ModuleType::NativeAddon),
+        ("./cjs/lib.wasm", ModuleType::Wasm),
+        ("./cjs/readme.txt", ModuleType::Unknown),
+    ];
+    for (request, module_type) in cases {
+        should_typed(&resolver, &case_path, request, module_type, None);
+    }
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![],
+        module_types: vec![
+            (String::from(".txt"), ModuleType::Css),
+            (String::from(".js"), ModuleType::Js),
+        ],
+        ..Default::default()
+    });
+    should_typed(
+        &resolver,
+        &case_path,
+        "./cjs/readme.txt",
+        ModuleType::Css,
+        None,
+    );
+    should_typed(
+        &resolver,
+        &case_path,
+        "./cjs/data.json",
+        ModuleType::Unknown,
+        None,
+    );
+}
+
+#[test]
+fn wasm_test() {
+    let case_path = p(vec!["wasm", "src"]);
+    let resolver = Resolver::new(Options::wasm_pack());
+    should_equal(
+        &resolver,
+        &case_path,
+        "./mod",
+        p(vec!["wasm", "src", "mod.wasm"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "wasm-cond",
+        p(vec!["wasm", "node_modules", "wasm-cond", "index_bg.wasm"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "wasm-pack-pkg",
+        p(vec![
+            "wasm",
+            "node_modules",
+            "wasm-pack-pkg",
+            "wasm_pack_pkg.js",
+        ]),
+    );
+    let wasm_path = p(vec!["wasm", "node_modules", "wasm-pack-pkg"]);
+    match resolver.resolve(&wasm_path, "./wasm_pack_pkg_bg.wasm") {
+        Ok(ResolveResult::Resource(resource)) => {
+            assert_eq!(resource.path, wasm_path.join("wasm_pack_pkg_bg.wasm"));
+            assert_eq!(resource.module_type, ModuleType::Wasm);
+        }
+        result => panic!("{result:?}"),
+    }
+
+    // without the `wasm` condition.
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_set(vec!["import"]),
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "./mod");
+    should_equal(
+        &resolver,
+        &case_path,
+        "wasm-cond",
+        p(vec!["wasm", "node_modules", "wasm-cond", "index.js"]),
+    );
+}
+
+#[test]
+fn probe_order_test() {
+    let case_path = p(vec!["probe-order"]);
+    let resolver = |probe_order, modules_probe_order| {
+        Resolver::new(Options {
+            extensions: vec![".css".to_string()],
+            probe_order,
+            modules_probe_order,
+            ..Default::default()
+        })
+    };
+
+    let file_first = resolver(ProbeOrder::FileFirst, ProbeOrder::FileFirst);
+    should_equal(
+        &file_first,
+        &case_path,
+        "./button",
+        p(vec!["probe-order", "button.css"]),
+    );
+    should_equal(
+        &file_first,
+        &case_path,
+        "ui/button",
+        p(vec!["probe-order", "node_modules", "ui", "button.css"]),
+    );
+
+    let dir_first = resolver(ProbeOrder::DirFirst, ProbeOrder::FileFirst);
+    should_equal(
+        &dir_first,
+        &case_path,
+        "./button",
+        p(vec!["probe-order", "button", "index.css"]),
+    );
+    should_equal(
+        &dir_first,
+        &case_path,
+        "./button.css",
+        p(vec!["probe-order", "button.css"]),
+    );
+    should_equal(
+        &dir_first,
+        &case_path,
+        "ui/button",
+        p(vec!["probe-order", "node_modules", "ui", "button.css"]),
+    );
+
+    let dir_first = resolver(ProbeOrder::FileFirst, ProbeOrder::DirFirst);
+    should_equal(
+        &dir_first,
+        &case_path,
+        "ui/button",
+        p(vec![
+            "probe-order",
+            "node_modules",
+            "ui",
+            "button",
+            "index.css",
+        ]),
+    );
+
+    let file_only = resolver(ProbeOrder::FileOnly, ProbeOrder::FileOnly);
+    should_failed(&file_only, &case_path, "./only-dir");
+    should_failed(&file_only, &case_path, "ui");
+    should_equal(
+        &file_only,
+        &case_path,
+        "ui/button",
+        p(vec!["probe-order", "node_modules", "ui", "button.css"]),
+    );
+
+    let dir_only = resolver(ProbeOrder::DirOnly, ProbeOrder::DirOnly);
+    should_failed(&dir_only, &case_path, "./button.css");
+    should_equal(
+        &dir_only,
+        &case_path,
+        "./only-dir",
+        p(vec!["probe-order", "only-dir", "index.css"]),
+    );
+}
+
+#[test]
+fn description_retention_test() {
+    let case_path = p(vec!["wasm", "src"]);
+    let resolve = |description_retention| {
+        let resolver = Resolver::new(Options {
+            description_retention,
+            ..Options::wasm_pack()
+        });
+        match resolver.resolve(&case_path, "wasm-pack-pkg") {
+            Ok(ResolveResult::Resource(resource)) => {
+                assert_eq!(
+                    resource.path,
+                    p(vec![
+                        "wasm",
+                        "node_modules",
+                        "wasm-pack-pkg",
+                        "wasm_pack_pkg.js"
+                    ])
+                );
+                resource.description.unwrap()
+            }
+            result => panic!("{result:?}"),
+        }
+    };
+
+    let description = resolve(DescriptionRetention::Full);
+    assert!(!description.data().is_trimmed());
+    assert!(description.data().raw().get("files").is_some());
+
+    let description = resolve(DescriptionRetention::ResolutionFields);
+    assert!(description.data().is_trimmed());
+    let keys: Vec<&String> = description
+        .data()
+        .raw()
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect();
+    assert_eq!(keys, ["name", "version", "module"]);
+    assert_eq!(description.data().name(), Some("wasm-pack-pkg"));
+}
+
+#[test]
+fn output_path_style_test() {
+    let fixture_path = p(vec![]);
+    let resolver = Resolver::new(Default::default());
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "./dirOrFile/../a.js",
+        p(vec!["a.js"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        output_path_style: PathStyle::ForwardSlash,
+        ..Default::default()
+    });
+    match resolver.resolve(&fixture_path.join("dirOrFile"), "../a") {
+        Ok(ResolveResult::Resource(resource)) => {
+            let path = resource.path.to_str().unwrap();
+            assert!(!path.contains('\\'));
+            assert!(!path.contains("/./") && !path.contains("/../"));
+            assert_eq!(
+                path,
+                p(vec!["a.js"]).display().to_string().replace('\\', "/")
+            );
+        }
+        result => panic!("{result:?}"),
+    }
+}
+
+#[test]
+fn warm_up_test() {
+    let store = p(vec!["modules-store", "store"]);
+    let resolver = Resolver::new(Options {
+        modules: vec!["node_modules".to_string(), store.display().to_string()],
+        ..Default::default()
+    });
+    let progress = std::sync::Mutex::new(vec![]);
+    let on_progress = |item: WarmUpProgress| progress.lock().unwrap().push(item);
+    let parsed = resolver
+        .warm_up(&[p(vec!["wasm", "node_modules"])], Some(&on_progress))
+        .unwrap();
+    // `lodash` in the store has no description file.
+    assert_eq!(parsed, 3);
+    let mut progress = progress.into_inner().unwrap();
+    progress.sort_by_key(|item| item.parsed);
+    assert_eq!(
+        progress,
+        (1..=4)
+            .map(|parsed| WarmUpProgress { parsed, total: 4 })
+            .collect::<Vec<_>>()
+    );
+
+    should_equal(
+        &resolver,
+        &p(vec!["modules-store", "project"]),
+        "@corp/ui",
+        store.join("@corp").join("ui").join("index.js"),
+    );
+}
+
+#[test]
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+fn io_uring_warm_up_test() {
+    let store = p(vec!["modules-store", "store"]);
+    let file_system = Arc::new(nodejs_resolver::IoUringFileSystem::new());
+    let resolver = Resolver::new(Options {
+        modules: vec!["node_modules".to_string(), store.display().to_string()],
+        external_cache: Some(Arc::new(Cache::with_file_system(file_system))),
+        ..Default::default()
+    });
+    let parsed = resolver
+        .warm_up(&[p(vec!["wasm", "node_modules"])], None)
+        .unwrap();
+    assert_eq!(parsed, 3);
+    should_equal(
+        &resolver,
+        &p(vec!["modules-store", "project"]),
+        "@corp/ui",
+        store.join("@corp").join("ui").join("index.js"),
+    );
+}
+
+#[test]
+fn gc_test() {
+    let fixture_path = p(vec![]);
+    let cache = Arc::new(Cache::default());
+    let resolver = Resolver::new(Options {
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    let dir_or_file = p(vec!["dirOrFile", "index.js"]);
+    should_equal(&resolver, &fixture_path, "./a.js", p(vec!["a.js"]));
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "./dirOrFile/index.js",
+        dir_or_file.clone(),
+    );
+    assert!(cache.entries.contains_key(dir_or_file.as_path()));
+
+    let generation = resolver.next_generation();
+    should_equal(&resolver, &fixture_path, "./a.js", p(vec!["a.js"]));
+    assert!(resolver.gc(generation) > 0);
+    assert!(!cache.entries.contains_key(dir_or_file.as_path()));
+    // the ancestors of the live entries are retained.
+    assert!(cache.entries.contains_key(p(vec!["a.js"]).as_path()));
+    assert!(cache.entries.contains_key(fixture_path.as_path()));
+
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "./dirOrFile/index.js",
+        dir_or_file,
+    );
+}
+
+#[test]
+fn compat_corpus_test() {
+    let case_path = p(vec!["compat-corpus"]);
+    let expected = std::fs::read_to_string(case_path.join("expected.json")).unwrap();
+    let expected: serde_json::Value = serde_json::from_str(&expected).unwrap();
+    for (env, conditions) in expected["environments"].as_object().unwrap() {
+        let resolver = Resolver::new(Options {
+            condition_names: conditions
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|condition| condition.as_str().unwrap().to_string())
+                .collect(),
+            ..Default::default()
+        });
+        for case in expected["cases"].as_array().unwrap() {
+            let request = case["request"].as_str().unwrap();
+            let actual = match resolver.resolve(&case_path, request) {
+                Ok(ResolveResult::Resource(resource)) => Some(resource.join()),
+                Ok(ResolveResult::Ignored | ResolveResult::OptionalMissing) => {
+                    panic!("{request} should not be ignored in {env}")
+                }
+                Err(_) => None,
+            };
+            let expected = case["expected"][env]
+                .as_str()
+                .map(|relative| case_path.join(relative));
+            assert_eq!(actual, expected, "{request} in {env}");
+        }
+    }
+}
+
+#[test]
+fn symlink_chain_test() {
+    let linked_path = p(vec!["symlink", "linked"]);
+    let resource =
+        |resolver: &Resolver, request: &str| match resolver.resolve(&linked_path, request).unwrap()
+        {
+            ResolveResult::Resource(resource) => resource,
+            _ => unreachable!(),
+        };
+    let link = |path: &str, target: Vec<&str>| Symlink {
+        path: linked_path.join(path),
+        target: p(target),
+    };
+    let expected = vec![
+        link("that", vec!["symlink", "linked"]),
+        link("that/lib", vec!["symlink", "lib"]),
+    ];
+
+    let resolver = Resolver::new(Options::default());
+    let index = resource(&resolver, "./that/lib/index.js");
+    assert_eq!(index.path, p(vec!["symlink", "lib", "index.js"]));
+    assert_eq!(index.symlink_chain, expected);
+    assert_eq!(
+        resolver
+            .entry(linked_path.join("that/lib/index.js"))
+            .symlink_chain(),
+        expected
+    );
+
+    // the chain is recorded for the hosts which resolve the symlinks themselves.
+    let resolver = Resolver::new(Options {
+        symlinks: false,
+        ..Default::default()
+    });
+    let index = resource(&resolver, "./that/lib/index.js");
+    assert_eq!(index.path, linked_path.join("that/lib/index.js"));
+    assert_eq!(index.symlink_chain, expected);
+
+    let index = resource(&resolver, "../lib/index.js");
+    assert!(index.symlink_chain.is_empty());
+}
+
+#[test]
+fn symlink_escape_test() {
+    let linked_path = p(vec!["symlink", "linked"]);
+    let resolver = Resolver::new(Options {
+        allow_symlink_escape: false,
+        roots: vec![linked_path.clone()],
+        ..Default::default()
+    });
+    should_unexpected_value_error(
+        &resolver,
+        &linked_path,
+        "./index.js",
+        format!(
+            "{} is a symlink to {} which is outside of the roots",
+            linked_path.join("index.js").display(),
+            p(vec!["symlink", "lib", "index.js"]).display()
+        ),
+    );
+    assert!(resolver
+        .resolve(&linked_path, "./this/lib/index.js")
+        .is_err());
+
+    // the links inside of the roots are followed.
+    let resolver = Resolver::new(Options {
+        allow_symlink_escape: false,
+        roots: vec![p(vec!["symlink"])],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &linked_path,
+        "./index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    // the files outside of the roots which are not symlinks are not rejected.
+    should_equal(&resolver, &p(vec![]), "./a.js", p(vec!["a.js"]));
+
+    // the current working directory is the root by default.
+    let resolver = Resolver::new(Options {
+        allow_symlink_escape: false,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &linked_path,
+        "./index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+}
+
+#[test]
+fn sandbox_test() {
+    let case_path = p(vec!["simple"]);
+    let resolver = Resolver::new(Options {
+        sandbox: true,
+        roots: vec![case_path.clone()],
+        ..Default::default()
+    });
+    let sandboxed = resolver.resolve_sandboxed(&case_path, "./lib/index");
+    match sandboxed.result {
+        Ok(ResolveResult::Resource(resource)) => {
+            assert_eq!(resource.path, p(vec!["simple", "lib", "index.js"]))
+        }
+        _ => unreachable!(),
+    }
+    assert!(sandboxed.blocked.is_empty());
+
+    // `m1/a` is in `fixtures/node_modules`, which is outside of the roots.
+    let sandboxed = resolver.resolve_sandboxed(&case_path, "m1/a");
+    assert!(matches!(sandboxed.result, Err(Error::NotFound { .. })));
+    assert!(sandboxed.blocked.contains(&p(vec!["node_modules"])));
+    should_failed(&resolver, &case_path, p(vec!["a.js"]).to_str().unwrap());
+
+    let resolver = Resolver::new(Options {
+        roots: vec![case_path.clone()],
+        ..Default::default()
+    });
+    let sandboxed = resolver.resolve_sandboxed(&case_path, "m1/a");
+    assert!(sandboxed.result.is_ok());
+    assert!(sandboxed.blocked.is_empty());
+
+    // the resolvers sharing the cache still see the description file above
+    // the roots of the sandboxed one.
+    let lib = case_path.join("lib");
+    let description = |resolver: &Resolver| match resolver.resolve(&lib, "./index") {
+        Ok(ResolveResult::Resource(resource)) => resource.description.map(|d| d.dir().clone()),
+        result => panic!("{result:?}"),
+    };
+    let cache = Arc::new(Cache::default());
+    let sandboxed = Resolver::new(Options {
+        sandbox: true,
+        roots: vec![lib.clone()],
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    assert_eq!(description(&sandboxed), None);
+    let resolver = Resolver::new(Options {
+        external_cache: Some(cache),
+        ..Default::default()
+    });
+    assert_eq!(
+        description(&resolver).as_ref().map(|dir| dir.as_ref()),
+        Some(case_path.as_path())
+    );
+}
+
+#[test]
+fn workspace_root_test() {
+    let linked_path = p(vec!["symlink", "linked"]);
+    let resolver = Resolver::new(Options {
+        workspace_root: Some(p(vec!["symlink"])),
+        ..Default::default()
+    });
+    let resolve =
+        |resolver: &Resolver, path: &Path, request: &str| match resolver.resolve(path, request) {
+            Ok(ResolveResult::Resource(resource)) => resource.path,
+            _ => unreachable!(),
+        };
+    assert_eq!(
+        resolve(&resolver, &linked_path, "./index.js"),
+        PathBuf::from("lib/index.js")
+    );
+
+    // the real path outside of the workspace is not used.
+    let resolver = Resolver::new(Options {
+        workspace_root: Some(linked_path.clone()),
+        ..Default::default()
+    });
+    assert_eq!(
+        resolve(&resolver, &linked_path, "./index.js"),
+        PathBuf::from("index.js")
+    );
+    assert_eq!(
+        resolve(&resolver, &linked_path, "../lib/index.js"),
+        ["..", "lib", "index.js"].iter().collect::<PathBuf>()
+    );
+}
+
+#[test]
+fn resolver_pool_test() {
+    let case_path = p(vec!["node-algorithm", "src"]);
+    let pool = ResolverPool::new(Resolver::new(Options::default()));
+    let main_field = |result: Result<ResolveResult<Resource>, Error>| match result {
+        Ok(ResolveResult::Resource(resource)) => resource.main_field,
+        _ => unreachable!(),
+    };
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                let mut handle = pool.handle();
+                for _ in 0..8 {
+                    let result = handle.resolve(&case_path, "legacy");
+                    assert_eq!(main_field(result), Some(String::from("main")));
+                    // the scratch context is reset between the calls.
+                    let result = handle.resolve(&case_path, "./a");
+                    assert_eq!(main_field(result), None);
+                }
+            });
+        }
+    });
+    let idle = pool.idle_count();
+    assert!((1..=4).contains(&idle));
+    drop(pool.handle());
+    assert_eq!(pool.idle_count(), idle);
+    assert_eq!(pool.resolver().metrics().snapshot().resolutions, 64);
+}
+
+#[test]
+fn path_allocations_test() {
+    let case_path = p(vec!["full", "a"]);
+    let pool = ResolverPool::new(Resolver::new(Options::default()));
+    let metrics = pool.resolver().metrics();
+    let mut handle = pool.handle();
+    let mut resolve_all = || {
+        for request in ["./index", "package1", "package2/lib", "./inexist"] {
+            let _ = handle.resolve(&case_path, request);
+        }
+    };
+    resolve_all();
+    let warm = metrics.snapshot().path_allocations;
+    assert!(warm > 0);
+    // the buffers of the candidates are reused by the next resolutions.
+    for _ in 0..8 {
+        resolve_all();
+    }
+    assert_eq!(metrics.snapshot().path_allocations, warm);
+}
+
+#[test]
+fn result_kind_test() {
+    let resource =
+        |resolver: &Resolver, path: &Path, request: &str, kind: ResultKind| match resolver
+            .resolve_with_kind(path, request, kind)
+            .unwrap()
+        {
+            ResolveResult::Resource(resource) => resource,
+            _ => unreachable!(),
+        };
+    let case_path = p(vec!["result-kind"]);
+    let resolver = Resolver::new(Options::default());
+    let full = resource(&resolver, &case_path, "pkg", ResultKind::FullMetadata);
+    let path = resource(&resolver, &case_path, "pkg?a#b", ResultKind::Path);
+    assert_eq!(path.path, full.path);
+    assert_eq!(
+        path.join(),
+        p(vec![
+            "result-kind",
+            "node_modules",
+            "pkg",
+            "lib",
+            "index.js?a#b"
+        ])
+    );
+    assert!(full.description.is_some());
+    assert!(path.description.is_none());
+    assert_eq!(full.main_field.as_deref(), Some("main"));
+    assert!(path.main_field.is_none());
+
+    // the symlinks are not resolved for the existence.
+    let linked_path = p(vec!["symlink", "linked"]);
+    let request = "./that/lib/index.js";
+    let path = resource(&resolver, &linked_path, request, ResultKind::Path);
+    assert_eq!(path.path, p(vec!["symlink", "lib", "index.js"]));
+    let existence = resource(&resolver, &linked_path, request, ResultKind::Existence);
+    assert_eq!(existence.path, linked_path.join(request));
+    assert!(existence.symlink_chain.is_empty());
+    let resolver = Resolver::new(Options {
+        allow_symlink_escape: false,
+        roots: vec![linked_path.clone()],
+        ..Default::default()
+    });
+    assert!(resolver
+        .resolve_with_kind(&linked_path, request, ResultKind::Existence)
+        .is_err());
+    assert!(resolver
+        .resolve_with_kind(&linked_path, "./that/lib/inexist.js", ResultKind::Existence)
+        .is_err());
+
+    // the main fields are not followed for the existence of a context.
+    let resolver = Resolver::new(Options {
+        resolve_to_context: true,
+        ..Default::default()
+    });
+    let full = resource(&resolver, &case_path, "pkg", ResultKind::FullMetadata);
+    assert_eq!(
+        full.path,
+        p(vec!["result-kind", "node_modules", "pkg", "lib"])
+    );
+    let existence = resource(&resolver, &case_path, "pkg", ResultKind::Existence);
+    assert_eq!(
+        existence.path,
+        p(vec!["result-kind", "node_modules", "pkg"])
+    );
+    assert!(resolver
+        .resolve_with_kind(&case_path, "inexist", ResultKind::Existence)
+        .is_err());
+}
+
+#[test]
+#[cfg(feature = "trace")]
+fn resolve_traced_test() {
+    let case_path = p(vec!["node-algorithm", "src"]);
+    let resolver = Resolver::new(Options::default());
+    let (result, trace) = resolver.resolve_traced(&case_path, "legacy", vec![]);
+    assert!(result.is_ok());
+    let trace = String::from_utf8(trace).unwrap();
+    assert!(trace.contains("Try to resolve"));
+    assert!(trace.contains("MainField in"));
+
+    // the write errors don't fail the resolution.
+    struct Broken;
+    impl std::io::Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let (result, _) = resolver.resolve_traced(&case_path, "legacy", Broken);
+    assert!(result.is_ok());
+}
+
+#[test]
+fn browser_exports_test() {
+    let case_path = p(vec!["browser-exports", "src"]);
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        ..Default::default()
+    });
+    // the targets of `exports` are mapped by the `browser` field of the package,
+    // the keys match the files with or without the extensions.
+    should_ignored(&resolver, &case_path, "ext-pkg");
+    should_ignored(&resolver, &case_path, "ext-pkg/util");
+    should_ignored(&resolver, &case_path, "ext-pkg/stream");
+    should_equal(
+        &resolver,
+        &case_path,
+        "ext-pkg/remapped",
+        p(vec![
+            "browser-exports",
+            "node_modules",
+            "ext-pkg",
+            "lib",
+            "remapped-browser.js",
+        ]),
+    );
+    // the specifier is mapped by the `browser` field of the issuer before `exports`.
+    should_ignored(&resolver, &case_path, "ext-pkg/feature");
+
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "ext-pkg/util",
+        p(vec![
+            "browser-exports",
+            "node_modules",
+            "ext-pkg",
+            "lib",
+            "util.js",
+        ]),
+    );
+}
+
+#[test]
+#[cfg(feature = "excerpt")]
+fn render_error_test() {
+    use nodejs_resolver::{ErrorRenderer, ErrorReport};
+
+    let case_path = p(vec!["incorrect-package", "pack1"]);
+    let resolver = Resolver::new(Options::default());
+    let error = resolver.resolve(&case_path, ".").unwrap_err();
+    let rendered = resolver.render_error(&case_path, ".", &error);
+    assert!(rendered.contains("Can't resolve"));
+    assert!(rendered.contains(&format!("{}:3:1", case_path.join("package.json").display())));
+    assert!(rendered.contains("\"main\": \"./a.js\","));
+
+    #[derive(Debug)]
+    struct Localized;
+    impl ErrorRenderer for Localized {
+        fn render(&self, report: &ErrorReport) -> String {
+            let excerpt = report.excerpt.as_ref().unwrap();
+            format!("无法解析 {}（第 {} 行）", report.request, excerpt.line)
+        }
+    }
+    let resolver = Resolver::new(Options {
+        error_renderer: Some(Arc::new(Localized)),
+        ..Default::default()
+    });
+    let error = resolver.resolve(&case_path, ".").unwrap_err();
+    assert_eq!(
+        resolver.render_error(&case_path, ".", &error),
+        "无法解析 .（第 3 行）"
+    );
+    assert_eq!(Error::Overflow.to_string(), "Too many nested resolutions");
+}
+
+#[test]
+#[cfg(feature = "excerpt")]
+fn error_excerpt_test() {
+    let resolver = Resolver::new(Options::default());
+    // the key which the request matched.
+    let case_path = p(vec!["exports-field-error"]);
+    let error = resolver.resolve(&case_path, "exports-field").unwrap_err();
+    let excerpt = resolver
+        .error_excerpt(&case_path, "exports-field", &error)
+        .unwrap();
+    assert_eq!(
+        excerpt.path,
+        p(vec![
+            "exports-field-error",
+            "node_modules",
+            "exports-field",
+            "package.json"
+        ])
+    );
+    assert_eq!((excerpt.line, excerpt.column), (5, 5));
+    assert_eq!(excerpt.offset, 70);
+    assert_eq!(
+        excerpt.lines[1],
+        (
+            5,
+            String::from("    \".\": \"./a/../b/../../pack1/index.js\"")
+        )
+    );
+
+    // the field if no key is matched.
+    let case_path = p(vec!["node-algorithm", "src"]);
+    let error = resolver
+        .resolve(&case_path, "esm-pkg/internal.js")
+        .unwrap_err();
+    let excerpt = resolver
+        .error_excerpt(&case_path, "esm-pkg/internal.js", &error)
+        .unwrap();
+    assert_eq!((excerpt.line, excerpt.column), (3, 3));
+
+    assert!(resolver
+        .error_excerpt(&case_path, "./a", &Error::ResolveFailedTag)
+        .is_none());
+}
+
+#[test]
+fn resolve_matrix_test() {
+    let case_path = p(vec!["compat-corpus"]);
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_set(vec!["node", "import"]),
+        ..Default::default()
+    });
+    let condition_sets = [
+        vec_to_set(vec!["node", "import"]),
+        vec_to_set(vec!["node", "require"]),
+        vec_to_set(vec!["browser", "import"]),
+    ];
+    let paths: Vec<Option<PathBuf>> = resolver
+        .resolve_matrix(&case_path, "uuid", &condition_sets)
+        .into_iter()
+        .map(|result| match result {
+            Ok(ResolveResult::Resource(resource)) => Some(resource.path),
+            _ => None,
+        })
+        .collect();
+    let uuid = |path: Vec<&str>| {
+        Some(p(
+            [vec!["compat-corpus", "node_modules", "uuid"], path].concat()
+        ))
+    };
+    assert_eq!(
+        paths,
+        vec![
+            uuid(vec!["wrapper.mjs"]),
+            uuid(vec!["dist", "index.js"]),
+            uuid(vec!["dist", "esm-browser", "index.js"]),
+        ]
+    );
+    // the resolutions are counted by this resolver.
+    assert_eq!(resolver.metrics().snapshot().resolutions, 3);
+
+    let results = resolver.resolve_matrix(&case_path, "msw/node", &condition_sets[1..]);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn dual_package_hazard_test() {
+    let case_path = p(vec!["compat-corpus"]);
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_set(vec!["node"]),
+        ..Default::default()
+    });
+    let hazard = resolver
+        .dual_package_hazard(&case_path, "uuid")
+        .unwrap()
+        .unwrap();
+    let uuid_path = p(vec!["compat-corpus", "node_modules", "uuid"]);
+    assert_eq!(hazard.import, uuid_path.join("wrapper.mjs"));
+    assert_eq!(hazard.require, uuid_path.join("dist").join("index.js"));
+    let package = PackageInstance {
+        version: Some(String::from("9.0.1")),
+        path: uuid_path,
+    };
+    assert_eq!(hazard.import_package, Some(package.clone()));
+    assert_eq!(hazard.require_package, Some(package));
+
+    // the same file for both.
+    assert!(resolver
+        .dual_package_hazard(&case_path, "@babel/runtime/helpers/typeof")
+        .unwrap()
+        .is_none());
+    assert!(resolver
+        .dual_package_hazard(&case_path, "firebase")
+        .is_err());
+}
+
+#[test]
+fn condition_sensitivity_test() {
+    let case_path = p(vec!["compat-corpus"]);
+    let resolver = Resolver::new(Options::default());
+    let uuid = |path: &str| {
+        Ok(Some(
+            p(vec!["compat-corpus", "node_modules", "uuid"]).join(path),
+        ))
+    };
+    let sensitivity = resolver.condition_sensitivity(
+        &case_path,
+        "uuid",
+        &vec_to_set(vec!["node", "import"]),
+        &["import", "module", "browser", "development"],
+    );
+    assert_eq!(
+        sensitivity,
+        vec![
+            ConditionSensitivity {
+                condition: String::from("import"),
+                added: false,
+                base: uuid("wrapper.mjs"),
+                toggled: uuid("dist/esm-browser/index.js"),
+            },
+            ConditionSensitivity {
+                condition: String::from("module"),
+                added: true,
+                base: uuid("wrapper.mjs"),
+                toggled: uuid("dist/esm-node/index.js"),
+            },
+        ]
+    );
+
+    // the errors are the results as well.
+    let sensitivity = resolver.condition_sensitivity(
+        &case_path,
+        "msw/node",
+        &vec_to_set(vec!["node", "require"]),
+        &["node", "browser"],
+    );
+    assert_eq!(sensitivity.len(), 1);
+    assert_eq!(sensitivity[0].condition, "browser");
+    assert!(sensitivity[0].base.is_ok());
+    assert!(sensitivity[0].toggled.is_err());
+}
+
+#[test]
+fn resolve_bin_test() {
+    let case_path = p(vec!["bin", "src"]);
+    let resolver = Resolver::new(Options::default());
+    assert_eq!(
+        resolver
+            .resolve_bin(&case_path, "single-bin", "single-bin")
+            .unwrap(),
+        p(vec!["bin", "node_modules", "single-bin", "cli.js"])
+    );
+    assert_eq!(
+        resolver
+            .resolve_bin(&case_path, "@scope/multi", "multi")
+            .unwrap(),
+        p(vec![
+            "bin",
+            "node_modules",
+            "@scope",
+            "multi",
+            "bin",
+            "multi.js"
+        ])
+    );
+    // the package can be a directory.
+    assert_eq!(
+        resolver
+            .resolve_bin(&case_path, "../node_modules/@scope/multi", "multi-dev")
+            .unwrap(),
+        p(vec![
+            "bin",
+            "node_modules",
+            "@scope",
+            "multi",
+            "bin",
+            "dev.js"
+        ])
+    );
+    for (package, bin_name) in [
+        ("single-bin", "cli"),
+        ("@scope/multi", "multi-missing"),
+        ("@scope/multi", "unknown"),
+        ("unknown", "unknown"),
+    ] {
+        assert!(matches!(
+            resolver.resolve_bin(&case_path, package, bin_name),
+            Err(Error::UnexpectedValue(_))
+        ));
+    }
+}
+
+#[test]
+fn description_parse_test() {
+    let content = r##"{
+        "name": "pkg",
+        "version": "1.0.0",
+        "exports": {
+            ".": { "node": { "import": "./a.mjs", "require": "./a.cjs" }, "default": "./a.js" },
+            "./feature/*": ["./feature/*.js", "./fallback.js"],
+            "./private/*": null
+        },
+        "imports": { "#dep": { "browser": "./dep.browser.js", "default": "dep" } }
+    }"##;
+    let path = Path::new("/pkg/package.json");
+    let json = PkgJSON::parse(content, path, None).unwrap();
+    assert_eq!(json.name(), Some("pkg"));
+    assert!(!json.is_trimmed());
+    let trimmed = PkgJSON::parse(content, path, Some(&["exports"])).unwrap();
+    assert_eq!(trimmed.name(), Some("pkg"));
+    assert!(trimmed.get_filed(&vec![String::from("version")]).is_none());
+
+    let exports = json.get_filed(&vec![String::from("exports")]).unwrap();
+    let entry = |key, conditions: Vec<&'static str>, target| MappingEntry {
+        key,
+        conditions,
+        target,
+    };
+    assert_eq!(
+        ExportsField::entries(exports).unwrap(),
+        vec![
+            entry(".", vec!["node", "import"], Some("./a.mjs")),
+            entry(".", vec!["node", "require"], Some("./a.cjs")),
+            entry(".", vec!["default"], Some("./a.js")),
+            entry("./feature/*", vec![], Some("./feature/*.js")),
+            entry("./feature/*", vec![], Some("./fallback.js")),
+            entry("./private/*", vec![], None),
+        ]
+    );
+    // the same semantics as resolving.
+    assert_eq!(
+        ExportsField::field_process(exports, "./feature/x", &vec_to_set(vec![])).unwrap(),
+        vec!["./feature/x.js", "./fallback.js"]
+    );
+    let imports = json.get_filed(&vec![String::from("imports")]).unwrap();
+    assert_eq!(
+        ImportsField::entries(imports).unwrap(),
+        vec![
+            entry("#dep", vec!["browser"], Some("./dep.browser.js")),
+            entry("#dep", vec!["default"], Some("dep")),
+        ]
+    );
+
+    // sugar
+    let sugar = serde_json::json!({ "import": "./a.mjs", "default": "./a.js" });
+    assert_eq!(
+        ExportsField::entries(&sugar).unwrap(),
+        vec![
+            entry(".", vec!["import"], Some("./a.mjs")),
+            entry(".", vec!["default"], Some("./a.js")),
+        ]
+    );
+    // invalid
+    let mixed = serde_json::json!({ ".": "./a.js", "import": "./a.mjs" });
+    assert!(matches!(
+        ExportsField::entries(&mixed),
+        Err(Error::UnexpectedValue(_))
+    ));
+    let default_first = serde_json::json!({ "default": "./a.js", "import": "./a.mjs" });
+    assert!(matches!(
+        ExportsField::entries(&default_first),
+        Err(Error::UnexpectedValue(_))
+    ));
+    assert!(matches!(
+        PkgJSON::parse("{", path, None),
+        Err(Error::UnexpectedJson(_))
+    ));
+}
+
+#[test]
+fn clone_with_conditions_test() {
+    let case_path = p(vec!["compat-corpus"]);
+    let cache = Arc::new(Cache::default());
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_set(vec!["node", "import"]),
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    let uuid = |path: Vec<&str>| p([vec!["compat-corpus", "node_modules", "uuid"], path].concat());
+    should_equal(&resolver, &case_path, "uuid", uuid(vec!["wrapper.mjs"]));
+
+    let require = resolver.clone_with_conditions(&["require"], &["import"]);
+    assert_eq!(
+        require.options.condition_names,
+        vec_to_set(vec!["node", "require"])
+    );
+    assert!(Arc::ptr_eq(
+        require.options.external_cache.as_ref().unwrap(),
+        &cache
+    ));
+    should_equal(&require, &case_path, "uuid", uuid(vec!["dist", "index.js"]));
+    let browser = require.clone_with_conditions(&["browser", "import"], &["node", "require"]);
+    should_equal(
+        &browser,
+        &case_path,
+        "uuid",
+        uuid(vec!["dist", "esm-browser", "index.js"]),
+    );
+    // the original resolver is unchanged.
+    should_equal(&resolver, &case_path, "uuid", uuid(vec!["wrapper.mjs"]));
+    assert_eq!(resolver.metrics().snapshot().resolutions, 2);
+    assert_eq!(require.metrics().snapshot().resolutions, 1);
+}
+
+#[test]
+fn root_package_test() {
+    let case_path = p(vec!["root-package", "src"]);
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        ..Default::default()
+    });
+    // `src/package.json` shadows the fields of the application.
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib/server",
+        p(vec!["root-package", "src", "lib", "server.js"]),
+    );
+    should_failed(&resolver, &case_path, "fs");
+
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        root_package: Some(p(vec!["root-package"])),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib/server",
+        p(vec!["root-package", "src", "lib", "client.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path.join("lib"),
+        "./server.js",
+        p(vec!["root-package", "src", "lib", "client.js"]),
+    );
+    should_ignored(&resolver, &case_path, "fs");
+    should_equal(
+        &resolver,
+        &case_path,
+        "#settings",
+        p(vec!["root-package", "src", "config.js"]),
+    );
+    // the same as without `root_package` in the application package.
+    should_equal(
+        &resolver,
+        &p(vec!["root-package"]),
+        "./src/lib/server",
+        p(vec!["root-package", "src", "lib", "client.js"]),
+    );
+    // the packages in `node_modules` are not affected.
+    should_failed(
+        &resolver,
+        &p(vec!["root-package", "node_modules", "dep"]),
+        "fs",
+    );
+
+    // the `browser` field is still gated by `browser_field`.
+    let resolver = Resolver::new(Options {
+        root_package: Some(p(vec!["root-package"])),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib/server",
+        p(vec!["root-package", "src", "lib", "server.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "#settings",
+        p(vec!["root-package", "src", "config.js"]),
+    );
+}
+
+#[test]
+fn protocol_test() {
+    #[derive(Debug)]
+    struct Virtual;
+
+    impl ProtocolHandler for Virtual {
+        fn resolve(&self, path: &Path, specifier: &str) -> Result<ProtocolResolution, Error> {
+            match specifier {
+                "entry" => Ok(ProtocolResolution::Resolved(path.join("virtual-entry.js"))),
+                "empty" => Ok(ProtocolResolution::Ignored),
+                "error" => Err(Error::UnexpectedValue(String::from("virtual error"))),
+                _ => Ok(ProtocolResolution::Delegate(format!("./{specifier}"))),
+            }
+        }
+    }
+
+    let fixture_path = p(vec![]);
+    let resolver = Resolver::new(Options {
+        protocols: [
+            vec![(
+                String::from("virtual:"),
+                Arc::new(Virtual) as Arc<dyn ProtocolHandler>,
+            )],
+            Options::default().protocols,
+        ]
+        .concat(),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &fixture_path,
+        "virtual:entry",
+        p(vec!["virtual-entry.js"]),
+    );
+    should_ignored(&resolver, &fixture_path, "virtual:empty");
+    should_unexpected_value_error(
+        &resolver,
+        &fixture_path,
+        "virtual:error",
+        "virtual error".to_string(),
+    );
+    // the delegated request is resolved in the same directory.
+    should_equal(&resolver, &fixture_path, "virtual:a", p(vec!["a.js"]));
+    should_equal(&resolver, &fixture_path, "VIRTUAL:a?q", p(vec!["a.js?q"]));
+
+    // built-in
+    should_ignored(&resolver, &fixture_path, "node:fs");
+    let url = format!(
+        "file://{}{}",
+        if cfg!(windows) { "/" } else { "" },
+        fixture_path
+            .join("a")
+            .display()
+            .to_string()
+            .replace('\\', "/")
+    );
+    should_equal(&resolver, &fixture_path, &url, p(vec!["a.js"]));
+    // unknown protocols are resolved as usual.
+    should_failed(&resolver, &fixture_path, "jsr:@std/path");
+
+    let resolver = Resolver::new(Options {
+        protocols: vec![],
+        ..Default::default()
+    });
+    should_failed(&resolver, &fixture_path, "node:fs");
+    should_failed(&resolver, &fixture_path, "virtual:entry");
+}
+
+#[test]
+fn package_store_protocol_test() {
+    let case_path = p(vec!["package-store", "src"]);
+    let resolver = Resolver::new(Options {
+        protocols: vec![
+            (
+                String::from("npm:"),
+                Arc::new(NpmProtocol::new(
+                    p(vec!["package-store", "npm"]),
+                    Arc::new(OsFileSystem),
+                )),
+            ),
+            (
+                String::from("jsr:"),
+                Arc::new(JsrProtocol::new(
+                    p(vec!["package-store", "jsr"]),
+                    Arc::new(OsFileSystem),
+                )),
+            ),
+        ],
+        ..Default::default()
+    });
+    let npm = |entry: &str, path: Vec<&str>| {
+        p([vec!["package-store", "npm", entry, "node_modules"], path].concat())
+    };
+    should_equal(
+        &resolver,
+        &case_path,
+        "npm:pkg",
+        npm("pkg@2.0.0", vec!["pkg", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "npm:pkg@^1",
+        npm("pkg@1.2.0", vec!["pkg", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "npm:pkg@^1/feature",
+        npm("pkg@1.2.0", vec!["pkg", "feature.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "npm:/pkg@~1.0",
+        npm("pkg@1.0.0", vec!["pkg", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "npm:@scope/lib@^0.3",
+        npm(
+            "@scope+lib@0.3.1_react@18.2.0",
+            vec!["@scope", "lib", "index.js"],
+        ),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "npm:pkg@3",
+        "No version of pkg in".to_string(),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "npm:pkg@^a",
+        "Invalid version range \"^a\"".to_string(),
+    );
+
+    let jsr = |path: Vec<&str>| p([vec!["package-store", "jsr", "@std", "path"], path].concat());
+    // the prereleases are not selected by the range.
+    should_equal(
+        &resolver,
+        &case_path,
+        "jsr:@std/path",
+        jsr(vec!["1.0.0", "mod.ts"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "jsr:@std/path@1/join",
+        jsr(vec!["1.0.0", "join.ts"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "jsr:@std/path@^0.220",
+        jsr(vec!["0.220.0", "mod.ts"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "jsr:@std/path@1.1.0-rc.1",
+        jsr(vec!["1.1.0-rc.1", "mod.ts"]),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "jsr:@std/path/missing",
+        "\"./missing\" is not exported by jsr:@std/path@1.0.0".to_string(),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "jsr:std",
+        "The name of a JSR package should be in the form of \"@scope/name\"".to_string(),
+    );
+}
+
+#[test]
+fn provenance_test() {
+    fn provenance_of(resolver: &Resolver, path: &Path, request: &str) -> Provenance {
+        match resolver.resolve(path, request) {
+            Ok(ResolveResult::Resource(resource)) => resource.provenance,
+            other => panic!("{other:?}"),
+        }
+    }
+    let provenance = |stage: Option<Stage>, key: Option<&str>, lookup: Lookup| Provenance {
+        stage,
+        key: key.map(String::from),
+        lookup,
+    };
+    let js = || Lookup::Extension(String::from(".js"));
+
+    #[derive(Debug)]
+    struct Virtual;
+
+    impl ProtocolHandler for Virtual {
+        fn resolve(&self, path: &Path, specifier: &str) -> Result<ProtocolResolution, Error> {
+            match specifier {
+                "entry" => Ok(ProtocolResolution::Resolved(path.join("virtual-entry.js"))),
+                _ => Ok(ProtocolResolution::Delegate(format!("./{specifier}"))),
+            }
+        }
+    }
+
+    let fixture_path = p(vec![]);
+    let resolver = Resolver::new(Options {
+        alias: vec![(
+            String::from("./b$"),
+            vec![AliasMap::Target(String::from("./a"))],
+        )],
+        fallback: vec![(
+            String::from("./missing"),
+            vec![AliasMap::Target(String::from("./a.js"))],
+        )],
+        protocols: vec![(String::from("virtual:"), Arc::new(Virtual))],
+        ..Default::default()
+    });
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "./a.js"),
+        Provenance::default()
+    );
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "./a"),
+        provenance(None, None, js())
+    );
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "./b"),
+        provenance(Some(Stage::Alias), Some("./b$"), js())
+    );
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "./missing"),
+        provenance(Some(Stage::Fallback), Some("./missing"), Lookup::Exact)
+    );
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "virtual:entry"),
+        provenance(Some(Stage::Protocol), Some("virtual:"), Lookup::Exact)
+    );
+    // the innermost stage is recorded.
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "virtual:a"),
+        provenance(Some(Stage::Protocol), Some("virtual:"), js())
+    );
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "./main-field"),
+        provenance(Some(Stage::MainField), Some("main"), js())
+    );
+    assert_eq!(
+        provenance_of(&resolver, &fixture_path, "./alias/a/dir/../"),
+        provenance(Some(Stage::MainFile), Some("index"), Lookup::Exact)
+    );
+
+    let exports_path = p(vec!["exports-field"]);
+    assert_eq!(
+        provenance_of(&resolver, &exports_path, "exports-field"),
+        provenance(Some(Stage::ExportsField), Some("."), Lookup::Exact)
+    );
+    assert_eq!(
+        provenance_of(&resolver, &exports_path, "exports-field/dist/main.js"),
+        provenance(Some(Stage::ExportsField), Some("./dist/"), Lookup::Exact)
+    );
+
+    let imports_path = p(vec!["imports-field"]);
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_set(vec!["webpack"]),
+        ..Default::default()
+    });
+    assert_eq!(
+        provenance_of(&resolver, &imports_path, "#imports-field"),
+        provenance(
+            Some(Stage::ImportsField),
+            Some("#imports-field"),
+            Lookup::Exact
+        )
+    );
+    assert_eq!(
+        provenance_of(&resolver, &imports_path, "#ccc/index"),
+        provenance(Some(Stage::ImportsField), Some("#ccc/"), js())
+    );
+}
+
+#[test]
+fn dir_snapshot_file_system_test() {
+    let dir = std::env::temp_dir().join(format!("dir-snapshot-{}", std::process::id()));
+    let pkg_dir = dir.join("node_modules").join("pkg");
+    std::fs::create_dir_all(&pkg_dir).unwrap();
+    std::fs::write(pkg_dir.join("package.json"), r#"{"main": "main.js"}"#).unwrap();
+    std::fs::write(pkg_dir.join("main.js"), "").unwrap();
+
+    let file_system = Arc::new(DirSnapshotFileSystem::new());
+    let resolver = Resolver::new(Options {
+        external_cache: Some(Arc::new(Cache::with_file_system(file_system.clone()))),
+        ..Default::default()
+    });
+    should_equal(&resolver, &dir, "pkg", pkg_dir.join("main.js"));
+    should_equal(&resolver, &dir, "pkg/main", pkg_dir.join("main.js"));
+    should_failed(&resolver, &dir, "pkg/feature");
+    should_failed(&resolver, &dir, "pkg/main.js/a");
+    should_failed(&resolver, &dir, "missing");
+
+    // the snapshots are kept until they are invalidated.
+    let feature = pkg_dir.join("feature.js");
+    std::fs::write(&feature, "").unwrap();
+    resolver.clear_entries();
+    should_failed(&resolver, &dir, "pkg/feature");
+    file_system.invalidate(&feature);
+    resolver.clear_entries();
+    should_equal(&resolver, &dir, "pkg/feature", feature);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn prime_from_lockfile_test() {
+    let instance = |version: Option<&str>, path: PathBuf| PackageInstance {
+        version: version.map(String::from),
+        path,
+    };
+
+    let case_path = p(vec!["lockfile", "npm"]);
+    let resolver = Resolver::new(Options::default());
+    // `missing` is not installed.
+    let learned = resolver
+        .prime_from_lockfile(case_path.join("package-lock.json"))
+        .unwrap();
+    assert_eq!(learned, 3);
+    let report = resolver.duplicate_report();
+    assert_eq!(report.len(), 1);
+    assert_eq!(report[0].name, "a");
+    assert_eq!(
+        report[0].instances,
+        vec![
+            instance(Some("1.0.0"), case_path.join("node_modules/a")),
+            instance(
+                Some("2.0.0"),
+                case_path.join("node_modules/b/node_modules/a")
+            ),
+        ]
+    );
+    // the instances are not recorded twice once they are resolved.
+    should_equal(
+        &resolver,
+        &case_path,
+        "a",
+        case_path.join("node_modules/a/index.js"),
+    );
+    assert_eq!(resolver.duplicate_report(), report);
+
+    let case_path = p(vec!["lockfile", "pnpm"]);
+    let resolver = Resolver::new(Options::default());
+    let learned = resolver
+        .prime_from_lockfile(case_path.join("pnpm-lock.yaml"))
+        .unwrap();
+    assert_eq!(learned, 3);
+    let store = case_path.join("node_modules").join(".pnpm");
+    let report = resolver.duplicate_report();
+    assert_eq!(report.len(), 1);
+    assert_eq!(
+        report[0].instances,
+        vec![
+            instance(Some("1.0.0"), store.join("a@1.0.0/node_modules/a")),
+            instance(Some("2.0.0"), store.join("a@2.0.0_b@1.0.0/node_modules/a")),
+        ]
+    );
+
+    // only the hoisted copies are known from yarn.lock.
+    let case_path = p(vec!["lockfile", "yarn"]);
+    let resolver = Resolver::new(Options::default());
+    let learned = resolver
+        .prime_from_lockfile(case_path.join("yarn.lock"))
+        .unwrap();
+    assert_eq!(learned, 2);
+    assert!(resolver.duplicate_report().is_empty());
+
+    assert!(matches!(
+        resolver.prime_from_lockfile(p(vec!["lockfile", "missing.lock"])),
+        Err(Error::Io(_))
+    ));
+    assert!(matches!(
+        resolver.prime_from_lockfile(p(vec!["lockfile", "npm", "node_modules", "a", "package.json"])),
+        Err(Error::UnexpectedValue(message)) if message.contains("is not a lockfile")
+    ));
+}
+
+#[test]
+fn stop_at_test() {
+    let issuer = p(vec!["dependencies", "a", "b"]);
+    let resolver = Resolver::new(Options {
+        stop_at: Some(p(vec!["dependencies", "a"])),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &issuer,
+        "some-module",
+        p(vec![
+            "dependencies",
+            "a",
+            "b",
+            "node_modules",
+            "some-module",
+            "index.js",
+        ]),
+    );
+    // the `node_modules` in the boundary is still looked up.
+    should_equal(
+        &resolver,
+        &issuer,
+        "module/file",
+        p(vec![
+            "dependencies",
+            "a",
+            "node_modules",
+            "module",
+            "file.js",
+        ]),
+    );
+    let error = resolver.resolve(&issuer, "other-module/file").unwrap_err();
+    let Error::StoppedAt { request, boundary } = &error else {
+        panic!("{error:?}")
+    };
+    assert_eq!(request, "other-module/file");
+    assert_eq!(boundary.as_ref(), p(vec!["dependencies", "a"]));
+    assert!(error.to_string().contains(&format!(
+        "the lookup of modules stopped at '{}'",
+        p(vec!["dependencies", "a"]).display()
+    )));
+    let rendered = resolver.render_error(&issuer, "other-module/file", &error);
+    assert!(rendered.contains(&format!(
+        "the modules above '{}' are not looked up",
+        boundary.display()
+    )));
+    // the relative requests are not bounded.
+    should_equal(
+        &resolver,
+        &issuer,
+        "../../node_modules/other-module/file",
+        p(vec![
+            "dependencies",
+            "node_modules",
+            "other-module",
+            "file.js",
+        ]),
+    );
+    // the requests issued outside of the boundary are not bounded.
+    should_equal(
+        &resolver,
+        &p(vec!["dependencies"]),
+        "other-module/file",
+        p(vec![
+            "dependencies",
+            "node_modules",
+            "other-module",
+            "file.js",
+        ]),
+    );
+    assert_eq!(
+        resolver.modules_paths(&issuer, "other-module").unwrap(),
+        vec![
+            ModulesPath {
+                path: p(vec!["dependencies", "a", "b", "node_modules"]),
+                exists: true,
+            },
+            ModulesPath {
+                path: p(vec!["dependencies", "a", "node_modules"]),
+                exists: true,
+            },
+        ]
+    );
+
+    let resolver = Resolver::new(Options {
+        stop_at: Some(p(vec!["dependencies"])),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &issuer,
+        "other-module/file",
+        p(vec![
+            "dependencies",
+            "node_modules",
+            "other-module",
+            "file.js",
+        ]),
+    );
+}
+
+#[test]
+fn use_node_path_test() {
+    let global = p(vec!["node-path", "global"]);
+    let project = p(vec!["node-path", "project"]);
+    // `NODE_PATH` is only read by the resolvers with `use_node_path`.
+    std::env::set_var("NODE_PATH", &global);
+    let resolver = Resolver::new(Options {
+        use_node_path: true,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &project,
+        "global-pkg",
+        global.join("global-pkg").join("index.js"),
+    );
+    // the global folders are looked up after the ancestors.
+    should_equal(
+        &resolver,
+        &project,
+        "local-pkg",
+        project.join("node_modules/local-pkg/index.js"),
+    );
+    assert_eq!(
+        resolver
+            .modules_paths(&project, "global-pkg")
+            .unwrap()
+            .into_iter()
+            .find(|item| item.path == global),
+        Some(ModulesPath {
+            path: global.clone(),
+            exists: true,
+        })
+    );
+
+    let resolver = Resolver::new(Options::default());
+    should_failed(&resolver, &project, "global-pkg");
+}
+
+#[test]
+fn resolver_factory_test() {
+    #[derive(Debug, Default)]
+    struct Created(std::sync::Mutex<Vec<String>>);
+
+    impl ResolverCreated for Created {
+        fn resolver_created(&self, category: &str, _: &OptionsDelta, resolver: &Resolver) {
+            assert_eq!(resolver.metrics().snapshot().resolutions, 0);
+            self.0.lock().unwrap().push(category.to_string());
+        }
+    }
+
+    let case_path = p(vec!["compat-corpus"]);
+    let created = Arc::new(Created::default());
+    let factory = ResolverFactory::new(Options {
+        condition_names: vec_to_set(vec!["node"]),
+        ..Default::default()
+    })
+    .on_created(created.clone());
+    let uuid = |path: Vec<&str>| p([vec!["compat-corpus", "node_modules", "uuid"], path].concat());
+    let esm_delta = OptionsDelta {
+        add_conditions: vec!["import".to_string()],
+        ..Default::default()
+    };
+    let cjs_delta = OptionsDelta {
+        add_conditions: vec!["require".to_string()],
+        ..Default::default()
+    };
+    let esm = factory.get("esm", &esm_delta);
+    should_equal(&esm, &case_path, "uuid", uuid(vec!["wrapper.mjs"]));
+    let cjs = factory.get("commonjs", &cjs_delta);
+    should_equal(&cjs, &case_path, "uuid", uuid(vec!["dist", "index.js"]));
+    assert!(Arc::ptr_eq(
+        cjs.options.external_cache.as_ref().unwrap(),
+        factory.cache()
+    ));
+
+    // the resolvers are created once for each category and delta.
+    assert!(Arc::ptr_eq(&esm, &factory.get("esm", &esm_delta)));
+    let worker = factory.get("worker", &esm_delta);
+    assert!(!Arc::ptr_eq(&esm, &worker));
+    assert_eq!(factory.len(), 3);
+    assert_eq!(
+        *created.0.lock().unwrap(),
+        vec!["esm", "commonjs", "worker"]
+    );
+
+    factory.clear();
+    assert!(factory.is_empty());
+    assert!(!Arc::ptr_eq(&esm, &factory.get("esm", &esm_delta)));
+    assert_eq!(created.0.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn resolver_factory_retention_test() {
+    let case_path = p(vec!["retention-factory"]);
+    let m = |file: &str| p(vec!["retention-factory", "node_modules", "m", file]);
+    let factory = ResolverFactory::new(Options {
+        description_retention: DescriptionRetention::ResolutionFields,
+        ..Default::default()
+    });
+    let esm_delta = OptionsDelta {
+        main_fields: Some(vec!["module".to_string(), "main".to_string()]),
+        ..Default::default()
+    };
+    let cjs = factory.get("commonjs", &OptionsDelta::default());
+    should_equal(&cjs, &case_path, "m", m("main.js"));
+    // the description trimmed for `commonjs` is read again with `module`.
+    let esm = factory.get("esm", &esm_delta);
+    should_equal(&esm, &case_path, "m", m("module.js"));
+    should_equal(&cjs, &case_path, "m", m("main.js"));
+}
+
+#[test]
+fn coalesce_requests_test() {
+    /// Holds the first resolution, so the others arrive while it is in progress.
+    #[derive(Debug, Default)]
+    struct Slow(std::sync::atomic::AtomicBool);
+
+    impl BeforeResolve for Slow {
+        fn before_resolve(&self, _: &mut RawRequest) {
+            if !self.0.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+            }
+        }
+    }
+
+    let case_path = p(vec!["full", "a"]);
+    let resolver = Resolver::new(Options {
+        coalesce_requests: true,
+        before_resolve: Some(Arc::new(Slow::default())),
+        ..Default::default()
+    });
+    let barrier = std::sync::Barrier::new(4);
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                barrier.wait();
+                should_equal(
+                    &resolver,
+                    &case_path,
+                    "package2",
+                    p(vec!["full", "a", "node_modules", "package2", "a.js"]),
+                );
+            });
+        }
+    });
+    let snapshot = resolver.metrics().snapshot();
+    assert_eq!(snapshot.resolutions, 1);
+    assert_eq!(snapshot.coalesced, 3);
+
+    // the failures are shared too, and the finished requests are resolved again.
+    std::thread::scope(|scope| {
+        for _ in 0..2 {
+            scope.spawn(|| should_failed(&resolver, &case_path, "missing"));
+        }
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "package2",
+        p(vec!["full", "a", "node_modules", "package2", "a.js"]),
+    );
+    let snapshot = resolver.metrics().snapshot();
+    assert_eq!(snapshot.resolutions + snapshot.coalesced, 7);
+}
+
+#[test]
+fn cancel_token_test() {
+    /// Cancels the resolution at the first candidate.
+    #[derive(Debug)]
+    struct CancelAtCandidate(CancelToken);
+
+    impl Observer for CancelAtCandidate {
+        fn on_candidate(&self, _path: &Path) {
+            self.0.cancel();
+        }
+    }
+
+    let case_path = p(vec!["full", "a"]);
+    let token = CancelToken::new();
+    let resolver = Resolver::new(Options {
+        observer: Some(Arc::new(CancelAtCandidate(token.clone()))),
+        ..Default::default()
+    });
+    assert!(!token.is_cancelled());
+    assert!(matches!(
+        resolver.resolve_cancellable(&case_path, "./abc", &token),
+        Err(Error::Cancelled)
+    ));
+    assert!(token.is_cancelled());
+    assert!(matches!(
+        resolver
+            .dir_resolver(&case_path)
+            .resolve_cancellable("package2", &token),
+        Err(Error::Cancelled)
+    ));
+
+    // the resolutions without the cancelled token are not affected.
+    should_equal(
+        &resolver,
+        &case_path,
+        "./abc",
+        p(vec!["full", "a", "abc.js"]),
+    );
+    let token = CancelToken::new();
+    assert!(resolver
+        .resolve_cancellable(&case_path, "./abc", &token)
+        .is_ok());
+}
+
+#[test]
+fn timeout_test() {
+    /// Stalls the probes as a hanging network file system.
+    #[derive(Debug)]
+    struct Stall;
+
+    impl Observer for Stall {
+        fn on_candidate(&self, _path: &Path) {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    }
+
+    let case_path = p(vec!["full", "a"]);
+    let resolver = Resolver::new(Options {
+        timeout: Some(std::time::Duration::from_millis(10)),
+        observer: Some(Arc::new(Stall)),
+        ..Default::default()
+    });
+    match resolver.resolve(&case_path, "./missing") {
+        Err(Error::Timeout {
+            elapsed,
+            last_probe,
+        }) => {
+            assert!(elapsed >= std::time::Duration::from_millis(10));
+            assert_eq!(&*last_probe, case_path.join("missing").as_path());
+        }
+        result => panic!("{result:?}"),
+    }
+
+    let resolver = Resolver::new(Options {
+        timeout: Some(std::time::Duration::from_secs(60)),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./abc",
+        p(vec!["full", "a", "abc.js"]),
+    );
+}
+
+#[test]
+fn io_error_class_test() {
+    /// Fails the first stat of each path as a busy network file system,
+    /// and denies the stats of `abc.js`.
+    #[derive(Debug, Default)]
+    struct Flaky(std::sync::Mutex<std::collections::HashSet<PathBuf>>);
+
+    impl FileSystem for Flaky {
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            OsFileSystem.read(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+            OsFileSystem.read_to_string(path)
+        }
+
+        fn metadata(&self, path: &Path) -> std::io::Result<nodejs_resolver::FileMetadata> {
+            if path.ends_with("abc.js") {
+                return Err(std::io::ErrorKind::PermissionDenied.into());
+            }
+            if self.0.lock().unwrap().insert(path.to_path_buf()) {
+                return Err(std::io::ErrorKind::WouldBlock.into());
+            }
+            OsFileSystem.metadata(path)
+        }
+
+        fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+            OsFileSystem.read_link(path)
+        }
+
+        fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+            OsFileSystem.canonicalize(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+            OsFileSystem.read_dir(path)
+        }
+    }
+
+    let case_path = p(vec!["full", "a"]);
+    let resolver = Resolver::new(Options {
+        external_cache: Some(Arc::new(Cache::with_file_system(
+            Arc::new(Flaky::default()),
+        ))),
+        ..Default::default()
+    });
+    // the transient errors are retried.
+    should_equal(
+        &resolver,
+        &case_path,
+        "package2",
+        p(vec!["full", "a", "node_modules", "package2", "a.js"]),
+    );
+    let entry = resolver.entry(case_path.join("index.js"));
+    assert!(entry.is_file());
+    assert_eq!(entry.io_error(), None);
+    assert_eq!(
+        resolver.entry(case_path.join("missing.js")).io_error(),
+        None
+    );
+
+    let entry = resolver.entry(case_path.join("abc.js"));
+    assert!(!entry.exists());
+    assert_eq!(entry.io_error(), Some(IoErrorClass::PermissionDenied));
+}
+
+#[test]
+fn permission_denied_test() {
+    /// Denies the stats of the files named `abc.js`.
+    #[derive(Debug)]
+    struct Denied;
+
+    impl FileSystem for Denied {
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            OsFileSystem.read(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+            OsFileSystem.read_to_string(path)
+        }
+
+        fn metadata(&self, path: &Path) -> std::io::Result<nodejs_resolver::FileMetadata> {
+            if path.ends_with("abc.js") {
+                return Err(std::io::ErrorKind::PermissionDenied.into());
+            }
+            OsFileSystem.metadata(path)
+        }
+
+        fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+            OsFileSystem.read_link(path)
+        }
+
+        fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+            OsFileSystem.canonicalize(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+            OsFileSystem.read_dir(path)
+        }
+    }
+
+    let case_path = p(vec!["full", "a"]);
+    let denied = case_path.join("abc.js");
+    let resolver = Resolver::new(Options {
+        external_cache: Some(Arc::new(Cache::with_file_system(Arc::new(Denied)))),
+        ..Default::default()
+    });
+    for request in ["./abc", "./abc.js"] {
+        match resolver.resolve(&case_path, request) {
+            Err(Error::PermissionDenied(path)) => assert_eq!(&*path, denied.as_path()),
+            result => panic!("{result:?}"),
+        }
+    }
+    should_equal(&resolver, &case_path, "./index", case_path.join("index.js"));
+
+    let resolver = Resolver::new(Options {
+        ignore_permission_denied: true,
+        external_cache: Some(Arc::new(Cache::with_file_system(Arc::new(Denied)))),
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "./abc");
+}
+
+#[test]
+fn read_mapped_without_parse_test() {
+    /// Returns from `read_mapped` without passing the content to `parse`.
+    #[derive(Debug)]
+    struct Unparsed;
+
+    impl FileSystem for Unparsed {
+        fn read(&self, path: &Path) -> std::io::Result<Vec<u8>> {
+            OsFileSystem.read(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> std::io::Result<String> {
+            OsFileSystem.read_to_string(path)
+        }
+
+        fn metadata(&self, path: &Path) -> std::io::Result<nodejs_resolver::FileMetadata> {
+            OsFileSystem.metadata(path)
+        }
+
+        fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+            OsFileSystem.read_link(path)
+        }
+
+        fn canonicalize(&self, path: &Path) -> std::io::Result<PathBuf> {
+            OsFileSystem.canonicalize(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> std::io::Result<Vec<PathBuf>> {
+            OsFileSystem.read_dir(path)
+        }
+
+        fn read_mapped(&self, _: &Path, _: &mut dyn FnMut(&[u8])) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let case_path = p(vec!["simple"]);
+    let resolver = Resolver::new(Options {
+        external_cache: Some(Arc::new(Cache::with_file_system(Arc::new(Unparsed)))),
+        ..Default::default()
+    });
+    // the description file which is not parsed is read as a missing one.
+    match resolver.resolve(&case_path, "./lib/index") {
+        Ok(ResolveResult::Resource(resource)) => {
+            assert_eq!(resource.path, p(vec!["simple", "lib", "index.js"]));
+            assert!(resource.description.is_none());
+        }
+        result => panic!("{result:?}"),
+    }
+}
+
+#[test]
+fn package_root_fields_test() {
+    let root = p(vec!["nested-manifest"]);
+    let esm = root.join("esm");
+    // the nearest description file shadows the fields of the package root.
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        ..Default::default()
+    });
+    should_equal(&resolver, &esm, "#util", esm.join("util.js"));
+    should_equal(&resolver, &esm, "./server.js", esm.join("server.js"));
+
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        package_root_fields: vec!["imports".to_string(), "browser".to_string()],
+        ..Default::default()
+    });
+    should_equal(&resolver, &esm, "#util", root.join("util.js"));
+    should_equal(&resolver, &esm, "./server.js", esm.join("client.js"));
+    should_equal(&resolver, &root, "#util", root.join("util.js"));
+
+    let entry = resolver.entry(esm.join("index.js"));
+    let nearest = entry.pkg_info().unwrap().unwrap();
+    assert_eq!(nearest.dir().as_ref(), esm.as_path());
+    let package_root = entry.package_root_info().unwrap().unwrap();
+    assert_eq!(package_root.dir().as_ref(), root.as_path());
+}
+
+#[test]
+fn nested_main_fields_test() {
+    let root = p(vec!["nested-manifest"]);
+    let lib = root.join("lib");
+    let main_fields = vec!["module".to_string(), "main".to_string()];
+    let resolver = Resolver::new(Options {
+        main_fields: main_fields.clone(),
+        ..Default::default()
+    });
+    should_equal(&resolver, &root, "./lib", lib.join("module.js"));
+
+    let resolver = Resolver::new(Options {
+        main_fields: main_fields.clone(),
+        nested_main_fields: NestedMainFields::Main,
+        ..Default::default()
+    });
+    should_equal(&resolver, &root, "./lib", lib.join("main.js"));
+
+    let resolver = Resolver::new(Options {
+        main_fields,
+        nested_main_fields: NestedMainFields::Ignore,
+        ..Default::default()
+    });
+    should_equal(&resolver, &root, "./lib", lib.join("index.js"));
+    // the description files of the packages are not nested.
+    should_equal(
+        &resolver,
+        &p(vec!["full", "a"]),
+        "package2",
+        p(vec!["full", "a", "node_modules", "package2", "a.js"]),
+    );
+}
+
+#[test]
+#[cfg(feature = "test-util")]
+fn virtual_tree_test() {
+    use nodejs_resolver::test_util::VirtualTree;
+    use serde_json::json;
+
+    let tree = VirtualTree::new("/project")
+        .file("src/index.js", "")
+        .file("package.json", json!({ "imports": { "#a": "a" } }))
+        .dir("node_modules/a")
+        .file("package.json", json!({ "exports": { ".": "./lib/a.js" } }))
+        .file("lib/a.js", "")
+        .dir("node_modules/b")
+        .file("index.js", "");
+    let resolver = tree.resolver(Options::default());
+    let src = tree.path("src");
+    should_equal(&resolver, &src, "#a", tree.path("node_modules/a/lib/a.js"));
+    should_equal(&resolver, &src, "b", tree.path("node_modules/b/index.js"));
+    should_equal(&resolver, &src, "./index", tree.path("src/index.js"));
+    should_failed(&resolver, &src, "c");
+    assert_eq!(
+        tree.file_system()
+            .read_dir(&tree.path("node_modules"))
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+#[cfg(all(feature = "oracle", unix))]
+fn oracle_test() {
+    use nodejs_resolver::oracle;
+
+    // the nightly job checks the other seeds, see `examples/oracle.rs`.
+    let node = Path::new("node");
+    if std::process::Command::new(node).arg("-v").output().is_err() {
+        return;
+    }
+    let workspace = std::env::temp_dir().join("nodejs_resolver_oracle_test");
+    std::fs::create_dir_all(&workspace).unwrap();
+    for seed in 0..16 {
+        assert_eq!(oracle::check(seed, &workspace, node).unwrap(), vec![]);
+    }
+}
+
+#[test]
+fn core_test() {
+    use nodejs_resolver::core::{self, PathKind};
+
+    let request = core::parse_request("./a.js?query#fragment");
+    assert_eq!(request.target(), "./a.js");
+    assert_eq!(request.query(), "?query");
+    assert_eq!(request.fragment(), "#fragment");
+    assert_eq!(request.kind(), PathKind::Relative);
+    assert!(core::parse_request("./dir/").is_directory());
+    assert_eq!(core::path_kind("#a"), PathKind::Internal);
+    assert_eq!(core::path_kind("/a"), PathKind::AbsolutePosix);
+
+    assert_eq!(core::split_module_request("a"), ("a", None));
+    assert_eq!(
+        core::split_module_request("a/b/c"),
+        ("a", Some(String::from("./b/c")))
+    );
+    assert_eq!(
+        core::split_module_request("@scope/a/b"),
+        ("@scope/a", Some(String::from("./b")))
+    );
+    assert_eq!(
+        core::split_module_request("@/a"),
+        ("@", Some(String::from("./a")))
+    );
+
+    let alias = vec![
+        (
+            String::from("a$"),
+            vec![AliasMap::Target(String::from("b"))],
+        ),
+        (String::from("c"), vec![AliasMap::Ignored]),
+        (
+            String::from("d"),
+            vec![AliasMap::Target(String::from("d/lib"))],
+        ),
+    ];
+    let from = |target| {
+        core::match_alias(&alias, target)
+            .map(|matched| matched.from)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(from("a"), vec!["a$"]);
+    assert!(from("a/b").is_empty());
+    assert_eq!(from("c/d"), vec!["c"]);
+    assert!(from("cd").is_empty());
+    let matched = core::match_alias(&alias, "a").next().unwrap();
+    assert_eq!(matched.apply("b"), Some(String::from("b")));
+    // the target which was aliased is not aliased again.
+    let matched = core::match_alias(&alias, "d/lib/e").next().unwrap();
+    assert_eq!(matched.apply("d/lib"), None);
+
+    // the fields are evaluated without the description files.
+    let exports = serde_json::json!({ "./a": { "node": "./a.js" } });
+    let conditions = std::collections::HashSet::from([String::from("node")]);
+    assert_eq!(
+        <core::ExportsField as core::Field>::field_process(&exports, "./a", &conditions).unwrap(),
+        vec![String::from("./a.js")]
+    );
+}
+
+#[test]
+fn evaluate_exports_test() {
+    use nodejs_resolver::{evaluate_exports, evaluate_imports};
+    use serde_json::json;
+
+    let exports = json!({
+        ".": { "import": "./index.mjs", "require": "./index.cjs" },
+        "./feature": ["./feature.js", "./fallback.js"],
+        "./lib/*": { "node": "./lib/*.js", "default": null },
+        "./internal/*": null,
+    });
+    assert_eq!(
+        evaluate_exports(&exports, ".", &["node", "import"]).unwrap(),
+        vec!["./index.mjs"]
+    );
+    assert_eq!(
+        evaluate_exports(&exports, ".", &["require"]).unwrap(),
+        vec!["./index.cjs"]
+    );
+    assert_eq!(
+        evaluate_exports(&exports, "./feature", &[]).unwrap(),
+        vec!["./feature.js", "./fallback.js"]
+    );
+    assert_eq!(
+        evaluate_exports(&exports, "./lib/a", &["node"]).unwrap(),
+        vec!["./lib/a.js"]
+    );
+    assert!(evaluate_exports(&exports, "./lib/a", &["browser"])
+        .unwrap()
+        .is_empty());
+    assert!(evaluate_exports(&exports, "./internal/a", &[])
+        .unwrap()
+        .is_empty());
+    assert!(evaluate_exports(&exports, "./missing", &[])
+        .unwrap()
+        .is_empty());
+    assert!(evaluate_exports(&exports, "feature", &[]).is_err());
+    assert_eq!(
+        evaluate_exports(&json!("./main.js"), ".", &[]).unwrap(),
+        vec!["./main.js"]
+    );
+
+    let imports = json!({
+        "#dep": { "node": "dep-node", "default": "./dep.js" },
+        "#internal/*": "./src/internal/*.js",
+    });
+    assert_eq!(
+        evaluate_imports(&imports, "#dep", &["node"]).unwrap(),
+        vec!["dep-node"]
+    );
+    assert_eq!(
+        evaluate_imports(&imports, "#dep", &[]).unwrap(),
+        vec!["./dep.js"]
+    );
+    assert_eq!(
+        evaluate_imports(&imports, "#internal/a", &[]).unwrap(),
+        vec!["./src/internal/a.js"]
+    );
+    assert!(evaluate_imports(&imports, "dep", &[]).is_err());
+}
+
+#[test]
+fn debug_bundle_test() {
+    use nodejs_resolver::DebugBundle;
+
+    let case_path = p(vec!["full", "a"]);
+    let resolver = Resolver::new(Options {
+        main_fields: vec![String::from("module"), String::from("main")],
+        alias: vec![(
+            String::from("missing"),
+            vec![AliasMap::Target(String::from("./missing"))],
+        )],
+        ..Default::default()
+    });
+    let out = std::env::temp_dir().join("nodejs_resolver_debug_bundle.json");
+
+    let bundle = resolver
+        .dump_debug_bundle(&case_path, "package2", &out)
+        .unwrap();
+    let expected = p(vec!["full", "a", "node_modules", "package2", "a.js"]);
+    assert_eq!(bundle.result, Ok(Some(expected.clone())));
+    let manifest = p(vec![
+        "full",
+        "a",
+        "node_modules",
+        "package2",
+        "package.json",
+    ]);
+    assert!(bundle.files[&manifest].contains("\"main\""));
+    assert!(bundle.files[&expected].is_empty());
+    assert!(bundle.dirs.contains(&case_path));
+    #[cfg(feature = "trace")]
+    assert!(!bundle.trace.is_empty());
+    assert_eq!(DebugBundle::load(&out).unwrap(), bundle);
+    assert_eq!(bundle.options().main_fields, resolver.options.main_fields);
+    assert_eq!(bundle.options().alias, resolver.options.alias);
+
+    let bundle = resolver
+        .dump_debug_bundle(&case_path, "missing", &out)
+        .unwrap();
+    assert!(bundle.result.is_err());
+    assert_eq!(DebugBundle::load(&out).unwrap(), bundle);
+
+    #[cfg(feature = "test-util")]
+    {
+        let bundle = resolver
+            .dump_debug_bundle(&case_path, "package2", &out)
+            .unwrap();
+        match bundle.replay() {
+            Ok(ResolveResult::Resource(resource)) => assert_eq!(resource.path, expected),
+            _ => panic!("the bundle should be replayed"),
+        }
+        assert!(resolver
+            .dump_debug_bundle(&case_path, "missing", &out)
+            .unwrap()
+            .replay()
+            .is_err());
+    }
+    std::fs::remove_file(out).unwrap();
+}
+
+#[test]
+fn resolve_unknown_extensions_test() {
+    let case_path = p(vec!["unknown-extension"]);
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "./icon.svg",
+        p(vec!["unknown-extension", "icon.svg.js"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        resolve_unknown_extensions: true,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./logo.svg",
+        p(vec!["unknown-extension", "logo.svg"]),
+    );
+    should_failed(&resolver, &case_path, "./icon.svg");
+    // the names with dots are treated as the extensions.
+    should_failed(&resolver, &case_path, "./jquery.min");
+    should_equal(
+        &resolver,
+        &case_path,
+        "./jquery.min.js",
+        p(vec!["unknown-extension", "jquery.min.js"]),
+    );
+
+    // the literal path is tried even if the extensions are enforced.
+    let resolver = Resolver::new(Options {
+        resolve_unknown_extensions: true,
+        enforce_extension: EnforceExtension::Enabled,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./logo.svg",
+        p(vec!["unknown-extension", "logo.svg"]),
+    );
+}
+
+#[test]
+fn extension_conflict_policy_test() {
+    let case_path = p(vec!["extension-conflict"]);
+    let explicit = p(vec!["extension-conflict", "a.js"]);
+    let appended = p(vec!["extension-conflict", "a.js.ts"]);
+    let options = Options {
+        extensions: vec![String::from(".ts"), String::from(".js")],
+        ..Default::default()
+    };
+
+    let resolver = Resolver::new(options.clone());
+    should_equal(&resolver, &case_path, "./a.js", explicit.clone());
+    should_equal(&resolver, &case_path, "./a", explicit.clone());
+
+    let resolver = Resolver::new(Options {
+        extension_conflict_policy: ExtensionConflictPolicy::PreferAppended,
+        ..options.clone()
+    });
+    should_equal(&resolver, &case_path, "./a.js", appended.clone());
+    should_equal(&resolver, &case_path, "./a", explicit.clone());
+    should_equal(&resolver, &case_path, "./a.js.ts", appended.clone());
+
+    // the request itself is never tried.
+    let resolver = Resolver::new(Options {
+        enforce_extension: EnforceExtension::Enabled,
+        ..options
+    });
+    should_equal(&resolver, &case_path, "./a.js", appended);
+    should_equal(&resolver, &case_path, "./a", explicit);
+}
+
+#[test]
+fn visited_test() {
+    #[derive(Debug, Default)]
+    struct Candidates(std::sync::atomic::AtomicUsize);
+
+    impl Observer for Candidates {
+        fn on_candidate(&self, _path: &Path) {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    // Every level falls back to the next one twice, such as the aliases
+    // merged from several configs, it probed `./not-exist` 2^24 times.
+    let levels = 24;
+    let mut alias: Vec<_> = (0..levels)
+        .map(|level| {
+            let next = AliasMap::Target(format!("level-{}", level + 1));
+            (format!("level-{level}$"), vec![next.clone(), next])
+        })
+        .collect();
+    alias.push((
+        format!("level-{levels}$"),
+        vec![AliasMap::Target("./not-exist".to_string())],
+    ));
+    alias.push((
+        "entry$".to_string(),
+        vec![
+            AliasMap::Target("level-0".to_string()),
+            AliasMap::Target("./abc".to_string()),
+        ],
+    ));
+    let candidates = Arc::new(Candidates::default());
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        alias,
+        observer: Some(candidates.clone()),
+        ..Default::default()
+    });
+    let fixture = p(vec!["full", "a"]);
+    should_failed(&resolver, &fixture, "level-0");
+    should_equal(&resolver, &fixture, "entry", p(vec!["full", "a", "abc.js"]));
+    // each level is looked up in `modules` once.
+    assert!(candidates.0.load(std::sync::atomic::Ordering::Relaxed) < 1000);
+}
+
+#[test]
+fn resolve_request_test() {
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        ..Default::default()
+    });
+    let fixture = p(vec![]);
+    let resolve = |request: &StructuredRequest| match resolver.resolve_request(&fixture, request) {
+        Ok(ResolveResult::Resource(resource)) => resource,
+        result => panic!("{result:?}"),
+    };
+    // the string API finds the file with `#` in the name first.
+    should_equal(
+        &resolver,
+        &fixture,
+        "./no#fragment/#/#",
+        p(vec!["no#fragment", "#", "#.js"]),
+    );
+    let resource = resolve(&StructuredRequest::new("./no#fragment/#/#"));
+    assert_eq!(resource.path, p(vec!["no#fragment", "#", "#.js"]));
+    assert_eq!(resource.fragment, None);
+    let resource = resolve(&StructuredRequest {
+        fragment: "#fragment/#/#".to_string(),
+        ..StructuredRequest::new("./no")
+    });
+    assert_eq!(resource.path, p(vec!["no.js"]));
+    assert_eq!(resource.fragment.as_deref(), Some("#fragment/#/#"));
+
+    let request = StructuredRequest {
+        specifier: "./full/a/abc".to_string(),
+        query: "?raw".to_string(),
+        fragment: "#top".to_string(),
+    };
+    assert_eq!(request.to_string(), "./full/a/abc?raw#top");
+    let resource = resolve(&request);
+    assert_eq!(resource.path, p(vec!["full", "a", "abc.js"]));
+    assert_eq!(resource.query.as_deref(), Some("?raw"));
+    assert_eq!(resource.fragment.as_deref(), Some("#top"));
+}
+
+#[test]
+fn loader_syntax_test() {
+    let fixture = p(vec!["full", "a"]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        ..Default::default()
+    });
+    should_failed(&resolver, &fixture, "style-loader!./abc");
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        loader_syntax: LoaderSyntax::Strip,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &fixture,
+        "-!style-loader!css-loader?modules!./abc",
+        p(vec!["full", "a", "abc.js"]),
+    );
+    let resource = match resolver.resolve(&fixture, "!!raw-loader!./abc?raw#top") {
+        Ok(ResolveResult::Resource(resource)) => resource,
+        result => panic!("{result:?}"),
+    };
+    assert_eq!(resource.path, p(vec!["full", "a", "abc.js"]));
+    assert_eq!(resource.query.as_deref(), Some("?raw"));
+    assert_eq!(resource.fragment.as_deref(), Some("#top"));
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        loader_syntax: LoaderSyntax::Error,
+        ..Default::default()
+    });
+    should_equal(&resolver, &fixture, "./abc", p(vec!["full", "a", "abc.js"]));
+    should_unexpected_value_error(
+        &resolver,
+        &fixture,
+        "style-loader!./abc",
+        "the inline loaders are not supported".to_string(),
+    );
+}
+
+#[test]
+fn resolve_with_loaders_test() {
+    let fixture = p(vec![]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        loader_syntax: LoaderSyntax::Resolve,
+        ..Default::default()
+    });
+    let path = |result: &ResolveResult<Resource>| match result {
+        ResolveResult::Resource(resource) => resource.path.clone(),
+        ResolveResult::Ignored | ResolveResult::OptionalMissing => panic!("{result:?}"),
+    };
+    let resolution = resolver
+        .resolve_with_loaders(&fixture, "-!m1/a?modules!m2/b!./a?raw")
+        .unwrap();
+    assert_eq!(resolution.prefix, LoaderPrefix::NoPreNormal);
+    assert_eq!(
+        resolution.loaders.iter().map(path).collect::<Vec<_>>(),
+        vec![
+            p(vec!["node_modules", "m1", "a.js"]),
+            p(vec!["node_modules", "m2", "b.js"]),
+        ]
+    );
+    let ResolveResult::Resource(loader) = &resolution.loaders[0] else {
+        unreachable!()
+    };
+    assert_eq!(loader.query.as_deref(), Some("?modules"));
+    assert_eq!(path(&resolution.resource), p(vec!["a.js"]));
+    should_equal(&resolver, &fixture, "m1/a!./a", p(vec!["a.js"]));
+    assert!(matches!(
+        resolver.resolve_with_loaders(&fixture, "m1/not-exist!./a"),
+        Err(Error::NotFound { .. })
+    ));
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        loader_syntax: LoaderSyntax::Strip,
+        ..Default::default()
+    });
+    let resolution = resolver.resolve_with_loaders(&fixture, "m1/a!./a").unwrap();
+    assert_eq!(resolution.prefix, LoaderPrefix::None);
+    assert!(resolution.loaders.is_empty());
+    assert_eq!(path(&resolution.resource), p(vec!["a.js"]));
+}
+
+#[test]
+fn package_overrides_test() {
+    let case_path = p(vec!["overrides"]);
+    let store = |version: &str| {
+        p(vec![
+            "overrides",
+            "node_modules",
+            ".store",
+            &format!("foo@{version}"),
+            "node_modules",
+            "foo",
+        ])
+    };
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "foo",
+        p(vec!["overrides", "node_modules", "foo", "index.js"]),
+    );
+    should_failed(&resolver, &case_path, "baz");
+
+    let resolver = Resolver::new(Options {
+        package_overrides: Some(case_path.clone()),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "baz",
+        p(vec!["overrides", "vendor", "baz", "index.js"]),
+    );
+    // the installed copies are scanned, so the result doesn't depend on
+    // what was resolved or learned before.
+    should_equal(
+        &resolver,
+        &case_path,
+        "foo",
+        store("1.1.0").join("index.js"),
+    );
+    resolver
+        .prime_from_lockfile(case_path.join("package-lock.json"))
+        .unwrap();
+    should_equal(
+        &resolver,
+        &case_path,
+        "foo",
+        store("1.1.0").join("index.js"),
+    );
+    // `.store/foo@1.1.0/node_modules/bar` links to the hoisted `bar`.
+    let bar = p(vec!["overrides", "node_modules", "bar"]);
+    should_equal(&resolver, &bar, "foo", store("2.0.0").join("index.js"));
+    should_equal(&resolver, &bar, "foo/sub", store("2.0.0").join("sub.js"));
+}
+
+#[test]
+fn isolated_mode_test() {
+    let store = p(vec!["isolated", "node_modules", ".pnpm"]);
+    let plugin = store.join("plugin@1.0.0/node_modules/plugin");
+    let resolver = Resolver::new(Options::default());
+    // the hoisted copy is another version of the peer.
+    should_equal(
+        &resolver,
+        &plugin,
+        "react",
+        store.join("node_modules/react/index.js"),
+    );
+    should_equal(
+        &resolver,
+        &plugin,
+        "left-pad",
+        store.join("node_modules/left-pad/index.js"),
+    );
+
+    let resolver = Resolver::new(Options {
+        isolated_mode: true,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &plugin,
+        "react",
+        store.join("react@17.0.2/node_modules/react/index.js"),
+    );
+    should_equal(
+        &resolver,
+        &plugin,
+        "lodash",
+        store.join("plugin@1.0.0/node_modules/lodash/index.js"),
+    );
+    should_equal(&resolver, &plugin, "plugin", plugin.join("index.js"));
+    should_failed(&resolver, &plugin, "left-pad");
+}
+
+#[test]
+fn report_optional_peers_test() {
+    let plugin = p(vec!["optional-peers", "node_modules", "plugin"]);
+    let resolver = Resolver::new(Options::default());
+    should_failed(&resolver, &plugin, "vue");
+
+    let resolver = Resolver::new(Options {
+        report_optional_peers: true,
+        ..Default::default()
+    });
+    let is_missing = |request: &str| {
+        matches!(
+            resolver.resolve(&plugin, request),
+            Ok(ResolveResult::OptionalMissing)
+        )
+    };
+    assert!(is_missing("vue"));
+    assert!(is_missing("vue/dist/vue.esm.js"));
+    // the optional peer is installed, but the subpath is missing.
+    should_equal(
+        &resolver,
+        &plugin,
+        "svelte",
+        p(vec!["optional-peers", "node_modules", "svelte", "index.js"]),
+    );
+    assert!(!is_missing("svelte/missing-subpath"));
+    should_failed(&resolver, &plugin, "svelte/missing-subpath");
+    should_failed(&resolver, &plugin, "react");
+    should_failed(&resolver, &plugin, "./vue");
+    should_equal(&resolver, &plugin, "plugin", plugin.join("index.js"));
+}
+
+#[test]
+fn dot_requests_test() {
+    let dir = p(vec!["dirOrFile"]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        ..Default::default()
+    });
+    should_equal(&resolver, &dir, "", p(vec!["dirOrFile.js"]));
+    should_equal(&resolver, &dir, ".", p(vec!["dirOrFile.js"]));
+    should_equal(&resolver, &dir.join("sub"), "..", p(vec!["dirOrFile.js"]));
+    should_equal(&resolver, &dir, "./", p(vec!["dirOrFile", "index.js"]));
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![".js".to_string()],
+        dot_requests: DotRequests::Node,
+        ..Default::default()
+    });
+    should_unexpected_value_error(
+        &resolver,
+        &dir,
+        "",
+        "Invalid request \"\", the specifier must not be empty".to_string(),
+    );
+    should_equal(&resolver, &dir, ".", p(vec!["dirOrFile", "index.js"]));
+    should_equal(
+        &resolver,
+        &dir.join("sub"),
+        "..",
+        p(vec!["dirOrFile", "index.js"]),
+    );
+    should_equal(&resolver, &dir, "./", p(vec!["dirOrFile", "index.js"]));
+
+    let resolver = Resolver::new(Options {
+        algorithm: Algorithm::NodeCjs,
+        ..Default::default()
+    });
+    should_equal(&resolver, &dir, ".", p(vec!["dirOrFile", "index.js"]));
+}
+
+#[test]
+fn suspicious_results_test() {
+    #[derive(Debug, Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+
+    impl Observer for Recorder {
+        fn on_suspicious_result(&self, _path: &Path, request: &str, _result: &Resource, dir: &str) {
+            self.0.lock().unwrap().push(format!("{request} in {dir}"));
+        }
+    }
+
+    let case_path = p(vec!["suspicious"]);
+    let modules = p(vec!["suspicious", "node_modules"]);
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "bad-bin",
+        modules.join("bad-bin/.bin/cli.js"),
+    );
+
+    let recorder = Arc::new(Recorder::default());
+    let resolver = Resolver::new(Options {
+        suspicious_results: SuspiciousResult::recommended(),
+        observer: Some(recorder.clone()),
+        ..Default::default()
+    });
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "bad-bin",
+        format!(
+            "'bad-bin' in '{}' is resolved to '{}' inside '.bin', which is likely a misconfiguration of the package",
+            case_path.display(),
+            modules.join("bad-bin/.bin/cli.js").display()
+        ),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "typed",
+        modules.join("typed/dist-types/index.js"),
+    );
+    assert_eq!(*recorder.0.lock().unwrap(), vec!["typed in dist-types"]);
+
+    // only the directories inside the package of the result are matched.
+    let resolver = Resolver::new(Options {
+        suspicious_results: vec![SuspiciousResult::new("fixtures", SuspiciousAction::Error)],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "typed",
+        modules.join("typed/dist-types/index.js"),
+    );
+}
+
+#[test]
+fn manifest_access_test() {
+    let case_path = p(vec!["manifest-access"]);
+    let modules = p(vec!["manifest-access", "node_modules"]);
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "exported/package.json",
+        modules.join("exported/package.json"),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "exported/data/a.json",
+        modules.join("exported/data/a.json"),
+    );
+    let not_exported = |resolver: &Resolver, request: &str| {
+        matches!(
+            resolver.resolve(&case_path, request),
+            Err(Error::NotExported { request: not_exported, package })
+                if not_exported == request && *package == *modules.join("hidden")
+        )
+    };
+    assert!(not_exported(&resolver, "hidden/package.json"));
+    assert!(not_exported(&resolver, "hidden/data.json"));
+
+    let resolver = Resolver::new(Options {
+        allow_manifest_access: true,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "hidden/package.json",
+        modules.join("hidden/package.json"),
+    );
+    assert!(not_exported(&resolver, "hidden/data.json"));
+}
+
+#[test]
+fn dedupe_by_realpath_test() {
+    let case_path = p(vec!["dedupe"]);
+    let resource = |resolver: &Resolver, dir: &str| match resolver
+        .resolve(&case_path.join(dir), "pkg")
+        .unwrap()
+    {
+        ResolveResult::Resource(resource) => resource,
+        _ => unreachable!(),
+    };
+    let resolver = Resolver::new(Options {
+        symlinks: false,
+        ..Default::default()
+    });
+    let a = resource(&resolver, "a");
+    let b = resource(&resolver, "b");
+    let (a_pkg, b_pkg) = (a.description.unwrap(), b.description.unwrap());
+    assert!(!Arc::ptr_eq(a_pkg.data().raw(), b_pkg.data().raw()));
+    assert_eq!(a.logical_path, None);
+    assert_eq!(a.real_path, None);
+
+    let resolver = Resolver::new(Options {
+        symlinks: false,
+        dedupe_by_realpath: true,
+        ..Default::default()
+    });
+    let a = resource(&resolver, "a");
+    let b = resource(&resolver, "b");
+    assert_eq!(
+        a.path,
+        p(vec!["dedupe", "a", "node_modules", "pkg", "index.js"])
+    );
+    let (a_pkg, b_pkg) = (a.description.unwrap(), b.description.unwrap());
+    assert_eq!(
+        a_pkg.dir().as_ref(),
+        p(vec!["dedupe", "a", "node_modules", "pkg"])
+    );
+    assert_eq!(
+        b_pkg.dir().as_ref(),
+        p(vec!["dedupe", "b", "node_modules", "pkg"])
+    );
+    assert!(Arc::ptr_eq(a_pkg.data().raw(), b_pkg.data().raw()));
+    assert_eq!(a.logical_path, Some(a.path.clone()));
+    assert_eq!(b.logical_path, Some(b.path.clone()));
+    let real = p(vec!["dedupe", "store", "pkg", "index.js"]);
+    assert_eq!(a.real_path, Some(real.clone()));
+    assert_eq!(b.real_path, Some(real));
+}
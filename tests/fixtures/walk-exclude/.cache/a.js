@@ -0,0 +1 @@
+module.exports = '.cache/a.js';
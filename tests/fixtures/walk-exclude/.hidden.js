@@ -0,0 +1 @@
+module.exports = '.hidden.js';
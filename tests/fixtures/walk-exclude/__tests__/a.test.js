@@ -0,0 +1 @@
+module.exports = '__tests__/a.test.js';